@@ -1,14 +1,21 @@
 use automato::automatomsg as am;
-use automato::automatomsg::Msgbuf;
+use automato::automatomsg::PayloadEnum;
+use automato::custom::sketch_codecs;
+use automato::fragment::{FragmentingTransport, NodeMtu};
+use automato::i2c;
+use automato::mesh::SimulatedMesh;
+use automato::ping;
+use automato::secure::{NodeKey, SecureTransport};
+use automato::transport::{
+    collect_replies, RecordingTransport, ReplayTransport, SerialTransport, Transport, REPLY_TIMEOUT,
+};
 use clap::{Arg, Command};
 use simple_error::bail;
+use std::convert::TryFrom;
 use std::error::Error;
-use std::io::Read;
-use std::thread::sleep;
+use std::path::Path;
 use std::time::Duration;
 
-use serialport::SerialPort;
-
 fn main() {
     match err_main() {
         Ok(()) => (),
@@ -57,10 +64,62 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 .short('a')
                 .long("address")
                 .value_name("0-255")
-                .help("lora network address of an automato")
+                .help("lora network address of an automato, or 255 for all of them")
                 .required(true)
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("window")
+                .short('w')
+                .long("window")
+                .value_name("NUMBER")
+                .help("how long to collect replies to a broadcast (ms); by default, as long as a reply can take")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("FILE")
+                .help("answer messages from a capture file instead of the serial port")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("simulate")
+                .long("simulate")
+                .value_name("FILE")
+                .help("talk to simulated automatos from a mesh config file instead of the serial port")
+                .conflicts_with("replay")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("capture")
+                .long("capture")
+                .value_name("FILE")
+                .help("save sent and received messages to a capture file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("mtu")
+                .long("mtu")
+                .value_name("NUMBER")
+                .help("largest message the automato's radio takes, e.g. 60 for an RFM69")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("key")
+                .long("key")
+                .value_name("hex string")
+                .help("pre-shared key of the automato, to seal messages to it")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("host-id")
+                .long("host-id")
+                .value_name("NUMBER")
+                .help("this host's id for --key, different from any other host with the key")
+                .default_value("1")
+                .takes_value(true),
+        )
         .arg(
             Arg::new("json")
                 .long("json")
@@ -90,6 +149,116 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 .about("query pin")
                 .arg(Arg::new("pin").value_name("PIN").takes_value(true)),
         )
+        .subcommand(
+            Command::new("writeanalog")
+                .about("pwm output: duty 0-65535, and optionally frequency (Hz)")
+                .arg(Arg::new("pin").value_name("PIN").takes_value(true))
+                .arg(Arg::new("duty").value_name("0 to 65535").takes_value(true))
+                .arg(Arg::new("frequency").value_name("Hz").takes_value(true)),
+        )
+        .subcommand(
+            Command::new("i2c")
+                .about("write hex data to an i2c device, then read length bytes back")
+                .arg(Arg::new("address").value_name("NUMBER").takes_value(true))
+                .arg(Arg::new("value").value_name("hex string").takes_value(true))
+                .arg(Arg::new("length").value_name("NUMBER").takes_value(true)),
+        )
+        .subcommand(Command::new("i2cscan").about("list the i2c devices that answer"))
+        .subcommand(
+            Command::new("spi")
+                .about("clock hex data out to an spi device, and print what comes back")
+                .arg(Arg::new("cspin").value_name("PIN").takes_value(true))
+                .arg(Arg::new("value").value_name("hex string").takes_value(true))
+                .arg(
+                    Arg::new("mode")
+                        .long("mode")
+                        .value_name("0-3")
+                        .default_value("0")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("clock")
+                        .long("clock")
+                        .value_name("Hz")
+                        .default_value("1000000")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            Command::new("onewire")
+                .about("DS18B20s on a onewire pin")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("scan")
+                        .about("list the rom ids on a pin")
+                        .arg(Arg::new("pin").value_name("PIN").takes_value(true)),
+                )
+                .subcommand(
+                    Command::new("read")
+                        .about("read the temperature of one")
+                        .arg(Arg::new("pin").value_name("PIN").takes_value(true))
+                        .arg(Arg::new("rom").value_name("16 hex digits").takes_value(true)),
+                ),
+        )
+        .subcommand(
+            Command::new("servo")
+                .about("move a servo to an angle, 0-180, or a pulse width with --us")
+                .arg(Arg::new("pin").value_name("PIN").takes_value(true))
+                .arg(Arg::new("position").value_name("NUMBER").takes_value(true))
+                .arg(
+                    Arg::new("us")
+                        .long("us")
+                        .help("position is a pulse width in microseconds")
+                        .takes_value(false),
+                )
+                .arg(
+                    Arg::new("speed")
+                        .long("speed")
+                        .value_name("degrees/s")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            Command::new("pulsecounter")
+                .about("count pulses on a pin, or stop counting with off")
+                .arg(Arg::new("pin").value_name("PIN").takes_value(true))
+                .arg(
+                    Arg::new("edge")
+                        .value_name("rising, falling, change or off")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(
+            Command::new("readpulsecount")
+                .about("read a pulse counter's count and frequency")
+                .arg(Arg::new("pin").value_name("PIN").takes_value(true))
+                .arg(
+                    Arg::new("reset")
+                        .long("reset")
+                        .help("start counting again from 0")
+                        .takes_value(false),
+                ),
+        )
+        .subcommand(
+            Command::new("reboot").about("restart the automato").arg(
+                Arg::new("factory")
+                    .long("factory-reset")
+                    .help("also wipe any settings the sketch has saved")
+                    .takes_value(false),
+            ),
+        )
+        .subcommand(
+            Command::new("ping")
+                .about("check the automato is up, and time the round trip")
+                .arg(
+                    Arg::new("count")
+                        .long("count")
+                        .short('c')
+                        .value_name("NUMBER")
+                        .default_value("1")
+                        .takes_value(true),
+                ),
+        )
         .subcommand(Command::new("readinfo").about("read automato general info"))
         .subcommand(Command::new("readhumidity").about("read automato humidity"))
         .subcommand(Command::new("readtemperature").about("read automato temperature"))
@@ -110,6 +279,16 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 .about("read field info from automato memory map")
                 .arg(Arg::new("index").value_name("NUMBER").takes_value(true)),
         )
+        .subcommand(
+            Command::new("custom")
+                .about("send a sketch specific command, by codec name or subtype number")
+                .arg(Arg::new("command").value_name("NAME or NUMBER").takes_value(true))
+                .arg(
+                    Arg::new("body")
+                        .value_name("json, or hex string for a subtype number")
+                        .takes_value(true),
+                ),
+        )
         .get_matches();
 
     let (port, baud, automatoaddr, timeout) = match (
@@ -127,22 +306,118 @@ fn err_main() -> Result<(), Box<dyn Error>> {
     };
 
     let json = matches.is_present("json");
+    let codecs = sketch_codecs();
+
+    let mut transport: Box<dyn Transport> =
+        match (matches.value_of("replay"), matches.value_of("simulate")) {
+            (Some(replay), _) => Box::new(ReplayTransport::open(Path::new(replay))?),
+            (None, Some(simulate)) => {
+                let mut mesh = SimulatedMesh::open(Path::new(simulate))?;
+                mesh.set_timeout(Duration::from_millis(timeout))?;
+                Box::new(mesh)
+            }
+            (None, None) => {
+                let port = serialport::new(port, baud)
+                    .data_bits(serialport::DataBits::Eight)
+                    .flow_control(serialport::FlowControl::None)
+                    .parity(serialport::Parity::None)
+                    .stop_bits(serialport::StopBits::One)
+                    .timeout(Duration::from_millis(timeout))
+                    .open()?;
+                Box::new(SerialTransport::new(
+                    port,
+                    am::ReadLimits {
+                        deadline: Duration::from_millis(timeout),
+                        ..am::ReadLimits::default()
+                    },
+                ))
+            }
+        };
+
+    // frames as they go over the wire, under any fragmenting and sealing.
+    if let Some(capture) = matches.value_of("capture") {
+        transport = Box::new(RecordingTransport::create(transport, Path::new(capture))?);
+    }
 
-    let mut mb = Msgbuf {
-        buf: [0; am::RH_RF95_MAX_MESSAGE_LEN],
+    // fragmented replies are put back together whether or not there's an mtu.
+    let mtus = match matches.value_of("mtu") {
+        Some(mtu) => vec![NodeMtu {
+            id: automatoaddr,
+            mtu: mtu.parse::<usize>()?,
+        }],
+        None => Vec::new(),
     };
+    transport = Box::new(FragmentingTransport::new(transport, &mtus)?);
 
-    let mut retmsg = mb.clone();
+    if let Some(key) = matches.value_of("key") {
+        let keys = [NodeKey {
+            id: automatoaddr,
+            key: key.to_string(),
+        }];
+        let host = match matches.value_of("host-id") {
+            Some(hoststr) => hoststr.parse::<u8>()?,
+            _ => bail!("arg failure"),
+        };
+        transport = Box::new(SecureTransport::new(transport, &keys, host)?);
+    }
+
+    // a scan is a transfer to each address, rather than one message.
+    if let Some(("i2cscan", _sub_matches)) = matches.subcommand() {
+        let scan = i2c::scan(&mut *transport, automatoaddr)?;
+        println!("{} i2c devices", scan.found.len());
+        for address in scan.found {
+            println!("  {:#04x}", address);
+        }
+        if !scan.missed.is_empty() {
+            println!("{} addresses didn't answer", scan.missed.len());
+        }
+        return Ok(());
+    }
+
+    if let Some(("ping", sub_matches)) = matches.subcommand() {
+        let count = sub_matches
+            .value_of("count")
+            .unwrap_or("1")
+            .parse::<u32>()?;
+        let mut rtts = Vec::new();
+        for _ in 0..count {
+            match ping::ping(&mut *transport, automatoaddr) {
+                Ok((rtt, pong)) => {
+                    println!(
+                        "pong from {}: {:.1} ms, millis {}",
+                        automatoaddr,
+                        rtt.as_secs_f64() * 1000.0,
+                        { pong.millis }
+                    );
+                    rtts.push(rtt);
+                }
+                Err(e) => println!("no pong from {}: {}", automatoaddr, e),
+            }
+        }
+        if count > 1 {
+            println!("{} of {} answered", rtts.len(), count);
+            if let (Some(min), Some(max)) = (rtts.iter().min(), rtts.iter().max()) {
+                let avg = rtts.iter().sum::<Duration>() / rtts.len() as u32;
+                println!(
+                    "rtt min/avg/max {:.1}/{:.1}/{:.1} ms",
+                    min.as_secs_f64() * 1000.0,
+                    avg.as_secs_f64() * 1000.0,
+                    max.as_secs_f64() * 1000.0
+                );
+            }
+        }
+        return Ok(());
+    }
 
     // set up the outgoing message.
-    match matches.subcommand() {
+    let request = match matches.subcommand() {
         Some(("writepin", sub_matches)) => {
             let (pin, val) = match (sub_matches.value_of("pin"), sub_matches.value_of("value")) {
                 (Some(pinstr), Some(valstr)) => (pinstr.parse::<u8>()?, valstr.parse::<u8>()?),
                 _ => bail!("arg failure"),
             };
 
-            unsafe { am::setup_writepin(&mut mb.payload, pin, val) };
+            PayloadEnum::writepin(pin, val)
         }
         Some(("pinmode", sub_matches)) => {
             let (pin, val) = match (sub_matches.value_of("pin"), sub_matches.value_of("value")) {
@@ -150,31 +425,138 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 _ => bail!("arg failure"),
             };
 
-            unsafe { am::setup_pinmode(&mut mb.payload, pin, val) };
+            PayloadEnum::pinmode(pin, val)
         }
         Some(("readpin", sub_matches)) => {
             let pin = match sub_matches.value_of("pin") {
                 Some(pinstr) => pinstr.parse::<u8>()?,
                 _ => bail!("arg failure"),
             };
-            unsafe { am::setup_readpin(&mut mb.payload, pin) };
+            PayloadEnum::readpin(pin)
         }
         Some(("readanalog", sub_matches)) => {
             let pin = match sub_matches.value_of("pin") {
                 Some(pinstr) => pinstr.parse::<u8>()?,
                 _ => bail!("arg failure"),
             };
-            unsafe { am::setup_readanalog(&mut mb.payload, pin) };
+            PayloadEnum::readanalog(pin)
         }
-        Some(("readinfo", _sub_matches)) => {
-            unsafe { am::setup_readinfo(&mut mb.payload) };
+        Some(("writeanalog", sub_matches)) => {
+            let (pin, duty) = match (sub_matches.value_of("pin"), sub_matches.value_of("duty")) {
+                (Some(pinstr), Some(dutystr)) => (pinstr.parse::<u8>()?, dutystr.parse::<u16>()?),
+                _ => bail!("arg failure"),
+            };
+            let frequency = match sub_matches.value_of("frequency") {
+                Some(freqstr) => Some(freqstr.parse::<u32>()?),
+                None => None,
+            };
+            PayloadEnum::writeanalog(pin, duty, frequency)
         }
-        Some(("readhumidity", _sub_matches)) => {
-            unsafe { am::setup_readhumidity(&mut mb.payload) };
+        Some(("i2c", sub_matches)) => {
+            let (addr, val) = match (
+                sub_matches.value_of("address"),
+                sub_matches.value_of("value"),
+            ) {
+                (Some(addrstr), Some(valstr)) => (parse_u8(addrstr)?, hex::decode(valstr)?),
+                _ => bail!("arg failure"),
+            };
+            let len = match sub_matches.value_of("length") {
+                Some(lenstr) => lenstr.parse::<u8>()?,
+                None => 0,
+            };
+            PayloadEnum::i2ctransfer(addr, val.as_slice(), len)?
         }
-        Some(("readtemperature", _sub_matches)) => {
-            unsafe { am::setup_readtemperature(&mut mb.payload) };
+        Some(("spi", sub_matches)) => {
+            let (cspin, val, mode, clock) = match (
+                sub_matches.value_of("cspin"),
+                sub_matches.value_of("value"),
+                sub_matches.value_of("mode"),
+                sub_matches.value_of("clock"),
+            ) {
+                (Some(pinstr), Some(valstr), Some(modestr), Some(clockstr)) => (
+                    pinstr.parse::<u8>()?,
+                    hex::decode(valstr)?,
+                    modestr.parse::<u8>()?,
+                    clockstr.parse::<u32>()?,
+                ),
+                _ => bail!("arg failure"),
+            };
+            PayloadEnum::spitransfer(cspin, mode, clock, val.as_slice())?
         }
+        Some(("onewire", sub_matches)) => match sub_matches.subcommand() {
+            Some(("scan", scan_matches)) => {
+                let pin = match scan_matches.value_of("pin") {
+                    Some(pinstr) => pinstr.parse::<u8>()?,
+                    _ => bail!("arg failure"),
+                };
+                PayloadEnum::onewirescan(pin)
+            }
+            Some(("read", read_matches)) => {
+                let (pin, rom) = match (read_matches.value_of("pin"), read_matches.value_of("rom"))
+                {
+                    (Some(pinstr), Some(romstr)) => (pinstr.parse::<u8>()?, hex::decode(romstr)?),
+                    _ => bail!("arg failure"),
+                };
+                let rom: [u8; 8] = match rom.try_into() {
+                    Ok(rom) => rom,
+                    Err(_) => bail!("rom ids are 8 bytes"),
+                };
+                PayloadEnum::readonewiretemperature(pin, rom)
+            }
+            meh => {
+                bail!("unhandled command! {:?}", meh)
+            }
+        },
+        Some(("servo", sub_matches)) => {
+            let (pin, position) = match (
+                sub_matches.value_of("pin"),
+                sub_matches.value_of("position"),
+            ) {
+                (Some(pinstr), Some(posstr)) => (pinstr.parse::<u8>()?, posstr.parse::<u16>()?),
+                _ => bail!("arg failure"),
+            };
+            let speed = match sub_matches.value_of("speed") {
+                Some(speedstr) => Some(speedstr.parse::<u16>()?),
+                None => None,
+            };
+            if sub_matches.is_present("us") {
+                PayloadEnum::servo(pin, position, speed)
+            } else if position > 180 {
+                bail!("angles are 0 to 180")
+            } else {
+                PayloadEnum::servo_angle(pin, position as u8, speed)
+            }
+        }
+        Some(("pulsecounter", sub_matches)) => {
+            let (pin, edge) = match (sub_matches.value_of("pin"), sub_matches.value_of("edge")) {
+                (Some(pinstr), Some(edgestr)) => (pinstr.parse::<u8>()?, edgestr),
+                _ => bail!("arg failure"),
+            };
+            let edge = match edge {
+                "off" => am::PulseEdge::PulseOff,
+                "rising" => am::PulseEdge::PulseRising,
+                "falling" => am::PulseEdge::PulseFalling,
+                "change" => am::PulseEdge::PulseChange,
+                _ => bail!("edge is rising, falling, change or off"),
+            };
+            PayloadEnum::pulsecounter(pin, edge)
+        }
+        Some(("readpulsecount", sub_matches)) => match sub_matches.value_of("pin") {
+            Some(pinstr) => {
+                PayloadEnum::readpulsecount(pinstr.parse::<u8>()?, sub_matches.is_present("reset"))
+            }
+            None => bail!("arg failure"),
+        },
+        Some(("reboot", sub_matches)) => {
+            if sub_matches.is_present("factory") {
+                PayloadEnum::factoryreset()
+            } else {
+                PayloadEnum::reboot()
+            }
+        }
+        Some(("readinfo", _sub_matches)) => PayloadEnum::readinfo(),
+        Some(("readhumidity", _sub_matches)) => PayloadEnum::readhumidity(),
+        Some(("readtemperature", _sub_matches)) => PayloadEnum::readtemperature(),
         Some(("writemem", sub_matches)) => {
             let (addr, val) = match (
                 sub_matches.value_of("address"),
@@ -184,7 +566,7 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 _ => bail!("arg failure"),
             };
 
-            unsafe { am::setup_writemem(&mut mb.payload, addr, val.as_slice()) };
+            PayloadEnum::writemem(addr, val.as_slice())?
         }
         Some(("readmem", sub_matches)) => {
             let (addr, len) = match (
@@ -194,73 +576,78 @@ fn err_main() -> Result<(), Box<dyn Error>> {
                 (Some(addrstr), Some(lenstr)) => (addrstr.parse::<u16>()?, lenstr.parse::<u8>()?),
                 _ => bail!("arg failure"),
             };
-            unsafe { am::setup_readmem(&mut mb.payload, addr, len) };
+            PayloadEnum::readmem(addr, len)?
         }
         Some(("readfield", sub_matches)) => {
             let index = match sub_matches.value_of("index") {
                 Some(istr) => istr.parse::<u16>()?,
                 _ => bail!("arg failure"),
             };
-            unsafe { am::setup_readfield(&mut mb.payload, index) };
+            PayloadEnum::readfield(index)
+        }
+        Some(("custom", sub_matches)) => {
+            let (command, body) = match (
+                sub_matches.value_of("command"),
+                sub_matches.value_of("body"),
+            ) {
+                (Some(command), Some(body)) => (command, body),
+                _ => bail!("arg failure"),
+            };
+            match command.parse::<u8>() {
+                Ok(subtype) => PayloadEnum::custom(subtype, hex::decode(body)?.as_slice())?,
+                Err(_) => codecs.build(command, &serde_json::from_str(body)?)?,
+            }
         }
         meh => {
             bail!("unhandled command! {:?}", meh)
         }
-    }
-
-    let mut port = serialport::new(port, baud)
-        .data_bits(serialport::DataBits::Eight)
-        .flow_control(serialport::FlowControl::None)
-        .parity(serialport::Parity::None)
-        .stop_bits(serialport::StopBits::One)
-        .timeout(Duration::from_millis(timeout))
-        .open()?;
-
-    let debug_reply = false;
-    unsafe {
-        let wr = am::write_message(&mut *port, &mb, automatoaddr);
+    };
+    let payload = am::Payload::try_from(request)?;
 
-        println!("write_message res: {:?}", wr);
+    let wr = transport.send(&payload, automatoaddr);
 
-        let mut fromid: u8 = 0;
+    println!("write_message res: {:?}", wr);
 
-        if debug_reply {
-            let mut monobuf = [0; 1];
-            let mut count = 0;
-            while port.read_exact(&mut monobuf).is_ok() {
-                // just print the chars we read.  good for debug from Serial.print() on the automato.
-                // print!("{}", monobuf[0] as char);
-                // println!("{} '{}'", monobuf[0] as u8, monobuf[0] as char);
-                // print the index, number, and char
-                println!("{} - {} - {}", count, monobuf[0] as u8, monobuf[0] as char);
-                count = count + 1;
+    if automatoaddr == am::RH_BROADCAST_ADDRESS {
+        let window = match matches.value_of("window") {
+            Some(window) => Duration::from_millis(window.parse::<u64>()?),
+            None => REPLY_TIMEOUT,
+        };
+        let replies = collect_replies(&mut *transport, window)?;
+        println!("{} replies", replies.len());
+        for (fromid, payload) in replies {
+            println!("reply from: {}", fromid);
+            let pe = PayloadEnum::from(payload);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&codecs.to_json(&pe)?)?);
+            } else {
+                println!("{:#}", codecs.display(&pe));
             }
-            // let mut buf = String::new();
-            // let mut monobuf = [0; 1];
-            // port.read_exact(&mut monobuf)?;
-            // buf.push(monobuf[0] as char);
-            // if monobuf[0] as char == '\n' {
-            //     println!("msg: {}", buf);
-            // }
-        } else {
-            match am::read_message(&mut *port, &mut retmsg, &mut fromid) {
-                Ok(()) => {
-                    println!("reply from: {}", fromid);
-                    // for i in 0..retmsg.buf.len() {
-                    //     let c = retmsg.buf[i];
-                    //     println!("{} - {}", c, c as char);
-                    // }
-                    if json {
-                        println!("unimplemented");
-                    } else {
-                        am::print_payload(&retmsg.payload);
-                    }
-                }
-                Err(e) => {
-                    println!("error: {:?}", e);
-                }
+        }
+        return Ok(());
+    }
+
+    match transport.receive() {
+        Ok((fromid, payload)) => {
+            println!("reply from: {}", fromid);
+            let pe = PayloadEnum::from(payload);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&codecs.to_json(&pe)?)?);
+            } else {
+                println!("{:#}", codecs.display(&pe));
             }
         }
+        Err(e) => {
+            println!("error: {:?}", e);
+        }
     }
     Ok(())
 }
+
+// i2c addresses are usually written in hex.
+fn parse_u8(s: &str) -> Result<u8, std::num::ParseIntError> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse::<u8>(),
+    }
+}
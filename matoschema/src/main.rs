@@ -0,0 +1,100 @@
+use automato::schema::Schema;
+use automato::simulator::MapField;
+use clap::{Arg, Command};
+use serde::Serialize;
+use simple_error::bail;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+// the memory map part of a simulator NodeConfig.
+#[derive(Serialize)]
+struct SimFields {
+    datalen: u16,
+    fields: Vec<MapField>,
+}
+
+fn main() {
+    match err_main() {
+        Ok(()) => (),
+        Err(e) => {
+            println!("error: {:?}", e)
+        }
+    }
+}
+
+fn err_main() -> Result<(), Box<dyn Error>> {
+    let matches = clap::Command::new("matoschema")
+        .version("1.0")
+        .author("Automato Enterprises")
+        .about("memory map schemas: C++ for the sketches, and layouts for checking them.")
+        .arg(
+            Arg::new("schema")
+                .value_name("FILE")
+                .help("schema file, toml or .json")
+                .required(true)
+                .takes_value(true),
+        )
+        .subcommand_required(true)
+        .subcommand(
+            Command::new("cpp")
+                .about("write the ServerData struct and memoryMap[] as a C++ header")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("header file to write, instead of stdout")
+                        .takes_value(true),
+                ),
+        )
+        .subcommand(Command::new("layout").about("print each field's offset, length and format"))
+        .subcommand(
+            Command::new("simfields")
+                .about("print datalen and fields for a matosim mesh config node"),
+        )
+        .get_matches();
+
+    let path = match matches.value_of("schema") {
+        Some(path) => Path::new(path),
+        None => bail!("arg failure"),
+    };
+    let map = Schema::load(path)?.layout()?;
+
+    match matches.subcommand() {
+        Some(("cpp", sub_matches)) => {
+            let source = path.file_name().unwrap_or_default().to_string_lossy();
+            let cpp = map.to_cpp(&source);
+            match sub_matches.value_of("output") {
+                Some(output) => fs::write(output, cpp)?,
+                None => print!("{}", cpp),
+            }
+        }
+        Some(("layout", _sub_matches)) => {
+            println!("datalen: {}", map.datalen);
+            for f in map.fields.iter() {
+                println!(
+                    "  {:5} {:3} {:8} {}{}",
+                    f.offset,
+                    f.ctype.size(),
+                    format!("{:?}", f.ctype.format()),
+                    f.name,
+                    if f.mapped { "" } else { " (unmapped)" }
+                );
+            }
+        }
+        Some(("simfields", _sub_matches)) => {
+            print!(
+                "{}",
+                toml::to_string_pretty(&SimFields {
+                    datalen: map.datalen,
+                    fields: map.map_fields(),
+                })?
+            );
+        }
+        meh => {
+            bail!("unhandled command! {:?}", meh)
+        }
+    }
+    Ok(())
+}
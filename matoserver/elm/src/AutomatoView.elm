@@ -13,7 +13,7 @@ import Element.Font as EF
 import Element.Input as EI
 import Json.Decode as JD
 import Json.Encode as JE
-import Messages exposing (AutomatoMsg)
+import Messages exposing (AutomatoMsg, SchemaMsg)
 import MsCommon as MS
 import Payload
 import Round as R
@@ -62,6 +62,7 @@ type alias Model =
     , temperature : Maybe Float
     , humidity : Maybe Float
     , fields : Dict Int Field
+    , schema : Maybe SchemaMsg
     , pendingMsgs : List PendingMsg
     , editedField : Maybe ( Int, String )
     , requestIdCount : Int
@@ -118,6 +119,7 @@ init automatoid ai requestIdCount =
             , temperature = Nothing
             , humidity = Nothing
             , fields = Dict.empty
+            , schema = Nothing
             , pendingMsgs = pendingMsgs
             , editedField = Nothing
             , requestIdCount = requestIdCount + List.length pendingMsgs
@@ -140,6 +142,98 @@ readField rfr =
     }
 
 
+{-| add a field, and queue a readmem for its value.
+-}
+addField : Payload.ReadFieldReply -> Model -> Model
+addField rfr model =
+    { model
+        | fields = Dict.insert rfr.index { rfr = rfr, value = Nothing } model.fields
+        , pendingMsgs =
+            model.pendingMsgs
+                ++ [ { automatoMsg =
+                        { id = model.id
+                        , message = Payload.PeReadmem <| readField rfr
+                        }
+                     , what =
+                        { id = model.requestIdCount, field = Just rfr.index }
+                     }
+                   ]
+        , requestIdCount = model.requestIdCount + 1
+    }
+
+
+pendingReadfield : PendingMsg -> Maybe Int
+pendingReadfield pm =
+    case pm.automatoMsg.message of
+        Payload.PeReadfield rf ->
+            Just rf.index
+
+        _ ->
+            Nothing
+
+
+{-| a readfield got no readfieldreply; fall back to the schema for that field, if there is one.
+-}
+readfieldFailed : Model -> Model -> Model
+readfieldFailed model nm =
+    let
+        schemafield =
+            List.head model.pendingMsgs
+                |> Maybe.andThen pendingReadfield
+                |> Maybe.andThen
+                    (\i ->
+                        nm.schema
+                            |> Maybe.andThen (.fields >> List.filter (\f -> f.index == i) >> List.head)
+                    )
+    in
+    case schemafield of
+        Just rfr ->
+            if Dict.member rfr.index nm.fields then
+                nm
+
+            else
+                addField rfr nm
+
+        Nothing ->
+            nm
+
+
+{-| label any fields whose readfield already failed.  fields still waiting on a
+readfield are left for readfieldFailed.
+-}
+onSchema : SchemaMsg -> Model -> ( Model, Command )
+onSchema schema model =
+    if schema.id /= model.id then
+        ( model, None )
+
+    else
+        let
+            pending =
+                List.filterMap pendingReadfield model.pendingMsgs
+
+            nm =
+                schema.fields
+                    |> List.foldl
+                        (\rfr m ->
+                            if Dict.member rfr.index m.fields || List.member rfr.index pending then
+                                m
+
+                            else
+                                addField rfr m
+                        )
+                        { model | schema = Just schema }
+        in
+        ( nm
+        , -- if nothing was in flight, start on the new readmems.
+          case ( model.pendingMsgs, nm.pendingMsgs ) of
+            ( [], pm :: _ ) ->
+                SendAutomatoMsg pm.automatoMsg pm.what
+
+            _ ->
+                None
+        )
+
+
 onSerialError : SerialError.Error -> MsgWhat -> Model -> ( Model, Command )
 onSerialError se mw model =
     let
@@ -149,11 +243,11 @@ onSerialError se mw model =
         --     Debug.log "pending what: " (List.head model.pendingMsgs |> Maybe.map .what)
         -- _ =
         --     Debug.log "incoming what: " mw
-        pms =
-            List.drop 1 model.pendingMsgs
+        nm =
+            readfieldFailed model { model | pendingMsgs = List.drop 1 model.pendingMsgs }
     in
-    ( { model | pendingMsgs = pms }
-    , case List.head pms of
+    ( nm
+    , case List.head nm.pendingMsgs of
         Just pm ->
             SendAutomatoMsg pm.automatoMsg pm.what
 
@@ -179,20 +273,7 @@ onAutomatoMsg am msgwhat model =
         nm =
             case am.message of
                 Payload.PeReadfieldreply rfr ->
-                    { nm0
-                        | fields = Dict.insert rfr.index { rfr = rfr, value = Nothing } nm0.fields
-                        , pendingMsgs =
-                            nm0.pendingMsgs
-                                ++ [ { automatoMsg =
-                                        { id = nm0.id
-                                        , message = Payload.PeReadmem <| readField rfr
-                                        }
-                                     , what =
-                                        { id = nm0.requestIdCount, field = Just rfr.index }
-                                     }
-                                   ]
-                        , requestIdCount = nm0.requestIdCount + 1
-                    }
+                    addField rfr nm0
 
                 Payload.PeReadmemreply rmr ->
                     msgwhat.field
@@ -228,7 +309,7 @@ onAutomatoMsg am msgwhat model =
                     }
 
                 _ ->
-                    nm0
+                    readfieldFailed model nm0
     in
     ( nm
     , case List.head nm.pendingMsgs of
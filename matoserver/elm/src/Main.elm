@@ -52,6 +52,7 @@ type Msg
     = ShowMessageMsg ShowMessage.Msg
     | PublicReplyData (Maybe String) (Result Http.Error PI.ServerResponse)
     | AutomatoMsgReplyData AutomatoView.MsgWhat (Result Http.Error PI.ServerResponse)
+    | SchemaReplyData (Result Http.Error PI.ServerResponse)
     | LoadUrl String
     | InternalUrl Url
     | SelectedText JD.Value
@@ -200,6 +201,9 @@ showMessage msg =
                            )
                    )
 
+        SchemaReplyData _ ->
+            "SchemaReplyData"
+
         LoadUrl _ ->
             "LoadUrl"
 
@@ -513,15 +517,20 @@ actualupdate msg model =
                                 -- ( AutomatoView av, _ ) ->
                                 --     handleAutomatoView model (AutomatoView.onAutomatoMsg am what av)
                                 ( _, Payload.PeReadinforeply info ) ->
-                                    handleAutomatoView model
-                                        (AutomatoView.init am.id
-                                            info
-                                            model.requestIdCount
-                                        )
+                                    initAutomatoView model am.id info
 
                                 _ ->
                                     ( model, Cmd.none )
 
+                        PI.AutomatoMsgs _ ->
+                            ( model, Cmd.none )
+
+                        PI.Pong _ ->
+                            ( model, Cmd.none )
+
+                        PI.Schema _ ->
+                            ( model, Cmd.none )
+
                         PI.SerialError se ->
                             ( displayMessageDialog model (JE.encode 2 (SerialError.errorEncoder se)), Cmd.none )
 
@@ -549,15 +558,20 @@ actualupdate msg model =
                                     handleAutomatoView model (AutomatoView.onAutomatoMsg am what av)
 
                                 ( _, Payload.PeReadinforeply info ) ->
-                                    handleAutomatoView model
-                                        (AutomatoView.init am.id
-                                            info
-                                            model.requestIdCount
-                                        )
+                                    initAutomatoView model am.id info
 
                                 _ ->
                                     ( model, Cmd.none )
 
+                        PI.AutomatoMsgs _ ->
+                            ( model, Cmd.none )
+
+                        PI.Pong _ ->
+                            ( model, Cmd.none )
+
+                        PI.Schema _ ->
+                            ( model, Cmd.none )
+
                         PI.SerialError se ->
                             case model.state of
                                 AutomatoView av ->
@@ -569,6 +583,15 @@ actualupdate msg model =
                                 _ ->
                                     ( model, Cmd.none )
 
+        ( SchemaReplyData urd, _ ) ->
+            -- most automatos have no schema; that's not worth a dialog.
+            case ( urd, model.state ) of
+                ( Ok (PI.Schema schema), AutomatoView av ) ->
+                    handleAutomatoView model (AutomatoView.onSchema schema av)
+
+                _ ->
+                    ( model, Cmd.none )
+
         ( DisplayMessageMsg bm, DisplayMessage bs prevstate ) ->
             case GD.update bm bs of
                 GD.Dialog nmod ->
@@ -632,6 +655,23 @@ actualupdate msg model =
             )
 
 
+{-| open the automato view, and ask for the automato's schema in case some
+of its fields don't answer readfield.
+-}
+initAutomatoView : Model -> Int -> Payload.RemoteInfo -> ( Model, Cmd Msg )
+initAutomatoView model id info =
+    let
+        ( nm, cmd ) =
+            handleAutomatoView model (AutomatoView.init id info model.requestIdCount)
+    in
+    ( nm
+    , Cmd.batch
+        [ cmd
+        , sendPIMsgExp model.location (PI.GetSchema id) SchemaReplyData
+        ]
+    )
+
+
 handleAutomatoView : Model -> ( AutomatoView.Model, AutomatoView.Command ) -> ( Model, Cmd Msg )
 handleAutomatoView model ( nm, cmd ) =
     case cmd of
@@ -8,7 +8,7 @@ import File
 import Http
 import Json.Decode
 import Json.Encode
-import Payload exposing (PayloadEnum, payloadEnumDecoder, payloadEnumEncoder)
+import Payload exposing (PayloadEnum, ReadFieldReply, payloadEnumDecoder, payloadEnumEncoder, readFieldReplyDecoder, readFieldReplyEncoder)
 import SerialError exposing (Error, errorDecoder, errorEncoder)
 import Url.Builder
 
@@ -52,6 +52,96 @@ automatoMsgDecoder =
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "message" payloadEnumDecoder))
 
 
+type alias BroadcastMsg =
+    { message : PayloadEnum
+    , windowMs : Int
+    }
+
+
+broadcastMsgEncoder : BroadcastMsg -> Json.Encode.Value
+broadcastMsgEncoder struct =
+    Json.Encode.object
+        [ ( "message", payloadEnumEncoder struct.message )
+        , ( "window_ms", Json.Encode.int struct.windowMs )
+        ]
+
+
+broadcastMsgDecoder : Json.Decode.Decoder BroadcastMsg
+broadcastMsgDecoder =
+    Json.Decode.succeed BroadcastMsg
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "message" payloadEnumDecoder))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "window_ms" Json.Decode.int))
+
+
+type alias SchemaMsg =
+    { id : Int
+    , datalen : Int
+    , fields : List ReadFieldReply
+    }
+
+
+schemaMsgEncoder : SchemaMsg -> Json.Encode.Value
+schemaMsgEncoder struct =
+    Json.Encode.object
+        [ ( "id", Json.Encode.int struct.id )
+        , ( "datalen", Json.Encode.int struct.datalen )
+        , ( "fields", Json.Encode.list readFieldReplyEncoder struct.fields )
+        ]
+
+
+schemaMsgDecoder : Json.Decode.Decoder SchemaMsg
+schemaMsgDecoder =
+    Json.Decode.succeed SchemaMsg
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" Json.Decode.int))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "datalen" Json.Decode.int))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "fields" (Json.Decode.list readFieldReplyDecoder)))
+
+
+type alias ResetMsg =
+    { id : Int
+    , factory : Bool
+    }
+
+
+resetMsgEncoder : ResetMsg -> Json.Encode.Value
+resetMsgEncoder struct =
+    Json.Encode.object
+        [ ( "id", Json.Encode.int struct.id )
+        , ( "factory", Json.Encode.bool struct.factory )
+        ]
+
+
+resetMsgDecoder : Json.Decode.Decoder ResetMsg
+resetMsgDecoder =
+    Json.Decode.succeed ResetMsg
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" Json.Decode.int))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "factory" Json.Decode.bool))
+
+
+type alias PongMsg =
+    { id : Int
+    , rttMs : Float
+    , millis : Int
+    }
+
+
+pongMsgEncoder : PongMsg -> Json.Encode.Value
+pongMsgEncoder struct =
+    Json.Encode.object
+        [ ( "id", Json.Encode.int struct.id )
+        , ( "rtt_ms", Json.Encode.float struct.rttMs )
+        , ( "millis", Json.Encode.int struct.millis )
+        ]
+
+
+pongMsgDecoder : Json.Decode.Decoder PongMsg
+pongMsgDecoder =
+    Json.Decode.succeed PongMsg
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" Json.Decode.int))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rtt_ms" Json.Decode.float))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "millis" Json.Decode.int))
+
+
 type alias WhatMsg =
     { what : String
     , msg : AutomatoMsg
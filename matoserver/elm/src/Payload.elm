@@ -227,6 +227,393 @@ readFieldReplyDecoder =
         |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "name" (Json.Decode.list (Json.Decode.int))))
 
 
+type alias Secure =
+    { counter : Int
+    , data : List (Int)
+    }
+
+
+secureEncoder : Secure -> Json.Encode.Value
+secureEncoder struct =
+    Json.Encode.object
+        [ ( "counter", (Json.Encode.int) struct.counter )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+secureDecoder : Json.Decode.Decoder Secure
+secureDecoder =
+    Json.Decode.succeed Secure
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "counter" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias Fragment =
+    { id : Int
+    , index : Int
+    , count : Int
+    , data : List (Int)
+    }
+
+
+fragmentEncoder : Fragment -> Json.Encode.Value
+fragmentEncoder struct =
+    Json.Encode.object
+        [ ( "id", (Json.Encode.int) struct.id )
+        , ( "index", (Json.Encode.int) struct.index )
+        , ( "count", (Json.Encode.int) struct.count )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+fragmentDecoder : Json.Decode.Decoder Fragment
+fragmentDecoder =
+    Json.Decode.succeed Fragment
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "id" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "index" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "count" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias Custom =
+    { subtype : Int
+    , data : List (Int)
+    }
+
+
+customEncoder : Custom -> Json.Encode.Value
+customEncoder struct =
+    Json.Encode.object
+        [ ( "subtype", (Json.Encode.int) struct.subtype )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+customDecoder : Json.Decode.Decoder Custom
+customDecoder =
+    Json.Decode.succeed Custom
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "subtype" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias Writeanalog =
+    { pin : Int
+    , duty : Int
+    , frequency : Int
+    }
+
+
+writeanalogEncoder : Writeanalog -> Json.Encode.Value
+writeanalogEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "duty", (Json.Encode.int) struct.duty )
+        , ( "frequency", (Json.Encode.int) struct.frequency )
+        ]
+
+
+writeanalogDecoder : Json.Decode.Decoder Writeanalog
+writeanalogDecoder =
+    Json.Decode.succeed Writeanalog
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "duty" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "frequency" (Json.Decode.int)))
+
+
+type alias I2cTransfer =
+    { address : Int
+    , readlength : Int
+    , data : List (Int)
+    }
+
+
+i2cTransferEncoder : I2cTransfer -> Json.Encode.Value
+i2cTransferEncoder struct =
+    Json.Encode.object
+        [ ( "address", (Json.Encode.int) struct.address )
+        , ( "readlength", (Json.Encode.int) struct.readlength )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+i2cTransferDecoder : Json.Decode.Decoder I2cTransfer
+i2cTransferDecoder =
+    Json.Decode.succeed I2cTransfer
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "address" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "readlength" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias I2cTransferReply =
+    { address : Int
+    , status : Int
+    , data : List (Int)
+    }
+
+
+i2cTransferReplyEncoder : I2cTransferReply -> Json.Encode.Value
+i2cTransferReplyEncoder struct =
+    Json.Encode.object
+        [ ( "address", (Json.Encode.int) struct.address )
+        , ( "status", (Json.Encode.int) struct.status )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+i2cTransferReplyDecoder : Json.Decode.Decoder I2cTransferReply
+i2cTransferReplyDecoder =
+    Json.Decode.succeed I2cTransferReply
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "address" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "status" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias SpiTransfer =
+    { cspin : Int
+    , mode : Int
+    , clock : Int
+    , data : List (Int)
+    }
+
+
+spiTransferEncoder : SpiTransfer -> Json.Encode.Value
+spiTransferEncoder struct =
+    Json.Encode.object
+        [ ( "cspin", (Json.Encode.int) struct.cspin )
+        , ( "mode", (Json.Encode.int) struct.mode )
+        , ( "clock", (Json.Encode.int) struct.clock )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+spiTransferDecoder : Json.Decode.Decoder SpiTransfer
+spiTransferDecoder =
+    Json.Decode.succeed SpiTransfer
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "cspin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "mode" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "clock" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias SpiTransferReply =
+    { cspin : Int
+    , data : List (Int)
+    }
+
+
+spiTransferReplyEncoder : SpiTransferReply -> Json.Encode.Value
+spiTransferReplyEncoder struct =
+    Json.Encode.object
+        [ ( "cspin", (Json.Encode.int) struct.cspin )
+        , ( "data", (Json.Encode.list (Json.Encode.int)) struct.data )
+        ]
+
+
+spiTransferReplyDecoder : Json.Decode.Decoder SpiTransferReply
+spiTransferReplyDecoder =
+    Json.Decode.succeed SpiTransferReply
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "cspin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "data" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias OnewireScanReply =
+    { pin : Int
+    , roms : List (List (Int))
+    }
+
+
+onewireScanReplyEncoder : OnewireScanReply -> Json.Encode.Value
+onewireScanReplyEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "roms", (Json.Encode.list (Json.Encode.list (Json.Encode.int))) struct.roms )
+        ]
+
+
+onewireScanReplyDecoder : Json.Decode.Decoder OnewireScanReply
+onewireScanReplyDecoder =
+    Json.Decode.succeed OnewireScanReply
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "roms" (Json.Decode.list (Json.Decode.list (Json.Decode.int)))))
+
+
+type alias OnewireAddress =
+    { pin : Int
+    , rom : List (Int)
+    }
+
+
+onewireAddressEncoder : OnewireAddress -> Json.Encode.Value
+onewireAddressEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "rom", (Json.Encode.list (Json.Encode.int)) struct.rom )
+        ]
+
+
+onewireAddressDecoder : Json.Decode.Decoder OnewireAddress
+onewireAddressDecoder =
+    Json.Decode.succeed OnewireAddress
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rom" (Json.Decode.list (Json.Decode.int))))
+
+
+type alias OnewireTemperature =
+    { pin : Int
+    , rom : List (Int)
+    , temperature : Float
+    }
+
+
+onewireTemperatureEncoder : OnewireTemperature -> Json.Encode.Value
+onewireTemperatureEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "rom", (Json.Encode.list (Json.Encode.int)) struct.rom )
+        , ( "temperature", (Json.Encode.float) struct.temperature )
+        ]
+
+
+onewireTemperatureDecoder : Json.Decode.Decoder OnewireTemperature
+onewireTemperatureDecoder =
+    Json.Decode.succeed OnewireTemperature
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "rom" (Json.Decode.list (Json.Decode.int))))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "temperature" (Json.Decode.float)))
+
+
+type alias Servo =
+    { pin : Int
+    , pulse : Int
+    , speed : Int
+    }
+
+
+servoEncoder : Servo -> Json.Encode.Value
+servoEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "pulse", (Json.Encode.int) struct.pulse )
+        , ( "speed", (Json.Encode.int) struct.speed )
+        ]
+
+
+servoDecoder : Json.Decode.Decoder Servo
+servoDecoder =
+    Json.Decode.succeed Servo
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pulse" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "speed" (Json.Decode.int)))
+
+
+type alias ServoReply =
+    { pin : Int
+    , pulse : Int
+    }
+
+
+servoReplyEncoder : ServoReply -> Json.Encode.Value
+servoReplyEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "pulse", (Json.Encode.int) struct.pulse )
+        ]
+
+
+servoReplyDecoder : Json.Decode.Decoder ServoReply
+servoReplyDecoder =
+    Json.Decode.succeed ServoReply
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pulse" (Json.Decode.int)))
+
+
+type alias PulseCounter =
+    { pin : Int
+    , edge : Int
+    }
+
+
+pulseCounterEncoder : PulseCounter -> Json.Encode.Value
+pulseCounterEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "edge", (Json.Encode.int) struct.edge )
+        ]
+
+
+pulseCounterDecoder : Json.Decode.Decoder PulseCounter
+pulseCounterDecoder =
+    Json.Decode.succeed PulseCounter
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "edge" (Json.Decode.int)))
+
+
+type alias ReadPulsecount =
+    { pin : Int
+    , reset : Int
+    }
+
+
+readPulsecountEncoder : ReadPulsecount -> Json.Encode.Value
+readPulsecountEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "reset", (Json.Encode.int) struct.reset )
+        ]
+
+
+readPulsecountDecoder : Json.Decode.Decoder ReadPulsecount
+readPulsecountDecoder =
+    Json.Decode.succeed ReadPulsecount
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "reset" (Json.Decode.int)))
+
+
+type alias PulseCount =
+    { pin : Int
+    , count : Int
+    , frequency : Float
+    }
+
+
+pulseCountEncoder : PulseCount -> Json.Encode.Value
+pulseCountEncoder struct =
+    Json.Encode.object
+        [ ( "pin", (Json.Encode.int) struct.pin )
+        , ( "count", (Json.Encode.int) struct.count )
+        , ( "frequency", (Json.Encode.float) struct.frequency )
+        ]
+
+
+pulseCountDecoder : Json.Decode.Decoder PulseCount
+pulseCountDecoder =
+    Json.Decode.succeed PulseCount
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "pin" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "count" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "frequency" (Json.Decode.float)))
+
+
+type alias Pong =
+    { nonce : Int
+    , millis : Int
+    }
+
+
+pongEncoder : Pong -> Json.Encode.Value
+pongEncoder struct =
+    Json.Encode.object
+        [ ( "nonce", (Json.Encode.int) struct.nonce )
+        , ( "millis", (Json.Encode.int) struct.millis )
+        ]
+
+
+pongDecoder : Json.Decode.Decoder Pong
+pongDecoder =
+    Json.Decode.succeed Pong
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "nonce" (Json.Decode.int)))
+        |> Json.Decode.andThen (\x -> Json.Decode.map x (Json.Decode.field "millis" (Json.Decode.int)))
+
+
 type PayloadEnum
     = PeAck
     | PeFail (Int)
@@ -247,6 +634,27 @@ type PayloadEnum
     | PeReadanalogreply (AnalogPinval)
     | PeReadfield (ReadField)
     | PeReadfieldreply (ReadFieldReply)
+    | PeSecure (Secure)
+    | PeFragment (Fragment)
+    | PeCustom (Custom)
+    | PeWriteanalog (Writeanalog)
+    | PeI2cTransfer (I2cTransfer)
+    | PeI2cTransferreply (I2cTransferReply)
+    | PeSpiTransfer (SpiTransfer)
+    | PeSpiTransferreply (SpiTransferReply)
+    | PeOnewirescan (Int)
+    | PeOnewirescanreply (OnewireScanReply)
+    | PeReadonewiretemperature (OnewireAddress)
+    | PeReadonewiretemperaturereply (OnewireTemperature)
+    | PeServo (Servo)
+    | PeServoreply (ServoReply)
+    | PePulsecounter (PulseCounter)
+    | PeReadpulsecount (ReadPulsecount)
+    | PeReadpulsecountreply (PulseCount)
+    | PeReboot (Int)
+    | PeFactoryReset (Int)
+    | PePing (Int)
+    | PePong (Pong)
 
 
 payloadEnumEncoder : PayloadEnum -> Json.Encode.Value
@@ -290,6 +698,48 @@ payloadEnumEncoder enum =
             Json.Encode.object [ ( "PeReadfield", readFieldEncoder inner ) ]
         PeReadfieldreply inner ->
             Json.Encode.object [ ( "PeReadfieldreply", readFieldReplyEncoder inner ) ]
+        PeSecure inner ->
+            Json.Encode.object [ ( "PeSecure", secureEncoder inner ) ]
+        PeFragment inner ->
+            Json.Encode.object [ ( "PeFragment", fragmentEncoder inner ) ]
+        PeCustom inner ->
+            Json.Encode.object [ ( "PeCustom", customEncoder inner ) ]
+        PeWriteanalog inner ->
+            Json.Encode.object [ ( "PeWriteanalog", writeanalogEncoder inner ) ]
+        PeI2cTransfer inner ->
+            Json.Encode.object [ ( "PeI2cTransfer", i2cTransferEncoder inner ) ]
+        PeI2cTransferreply inner ->
+            Json.Encode.object [ ( "PeI2cTransferreply", i2cTransferReplyEncoder inner ) ]
+        PeSpiTransfer inner ->
+            Json.Encode.object [ ( "PeSpiTransfer", spiTransferEncoder inner ) ]
+        PeSpiTransferreply inner ->
+            Json.Encode.object [ ( "PeSpiTransferreply", spiTransferReplyEncoder inner ) ]
+        PeOnewirescan inner ->
+            Json.Encode.object [ ( "PeOnewirescan", Json.Encode.int inner ) ]
+        PeOnewirescanreply inner ->
+            Json.Encode.object [ ( "PeOnewirescanreply", onewireScanReplyEncoder inner ) ]
+        PeReadonewiretemperature inner ->
+            Json.Encode.object [ ( "PeReadonewiretemperature", onewireAddressEncoder inner ) ]
+        PeReadonewiretemperaturereply inner ->
+            Json.Encode.object [ ( "PeReadonewiretemperaturereply", onewireTemperatureEncoder inner ) ]
+        PeServo inner ->
+            Json.Encode.object [ ( "PeServo", servoEncoder inner ) ]
+        PeServoreply inner ->
+            Json.Encode.object [ ( "PeServoreply", servoReplyEncoder inner ) ]
+        PePulsecounter inner ->
+            Json.Encode.object [ ( "PePulsecounter", pulseCounterEncoder inner ) ]
+        PeReadpulsecount inner ->
+            Json.Encode.object [ ( "PeReadpulsecount", readPulsecountEncoder inner ) ]
+        PeReadpulsecountreply inner ->
+            Json.Encode.object [ ( "PeReadpulsecountreply", pulseCountEncoder inner ) ]
+        PeReboot inner ->
+            Json.Encode.object [ ( "PeReboot", Json.Encode.int inner ) ]
+        PeFactoryReset inner ->
+            Json.Encode.object [ ( "PeFactoryReset", Json.Encode.int inner ) ]
+        PePing inner ->
+            Json.Encode.object [ ( "PePing", Json.Encode.int inner ) ]
+        PePong inner ->
+            Json.Encode.object [ ( "PePong", pongEncoder inner ) ]
 
 payloadEnumDecoder : Json.Decode.Decoder PayloadEnum
 payloadEnumDecoder = 
@@ -345,5 +795,26 @@ payloadEnumDecoder =
         , Json.Decode.map PeReadanalogreply (Json.Decode.field "PeReadanalogreply" (analogPinvalDecoder))
         , Json.Decode.map PeReadfield (Json.Decode.field "PeReadfield" (readFieldDecoder))
         , Json.Decode.map PeReadfieldreply (Json.Decode.field "PeReadfieldreply" (readFieldReplyDecoder))
+        , Json.Decode.map PeSecure (Json.Decode.field "PeSecure" (secureDecoder))
+        , Json.Decode.map PeFragment (Json.Decode.field "PeFragment" (fragmentDecoder))
+        , Json.Decode.map PeCustom (Json.Decode.field "PeCustom" (customDecoder))
+        , Json.Decode.map PeWriteanalog (Json.Decode.field "PeWriteanalog" (writeanalogDecoder))
+        , Json.Decode.map PeI2cTransfer (Json.Decode.field "PeI2cTransfer" (i2cTransferDecoder))
+        , Json.Decode.map PeI2cTransferreply (Json.Decode.field "PeI2cTransferreply" (i2cTransferReplyDecoder))
+        , Json.Decode.map PeSpiTransfer (Json.Decode.field "PeSpiTransfer" (spiTransferDecoder))
+        , Json.Decode.map PeSpiTransferreply (Json.Decode.field "PeSpiTransferreply" (spiTransferReplyDecoder))
+        , Json.Decode.map PeOnewirescan (Json.Decode.field "PeOnewirescan" (Json.Decode.int))
+        , Json.Decode.map PeOnewirescanreply (Json.Decode.field "PeOnewirescanreply" (onewireScanReplyDecoder))
+        , Json.Decode.map PeReadonewiretemperature (Json.Decode.field "PeReadonewiretemperature" (onewireAddressDecoder))
+        , Json.Decode.map PeReadonewiretemperaturereply (Json.Decode.field "PeReadonewiretemperaturereply" (onewireTemperatureDecoder))
+        , Json.Decode.map PeServo (Json.Decode.field "PeServo" (servoDecoder))
+        , Json.Decode.map PeServoreply (Json.Decode.field "PeServoreply" (servoReplyDecoder))
+        , Json.Decode.map PePulsecounter (Json.Decode.field "PePulsecounter" (pulseCounterDecoder))
+        , Json.Decode.map PeReadpulsecount (Json.Decode.field "PeReadpulsecount" (readPulsecountDecoder))
+        , Json.Decode.map PeReadpulsecountreply (Json.Decode.field "PeReadpulsecountreply" (pulseCountDecoder))
+        , Json.Decode.map PeReboot (Json.Decode.field "PeReboot" (Json.Decode.int))
+        , Json.Decode.map PeFactoryReset (Json.Decode.field "PeFactoryReset" (Json.Decode.int))
+        , Json.Decode.map PePing (Json.Decode.field "PePing" (Json.Decode.int))
+        , Json.Decode.map PePong (Json.Decode.field "PePong" (pongDecoder))
         ]
 
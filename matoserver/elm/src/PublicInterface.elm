@@ -17,12 +17,18 @@ import SerialError as SE
 type SendMsg
     = GetAutomatoList
     | SendAutomatoMsg Messages.AutomatoMsg
+    | SendResetMsg Messages.ResetMsg
+    | SendPing Int
+    | GetSchema Int
 
 
 type ServerResponse
     = ServerError String
     | AutomatoList (List Data.ListAutomato)
     | AutomatoMsg Messages.AutomatoMsg
+    | AutomatoMsgs (List Messages.AutomatoMsg)
+    | Pong Messages.PongMsg
+    | Schema Messages.SchemaMsg
     | SerialError SE.Error
 
 
@@ -38,6 +44,15 @@ showServerResponse sr =
         AutomatoMsg _ ->
             "AutomatoMsg"
 
+        AutomatoMsgs _ ->
+            "AutomatoMsgs"
+
+        Pong _ ->
+            "Pong"
+
+        Schema _ ->
+            "Schema"
+
         SerialError _ ->
             "SerialError"
 
@@ -56,6 +71,24 @@ encodeSendMsg sm =
                 , ( "data", Messages.automatoMsgEncoder msg )
                 ]
 
+        SendResetMsg msg ->
+            JE.object
+                [ ( "what", JE.string "ResetAutomato" )
+                , ( "data", Messages.resetMsgEncoder msg )
+                ]
+
+        SendPing id ->
+            JE.object
+                [ ( "what", JE.string "Ping" )
+                , ( "data", JE.object [ ( "id", JE.int id ) ] )
+                ]
+
+        GetSchema id ->
+            JE.object
+                [ ( "what", JE.string "GetSchema" )
+                , ( "data", JE.object [ ( "id", JE.int id ) ] )
+                ]
+
 
 serverResponseDecoder : JD.Decoder ServerResponse
 serverResponseDecoder =
@@ -76,6 +109,18 @@ serverResponseDecoder =
                         JD.at [ "content" ] Messages.automatoMsgDecoder
                             |> JD.map AutomatoMsg
 
+                    "automatomsgs" ->
+                        JD.at [ "content" ] (JD.list Messages.automatoMsgDecoder)
+                            |> JD.map AutomatoMsgs
+
+                    "pong" ->
+                        JD.at [ "content" ] Messages.pongMsgDecoder
+                            |> JD.map Pong
+
+                    "schema" ->
+                        JD.at [ "content" ] Messages.schemaMsgDecoder
+                            |> JD.map Schema
+
                     "serial error" ->
                         JD.at [ "content" ] SE.errorDecoder
                             |> JD.map SerialError
@@ -1,3 +1,6 @@
+use automato::fragment::NodeMtu;
+use automato::schema::NodeSchema;
+use automato::secure::NodeKey;
 use serde_derive::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -7,4 +10,21 @@ pub struct Config {
     pub port: u16,
     pub static_path: Option<PathBuf>,
     pub automato_ids: Vec<u8>,
+    // pre-shared keys for automatos that only take sealed messages.
+    #[serde(default)]
+    pub keys: Vec<NodeKey>,
+    // this server's id to the keyed automatos.  other hosts with the same
+    // keys, like matomsg, need ids of their own.
+    #[serde(default)]
+    pub host_id: u8,
+    // radio mtus for automatos that take less than the RFM95's 251 bytes.
+    #[serde(default)]
+    pub mtus: Vec<NodeMtu>,
+    // memory map schemas, for labeling fields of automatos that don't answer.
+    #[serde(default)]
+    pub schemas: Vec<NodeSchema>,
+    // automatos that ResetAutomato may reboot or factory reset.  none unless
+    // listed.
+    #[serde(default)]
+    pub resettable: Vec<u8>,
 }
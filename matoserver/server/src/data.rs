@@ -1,9 +1,15 @@
 use crate::Config;
-use serialport;
+use automato::custom::CodecRegistry;
+use automato::schema::MemoryMap;
+use automato::transport::Transport;
+use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
 
 pub struct ServerData {
     pub config: Config,
-    pub port: Arc<Mutex<Box<serialport::SerialPort>>>,
+    pub port: Arc<Mutex<Box<dyn Transport>>>,
+    // decodes sketch specific custom payloads.
+    pub codecs: Arc<CodecRegistry>,
+    // memory maps from schema files, by automato id.
+    pub schemas: Arc<BTreeMap<u8, MemoryMap>>,
 }
-
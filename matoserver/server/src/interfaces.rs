@@ -1,9 +1,13 @@
 use crate::data::ServerData;
-use crate::messages::AutomatoMsg;
-use crate::messages::{PublicMessage, ServerResponse};
+use crate::messages::{PongMsg, PublicMessage, ResetMsg, SchemaMsg, ServerResponse};
 use crate::serial_error;
 use automato::automatomsg as am;
-use log::info;
+use automato::custom::CodecRegistry;
+use automato::ping::{ping, PingError};
+use automato::transport::{collect_replies, REPLY_TIMEOUT};
+use log::{error, info};
+use serde_json::{json, Value};
+use std::convert::TryFrom;
 use std::error::Error;
 use std::time::Duration;
 
@@ -20,57 +24,176 @@ pub fn public_interface(
         }),
         "AutomatoMsg" => {
             let msgdata = Option::ok_or(msg.data.as_ref(), "malformed json data")?;
-            let am: AutomatoMsg = serde_json::from_value(msgdata.clone())?;
-            let mut mb = am::Msgbuf {
-                buf: [0; am::RH_RF95_MAX_MESSAGE_LEN],
+            // an AutomatoMsg, but custom payloads can be given by codec name.
+            let id: u8 = serde_json::from_value(msgdata["id"].clone())?;
+            let message = data.codecs.from_json(&msgdata["message"])?;
+            if is_reset(&message) {
+                return Err("resets go through ResetAutomato".into());
+            }
+            request(data, id, message)
+        }
+        "ResetAutomato" => {
+            let msgdata = Option::ok_or(msg.data.as_ref(), "malformed json data")?;
+            let rm: ResetMsg = serde_json::from_value(msgdata.clone())?;
+            if !data.config.resettable.contains(&rm.id) {
+                return Err(format!("automato {} isn't resettable", rm.id).into());
+            }
+            let message = if rm.factory {
+                am::PayloadEnum::factoryreset()
+            } else {
+                am::PayloadEnum::reboot()
             };
+            request(data, rm.id, message)
+        }
+        "BroadcastMsg" => {
+            let msgdata = Option::ok_or(msg.data.as_ref(), "malformed json data")?;
+            let window_ms: u64 = serde_json::from_value(msgdata["window_ms"].clone())?;
+            // the port is locked for the whole window.
+            let window = Duration::from_millis(window_ms);
+            if window > REPLY_TIMEOUT {
+                return Err(format!(
+                    "window_ms {} is over the maximum of {}",
+                    window_ms,
+                    REPLY_TIMEOUT.as_millis()
+                )
+                .into());
+            }
+            let message = data.codecs.from_json(&msgdata["message"])?;
+            // never every automato at once.
+            if is_reset(&message) {
+                return Err("resets can't be broadcast".into());
+            }
+            info!("broadcasting: {}", data.codecs.display(&message));
 
-            println!("sending automatomsg: {:?}", am);
-
-            let mut retmsg = mb.clone();
-
-            unsafe {
-                mb.payload = am::Payload::from(am.message);
-                let mut port = data.port.lock()?;
-                am::write_message(&mut **port, &mb, am.id)?;
-
-                let mut fromid: u8 = 0;
-                // set to more than the hardcoded RHMesh timeout, which is 4000ms
-                port.set_timeout(Duration::from_millis(4420))?;
-
-                match am::read_message(&mut **port, &mut retmsg, &mut fromid) {
-                    Ok(()) => {
-                        println!("reply from: {}", fromid);
-                        // for i in 0..retmsg.buf.len() {
-                        //     let c = retmsg.buf[i];
-                        //     println!("{} - {}", c, c as char);
-                        // }
-                        am::print_payload(&retmsg.payload);
+            message.validate()?;
+            let payload = am::Payload::try_from(message)?;
+            let mut port = data.port.lock()?;
+            port.send(&payload, am::RH_BROADCAST_ADDRESS)?;
 
-                        let rm = AutomatoMsg {
-                            id: fromid,
-                            message: am::PayloadEnum::from(retmsg.payload),
-                        };
-                        Ok(ServerResponse {
-                            what: "automatomsg".to_string(),
-                            content: serde_json::to_value(rm)?,
-                        })
-                    }
-                    Err(e) => {
-                        println!("read_message err: {:?}", e);
-                        let se = serial_error::Error::from(e);
-                        Ok(ServerResponse {
-                            what: "serial error".to_string(),
-                            content: serde_json::to_value(se)?,
-                            // content: serde_json::Value::Null,
+            match collect_replies(&mut **port, window) {
+                Ok(replies) => {
+                    info!("{} replies to broadcast", replies.len());
+                    let rms = replies
+                        .into_iter()
+                        .map(|(fromid, reply)| {
+                            automato_msg(&data.codecs, fromid, &am::PayloadEnum::from(reply))
                         })
-                    }
+                        .collect::<Result<Vec<Value>, _>>()?;
+                    Ok(ServerResponse {
+                        what: "automatomsgs".to_string(),
+                        content: Value::Array(rms),
+                    })
+                }
+                Err(e) => {
+                    error!("collect_replies err: {:?}", e);
+                    let se = serial_error::Error::from(e);
+                    Ok(ServerResponse {
+                        what: "serial error".to_string(),
+                        content: serde_json::to_value(se)?,
+                    })
                 }
             }
         }
+        "Ping" => {
+            let msgdata = Option::ok_or(msg.data.as_ref(), "malformed json data")?;
+            let id: u8 = serde_json::from_value(msgdata["id"].clone())?;
+            let mut port = data.port.lock()?;
+            port.set_timeout(REPLY_TIMEOUT)?;
+
+            match ping(&mut **port, id) {
+                Ok((rtt, pong)) => {
+                    info!("pong from {}: {:?}", id, rtt);
+                    Ok(ServerResponse {
+                        what: "pong".to_string(),
+                        content: serde_json::to_value(PongMsg {
+                            id,
+                            rtt_ms: rtt.as_secs_f64() * 1000.0,
+                            millis: pong.millis,
+                        })?,
+                    })
+                }
+                Err(PingError::Transport(e)) => {
+                    error!("ping err: {:?}", e);
+                    let se = serial_error::Error::from(e);
+                    Ok(ServerResponse {
+                        what: "serial error".to_string(),
+                        content: serde_json::to_value(se)?,
+                    })
+                }
+                Err(e) => Err(e.into()),
+            }
+        }
+        "GetSchema" => {
+            let msgdata = Option::ok_or(msg.data.as_ref(), "malformed json data")?;
+            let id: u8 = serde_json::from_value(msgdata["id"].clone())?;
+            let map = Option::ok_or(data.schemas.get(&id), "no schema for that automato")?;
+            Ok(ServerResponse {
+                what: "schema".to_string(),
+                content: serde_json::to_value(SchemaMsg {
+                    id,
+                    datalen: map.datalen,
+                    fields: map.readfield_replies(),
+                })?,
+            })
+        }
         wat => Err(Box::new(simple_error::SimpleError::new(format!(
             "invalid 'what' code:'{}'",
             wat
         )))),
     }
 }
+
+// send message to automato id and wait for its reply.
+fn request(
+    data: &ServerData,
+    id: u8,
+    message: am::PayloadEnum,
+) -> Result<ServerResponse, Box<dyn Error + '_>> {
+    info!("sending to {}: {}", id, data.codecs.display(&message));
+
+    // clients send PayloadEnum json; check it like the builders would.
+    message.validate()?;
+    let payload = am::Payload::try_from(message)?;
+    let mut port = data.port.lock()?;
+    port.send(&payload, id)?;
+
+    port.set_timeout(REPLY_TIMEOUT)?;
+
+    match port.receive() {
+        Ok((fromid, reply)) => {
+            let reply = am::PayloadEnum::from(reply);
+            info!("reply from {}: {}", fromid, data.codecs.display(&reply));
+
+            Ok(ServerResponse {
+                what: "automatomsg".to_string(),
+                content: automato_msg(&data.codecs, fromid, &reply)?,
+            })
+        }
+        Err(e) => {
+            error!("read_message err: {:?}", e);
+            let se = serial_error::Error::from(e);
+            Ok(ServerResponse {
+                what: "serial error".to_string(),
+                content: serde_json::to_value(se)?,
+                // content: serde_json::Value::Null,
+            })
+        }
+    }
+}
+
+// reboots and factory resets only go out through ResetAutomato.
+fn is_reset(message: &am::PayloadEnum) -> bool {
+    matches!(
+        message,
+        am::PayloadEnum::PeReboot(_) | am::PayloadEnum::PeFactoryReset(_)
+    )
+}
+
+// AutomatoMsg json, with custom payloads decoded where there's a codec.
+fn automato_msg(
+    codecs: &CodecRegistry,
+    id: u8,
+    message: &am::PayloadEnum,
+) -> Result<Value, serde_json::Error> {
+    Ok(json!({ "id": id, "message": codecs.to_json(message)? }))
+}
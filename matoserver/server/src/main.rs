@@ -9,6 +9,12 @@ use crate::data::ServerData;
 use actix_session::Session;
 use actix_web::{middleware, web, App, HttpRequest, HttpResponse, HttpServer, Result};
 use automato::automatomsg as am;
+use automato::custom::sketch_codecs;
+use automato::fragment::FragmentingTransport;
+use automato::mesh::SimulatedMesh;
+use automato::schema::Schema;
+use automato::secure::SecureTransport;
+use automato::transport::{RecordingTransport, ReplayTransport, SerialTransport, Transport};
 use config::Config;
 use log::{error, info};
 use messages::{PublicMessage, ServerResponse};
@@ -17,6 +23,7 @@ use std::path::Path;
 mod serial_error;
 use serialport;
 use simple_error::{bail, simple_error};
+use std::collections::BTreeMap;
 use std::env;
 use std::error::Error;
 use std::path::PathBuf;
@@ -91,6 +98,11 @@ fn defcon() -> Config {
         port: 8000,
         static_path: None,
         automato_ids: [].to_vec(),
+        keys: Vec::new(),
+        host_id: 0,
+        mtus: Vec::new(),
+        schemas: Vec::new(),
+        resettable: Vec::new(),
     }
 }
 
@@ -168,6 +180,28 @@ async fn err_main() -> Result<(), Box<dyn Error>> {
                 .default_value("420")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("FILE")
+                .help("answer messages from a capture file instead of the serial port")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("simulate")
+                .long("simulate")
+                .value_name("FILE")
+                .help("talk to simulated automatos from a mesh config file instead of the serial port")
+                .conflicts_with("replay")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("capture")
+                .long("capture")
+                .value_name("FILE")
+                .help("save automato traffic to a capture file")
+                .takes_value(true),
+        )
         .arg(
             Arg::new("writeelmbindings")
                 .long("writeelmbindings")
@@ -195,6 +229,23 @@ async fn err_main() -> Result<(), Box<dyn Error>> {
                     am::Writemem,
                     am::ReadField,
                     am::ReadFieldReply,
+                    am::Secure,
+                    am::Fragment,
+                    am::Custom,
+                    am::Writeanalog,
+                    am::I2cTransfer,
+                    am::I2cTransferReply,
+                    am::SpiTransfer,
+                    am::SpiTransferReply,
+                    am::OnewireScanReply,
+                    am::OnewireAddress,
+                    am::OnewireTemperature,
+                    am::Servo,
+                    am::ServoReply,
+                    am::PulseCounter,
+                    am::ReadPulsecount,
+                    am::PulseCount,
+                    am::Pong,
                     am::PayloadEnum
                 )
                 .unwrap();
@@ -236,6 +287,10 @@ async fn err_main() -> Result<(), Box<dyn Error>> {
                     "Messages",
                     &mut target,
                     messages::AutomatoMsg,
+                    messages::BroadcastMsg,
+                    messages::SchemaMsg,
+                    messages::ResetMsg,
+                    messages::PongMsg,
                     messages::WhatMsg,
                     messages::WhatError
                 )
@@ -294,15 +349,68 @@ async fn err_main() -> Result<(), Box<dyn Error>> {
 
             info!("config: {:?}", config);
 
-            let port = serialport::new(port, baud)
-                .data_bits(serialport::DataBits::Eight)
-                .flow_control(serialport::FlowControl::None)
-                .parity(serialport::Parity::None)
-                .stop_bits(serialport::StopBits::One)
-                .timeout(Duration::from_millis(timeout))
-                .open()?;
+            let mut transport: Box<dyn Transport> =
+                match (matches.value_of("replay"), matches.value_of("simulate")) {
+                    (Some(replay), _) => {
+                        info!("replaying traffic from: {}", replay);
+                        Box::new(ReplayTransport::open(Path::new(replay))?)
+                    }
+                    (None, Some(simulate)) => {
+                        info!("simulating automatos from: {}", simulate);
+                        let mut mesh = SimulatedMesh::open(Path::new(simulate))?;
+                        mesh.set_timeout(Duration::from_millis(timeout))?;
+                        Box::new(mesh)
+                    }
+                    (None, None) => {
+                        let port = serialport::new(port, baud)
+                            .data_bits(serialport::DataBits::Eight)
+                            .flow_control(serialport::FlowControl::None)
+                            .parity(serialport::Parity::None)
+                            .stop_bits(serialport::StopBits::One)
+                            .timeout(Duration::from_millis(timeout))
+                            .open()?;
+                        Box::new(SerialTransport::new(
+                            port,
+                            am::ReadLimits {
+                                deadline: Duration::from_millis(timeout),
+                                ..am::ReadLimits::default()
+                            },
+                        ))
+                    }
+                };
+
+            // frames as they go over the wire, under any fragmenting and sealing.
+            if let Some(capture) = matches.value_of("capture") {
+                info!("capturing traffic to: {}", capture);
+                transport = Box::new(RecordingTransport::create(transport, Path::new(capture))?);
+            }
+
+            transport = Box::new(FragmentingTransport::new(transport, &config.mtus)?);
+
+            if !config.keys.is_empty() {
+                info!(
+                    "sealing messages to automatos: {:?}",
+                    config.keys.iter().map(|k| k.id).collect::<Vec<_>>()
+                );
+                transport = Box::new(SecureTransport::new(
+                    transport,
+                    &config.keys,
+                    config.host_id,
+                )?);
+            }
+
+            let mut schemas = BTreeMap::new();
+            for ns in config.schemas.iter() {
+                info!("memory map schema for {}: {:?}", ns.id, ns.file);
+                let map = Schema::load(&ns.file)
+                    .and_then(|s| s.layout())
+                    .map_err(|e| format!("{:?}: {}", ns.file, e))?;
+                schemas.insert(ns.id, map);
+            }
+            let schemas = Arc::new(schemas);
 
-            let mp = Arc::new(Mutex::new(port));
+            let mp = Arc::new(Mutex::new(transport));
+            let codecs = Arc::new(sketch_codecs());
 
             let c = config.clone();
 
@@ -311,6 +419,8 @@ async fn err_main() -> Result<(), Box<dyn Error>> {
                 App::new()
                     .data(ServerData {
                         port: mp.clone(),
+                        codecs: codecs.clone(),
+                        schemas: schemas.clone(),
                         config: c.clone(),
                     }) // <- create app with shared state
                     .wrap(middleware::Logger::default())
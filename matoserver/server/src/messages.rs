@@ -30,6 +30,38 @@ pub struct AutomatoMsg {
     pub message: am::PayloadEnum,
 }
 
+// a message for every automato in range, and how long to collect replies;
+// at most the server's reply timeout.
+#[derive(Serialize, Deserialize, Debug, Clone, Elm, ElmJson)]
+pub struct BroadcastMsg {
+    pub message: am::PayloadEnum,
+    pub window_ms: u64,
+}
+
+// an automato's memory map from its schema file, with fields as readfield
+// would return them.
+#[derive(Serialize, Deserialize, Debug, Clone, Elm, ElmJson)]
+pub struct SchemaMsg {
+    pub id: u8,
+    pub datalen: u16,
+    pub fields: Vec<am::ReadFieldReply>,
+}
+
+// reboot an automato, or factory reset it.  the server fills in the token.
+#[derive(Serialize, Deserialize, Debug, Clone, Elm, ElmJson)]
+pub struct ResetMsg {
+    pub id: u8,
+    pub factory: bool,
+}
+
+// a ping's round trip, and the automato's millis() from its pong.
+#[derive(Serialize, Deserialize, Debug, Clone, Elm, ElmJson)]
+pub struct PongMsg {
+    pub id: u8,
+    pub rtt_ms: f64,
+    pub millis: u32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Elm, ElmJson)]
 pub struct WhatMsg {
     pub what: String,
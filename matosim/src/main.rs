@@ -0,0 +1,155 @@
+use automato::automatomsg::ReadLimits;
+use automato::mesh::{MeshConfig, SimulatedMesh};
+use automato::transport::{SerialTransport, Transport};
+use clap::Arg;
+use serialport::{SerialPort, TTYPort};
+use simple_error::bail;
+use std::error::Error;
+use std::fs;
+use std::time::{Duration, Instant};
+
+// how long serve waits for a request before going back to the mesh for
+// replies.  a request that starts in that time still gets the frame
+// allowance to come in whole.
+const POLL: Duration = Duration::from_millis(20);
+
+fn main() {
+    match err_main() {
+        Ok(()) => (),
+        Err(e) => {
+            println!("error: {:?}", e)
+        }
+    }
+}
+
+fn err_main() -> Result<(), Box<dyn Error>> {
+    let matches = clap::Command::new("matosim")
+        .version("1.0")
+        .author("Automato Enterprises")
+        .about("simulated automatos, answering messages over serial like a lora gateway.")
+        .arg(
+            Arg::new("port")
+                .short('p')
+                .long("port")
+                .value_name("FILE")
+                .help("serial port")
+                .default_value("/dev/ttyUSB0")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("baud")
+                .short('b')
+                .long("baud")
+                .value_name("NUMBER")
+                .help(
+                    "baud rate: 110, 300, 600, 1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200",
+                )
+                .default_value("115200")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("pty")
+                .long("pty")
+                .help("create a pseudo terminal to connect to, instead of using --port")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::new("config")
+                .short('c')
+                .long("config")
+                .value_name("FILE")
+                .help("mesh config file; defaults to one node like the serialtolora sketch")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("writeconfig")
+                .short('w')
+                .long("writeconfig")
+                .value_name("FILE")
+                .help("Write default config file")
+                .takes_value(true),
+        )
+        .get_matches();
+
+    if let Some(exportfile) = matches.value_of("writeconfig") {
+        fs::write(exportfile, toml::to_string_pretty(&MeshConfig::default())?)?;
+        return Ok(());
+    }
+
+    let config: MeshConfig = match matches.value_of("config") {
+        Some(file) => toml::from_str(fs::read_to_string(file)?.as_str())?,
+        None => MeshConfig::default(),
+    };
+
+    let mut mesh = SimulatedMesh::new(&config)?;
+
+    let (port, baud) = match (matches.value_of("port"), matches.value_of("baud")) {
+        (Some(port), Some(baudstr)) => (port, baudstr.parse::<u32>()?),
+        _ => bail!("arg failure"),
+    };
+
+    let ids: Vec<u8> = config.nodes.iter().map(|n| n.address).collect();
+
+    if matches.is_present("pty") {
+        let (mut master, slave) = TTYPort::pair()?;
+        master.set_timeout(POLL)?;
+        // hold on to the slave end; with no slave open, reads on the master
+        // fail with EIO between clients.
+        let ptyname = slave.name().ok_or("no pty name")?;
+        println!("simulating automatos {:?} on {}", ids, ptyname);
+        return serve(&mut mesh, &mut serial(Box::new(master)));
+    }
+
+    let port = serialport::new(port, baud)
+        .data_bits(serialport::DataBits::Eight)
+        .flow_control(serialport::FlowControl::None)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .timeout(POLL)
+        .open()?;
+
+    println!("simulating automatos {:?} on {:?}", ids, port.name());
+
+    serve(&mut mesh, &mut serial(port))
+}
+
+fn serial(port: Box<dyn SerialPort>) -> SerialTransport {
+    SerialTransport::new(
+        port,
+        ReadLimits {
+            deadline: POLL,
+            ..ReadLimits::default()
+        },
+    )
+}
+
+// pass messages between the port and the mesh until the port goes away.  on
+// this end of the link the frame id of a request is its destination.
+fn serve(mesh: &mut SimulatedMesh, transport: &mut dyn Transport) -> Result<(), Box<dyn Error>> {
+    // the mesh keeps its own time; here it follows the wall clock.
+    let mut last = Instant::now();
+    loop {
+        let now = Instant::now();
+        mesh.advance(now - last);
+        last = now;
+
+        while let Some((fromid, reply)) = mesh.poll() {
+            println!("{} <- {}", fromid, reply);
+            transport.send(&reply, fromid)?;
+        }
+
+        match transport.receive() {
+            Ok((toid, payload)) => {
+                println!("{} -> {}", toid, payload);
+                mesh.send(&payload, toid)?;
+            }
+            Err(e) => match e.kind {
+                serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut) => (),
+                serialport::ErrorKind::Io(std::io::ErrorKind::InvalidData) => {
+                    println!("bad message: {}", e);
+                }
+                _ => return Err(Box::new(e)),
+            },
+        }
+    }
+}
@@ -0,0 +1,61 @@
+// matosim --pty, talked to through the pty's path the way matomsg -p does.
+#![cfg(unix)]
+
+use automato::automatomsg::{Payload, PayloadEnum, ReadLimits};
+use automato::transport::{SerialTransport, Transport};
+use std::convert::TryFrom;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+// kill matosim when the test is done, pass or fail.
+struct Matosim(Child);
+
+impl Drop for Matosim {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn request(t: &mut SerialTransport, pe: PayloadEnum, toid: u8) -> (u8, PayloadEnum) {
+    t.send(&Payload::try_from(pe).unwrap(), toid).unwrap();
+    let (fromid, p) = t.receive().unwrap();
+    (fromid, PayloadEnum::from(p))
+}
+
+#[test]
+fn round_trip_over_pty() {
+    let mut sim = Matosim(
+        Command::new(env!("CARGO_BIN_EXE_matosim"))
+            .arg("--pty")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+
+    // "simulating automatos [1] on /dev/pts/N".  stdout stays open after, as
+    // matosim logs every message to it.
+    let mut stdout = BufReader::new(sim.0.stdout.take().unwrap());
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    let path = line.trim_end().rsplit(' ').next().unwrap().to_string();
+
+    // twice, so the pty has to outlast a client closing it.
+    for _ in 0..2 {
+        let port = serialport::new(&path, 115200)
+            .timeout(Duration::from_millis(1000))
+            .open()
+            .unwrap();
+        let mut t = SerialTransport::new(port, ReadLimits::default());
+        assert_eq!(
+            request(&mut t, PayloadEnum::PeReadhumidity, 1),
+            (1, PayloadEnum::PeReadhumidityreply(45.0))
+        );
+        assert_eq!(
+            request(&mut t, PayloadEnum::PeReadtemperature, 1),
+            (1, PayloadEnum::PeReadtemperaturereply(22.5))
+        );
+    }
+}
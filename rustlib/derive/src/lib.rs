@@ -0,0 +1,160 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Field, Fields, Lit, Meta, NestedMeta};
+
+// --------------------------------------------------------
+// #[derive(AutomatoMemory)] for a host side copy of a
+// sketch's ServerData.  fields go in the same order as the
+// C struct; see automato::memory for the runtime half.
+//
+//   #[automato(name = "checkInterval")]  name in memoryMap[]
+//   #[automato(unmapped)]                not in memoryMap[]
+// --------------------------------------------------------
+
+#[proc_macro_derive(AutomatoMemory, attributes(automato))]
+pub fn derive_automato_memory(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(&input) {
+        Ok(ts) => ts.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+struct MemField<'a> {
+    field: &'a Field,
+    name: String,
+    mapped: bool,
+}
+
+fn mem_field(field: &Field) -> syn::Result<MemField<'_>> {
+    let mut mf = MemField {
+        field,
+        name: field
+            .ident
+            .as_ref()
+            .map(|i| i.to_string())
+            .unwrap_or_default(),
+        mapped: true,
+    };
+    for attr in field.attrs.iter().filter(|a| a.path.is_ident("automato")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[automato(...)]")),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    match &nv.lit {
+                        Lit::Str(s) => mf.name = s.value(),
+                        lit => return Err(syn::Error::new_spanned(lit, "name should be a string")),
+                    }
+                }
+                NestedMeta::Meta(Meta::Path(p)) if p.is_ident("unmapped") => mf.mapped = false,
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        nested,
+                        "expected `name = \"...\"` or `unmapped`",
+                    ))
+                }
+            }
+        }
+    }
+    Ok(mf)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.generics,
+            "AutomatoMemory structs can't be generic",
+        ));
+    }
+    let fields = match &input.data {
+        Data::Struct(s) => match &s.fields {
+            Fields::Named(named) => named
+                .named
+                .iter()
+                .map(mem_field)
+                .collect::<syn::Result<Vec<MemField>>>()?,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "AutomatoMemory needs named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "AutomatoMemory is for structs",
+            ))
+        }
+    };
+
+    let mv = quote!(::automato::memory::MemoryValue);
+    let count = fields.len();
+    let sizes: Vec<TokenStream2> = fields
+        .iter()
+        .map(|f| {
+            let ty = &f.field.ty;
+            quote!((<#ty as #mv>::SIZE, <#ty as #mv>::ALIGN))
+        })
+        .collect();
+    let layout = quote!(::automato::memory::layout::<#count>([#(#sizes),*]));
+
+    let specs = fields.iter().enumerate().map(|(i, f)| {
+        let ty = &f.field.ty;
+        let name = &f.name;
+        let mapped = f.mapped;
+        quote! {
+            ::automato::memory::MemoryField {
+                name: #name,
+                offset: OFFSETS[#i] as u16,
+                length: <#ty as #mv>::SIZE as u8,
+                format: <#ty as #mv>::FORMAT,
+                mapped: #mapped,
+            }
+        }
+    });
+
+    let range = |i: usize, ty: &syn::Type| {
+        quote! {
+            Self::FIELDS[#i].offset as usize
+                ..Self::FIELDS[#i].offset as usize + <#ty as #mv>::SIZE
+        }
+    };
+    let encodes = fields.iter().enumerate().map(|(i, f)| {
+        let ty = &f.field.ty;
+        let fid = &f.field.ident;
+        let r = range(i, ty);
+        quote!(<#ty as #mv>::encode(&self.#fid, &mut bytes[#r]);)
+    });
+    let decodes = fields.iter().enumerate().map(|(i, f)| {
+        let ty = &f.field.ty;
+        let fid = &f.field.ident;
+        let r = range(i, ty);
+        quote!(#fid: <#ty as #mv>::decode(&bytes[#r]),)
+    });
+
+    Ok(quote! {
+        impl ::automato::memory::AutomatoMemory for #ident {
+            const FIELDS: &'static [::automato::memory::MemoryField] = {
+                const OFFSETS: [usize; #count] = #layout.0;
+                &[#(#specs),*]
+            };
+            const SIZE: usize = #layout.1;
+
+            fn encode(&self, bytes: &mut [u8]) {
+                #(#encodes)*
+            }
+
+            fn decode(bytes: &[u8]) -> Self {
+                #ident {
+                    #(#decodes)*
+                }
+            }
+        }
+    })
+}
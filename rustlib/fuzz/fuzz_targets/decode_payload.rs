@@ -0,0 +1,17 @@
+#![no_main]
+// raw payload bytes, as in a Msgbuf after the frame header.
+
+use automato::automatomsg as am;
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(p) = am::decode_payload(data) {
+        let encoded = am::encode_payload(&p);
+        assert_eq!(encoded[..], data[0..encoded.len()]);
+
+        let pe = am::PayloadEnum::from(p);
+        let p2 = am::Payload::try_from(pe).unwrap();
+        assert_eq!(am::encode_payload(&p2), encoded);
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+// json payloads, as POSTed to matoserver's /public endpoint.
+
+use automato::automatomsg as am;
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(pe) = serde_json::from_slice::<am::PayloadEnum>(data) {
+        if let Ok(p) = am::Payload::try_from(pe.clone()) {
+            let decoded = am::decode_payload(&am::encode_payload(&p)).unwrap();
+            assert_eq!(am::PayloadEnum::from(decoded), pe);
+        }
+    }
+    let _ = serde_json::from_slice::<am::Payload>(data);
+});
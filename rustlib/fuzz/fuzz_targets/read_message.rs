@@ -0,0 +1,16 @@
+#![no_main]
+// frames as they come from the serial port: 'm', from id, length, payload.
+
+use automato::automatomsg as am;
+use libfuzzer_sys::fuzz_target;
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    let mut port = std::io::Cursor::new(data);
+    let limits = am::ReadLimits::default();
+    // read every frame in the input, like the server reading a chattering port.
+    while let Ok(frame) = am::read_message(&mut port, &limits) {
+        let pe = am::PayloadEnum::from(frame.payload);
+        am::Payload::try_from(pe).unwrap();
+    }
+});
@@ -1,10 +1,15 @@
 use elm_rs::{Elm, ElmJson};
 use num_derive::{FromPrimitive, ToPrimitive};
+use num_traits::FromPrimitive;
 use serde::de::Deserializer;
 use serde::ser::Serializer;
 use serde::{Deserialize, Serialize};
 use serialport;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
 use std::mem::size_of;
+use std::time::{Duration, Instant};
 // --------------------------------------------------------
 // message structs.
 // --------------------------------------------------------
@@ -31,9 +36,30 @@ pub enum PayloadType {
     PtReadanalogreply = 16,
     PtReadfield = 17,
     PtReadfieldreply = 18,
+    PtSecure = 19,
+    PtFragment = 20,
+    PtCustom = 21,
+    PtWriteanalog = 22,
+    PtI2cTransfer = 23,
+    PtI2cTransferreply = 24,
+    PtSpiTransfer = 25,
+    PtSpiTransferreply = 26,
+    PtOnewirescan = 27,
+    PtOnewirescanreply = 28,
+    PtReadonewiretemperature = 29,
+    PtReadonewiretemperaturereply = 30,
+    PtServo = 31,
+    PtServoreply = 32,
+    PtPulsecounter = 33,
+    PtReadpulsecount = 34,
+    PtReadpulsecountreply = 35,
+    PtReboot = 36,
+    PtFactoryReset = 37,
+    PtPing = 38,
+    PtPong = 39,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Elm, ElmJson)]
 pub enum FieldFormat {
     FfString = 0, // called ff_char on the C++ side
     FfFloat = 1,
@@ -46,7 +72,26 @@ pub enum FieldFormat {
     FfOther = 8,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+impl TryFrom<u8> for FieldFormat {
+    type Error = BuildError;
+
+    fn try_from(format: u8) -> Result<FieldFormat, BuildError> {
+        match format {
+            0 => Ok(FieldFormat::FfString),
+            1 => Ok(FieldFormat::FfFloat),
+            2 => Ok(FieldFormat::FfUint8),
+            3 => Ok(FieldFormat::FfUint16),
+            4 => Ok(FieldFormat::FfUint32),
+            5 => Ok(FieldFormat::FfInt8),
+            6 => Ok(FieldFormat::FfInt16),
+            7 => Ok(FieldFormat::FfInt32),
+            8 => Ok(FieldFormat::FfOther),
+            _ => Err(BuildError::InvalidFieldFormat(format)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct RemoteInfo {
@@ -56,7 +101,7 @@ pub struct RemoteInfo {
     pub fieldcount: u16,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct Pinval {
@@ -64,7 +109,7 @@ pub struct Pinval {
     pub state: u8,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct AnalogPinval {
@@ -72,7 +117,86 @@ pub struct AnalogPinval {
     pub state: u16,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+// pwm output.  duty runs from 0, off, to 65535, always on.  a frequency of 0
+// leaves the pin's frequency as it was.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct Writeanalog {
+    pub pin: u8,
+    pub duty: u16,
+    pub frequency: u32,
+}
+
+// the Arduino Servo library's pulse widths for 0 and 180 degrees.
+pub const SERVO_MIN_PULSE: u16 = 544;
+pub const SERVO_MAX_PULSE: u16 = 2400;
+
+// move a servo to a pulse width in microseconds, at speed degrees a second.
+// a speed of 0 moves it as fast as it goes.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct Servo {
+    pub pin: u8,
+    pub pulse: u16,
+    pub speed: u16,
+}
+
+// the pulse width the servo was set to, after clamping.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct ServoReply {
+    pub pin: u8,
+    pub pulse: u16,
+}
+
+// count edges on a pin, like attachInterrupt().  edge is a PulseEdge;
+// PulseOff stops counting.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct PulseCounter {
+    pub pin: u8,
+    pub edge: u8,
+}
+
+// reset 1 starts the count over after reading it.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct ReadPulsecount {
+    pub pin: u8,
+    pub reset: u8,
+}
+
+// edges since the counter started or was reset, and pulses a second over
+// that time.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct PulseCount {
+    pub pin: u8,
+    pub count: u32,
+    pub frequency: f32,
+}
+
+// degrees to microseconds, the way Servo.write() does it.
+pub fn angle_to_pulse(angle: u8) -> u16 {
+    let angle = angle.min(180) as u32;
+    let range = (SERVO_MAX_PULSE - SERVO_MIN_PULSE) as u32;
+    SERVO_MIN_PULSE + (angle * range / 180) as u16
+}
+
+// and back again, like Servo.read().
+pub fn pulse_to_angle(pulse: u16) -> u8 {
+    let pulse = pulse.clamp(SERVO_MIN_PULSE, SERVO_MAX_PULSE) as u32;
+    let range = (SERVO_MAX_PULSE - SERVO_MIN_PULSE) as u32;
+    (((pulse - SERVO_MIN_PULSE as u32) * 180 + range / 2) / range) as u8
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct Pinmode {
@@ -80,7 +204,7 @@ pub struct Pinmode {
     pub mode: u8,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct Readmem {
@@ -88,12 +212,31 @@ pub struct Readmem {
     pub length: u8,
 }
 
+// messages to this address go to every automato in range.
+pub const RH_BROADCAST_ADDRESS: u8 = 0xff;
+
 pub const RH_RF95_MAX_MESSAGE_LEN: usize = 251; // 255 - 4.
 
+// radios with smaller messages; see fragment.rs.
+pub const RH_RF69_MAX_MESSAGE_LEN: usize = 60; // 64 - 4.
+pub const SX128X_MAX_MESSAGE_LEN: usize = 251; // 255 - 4, as for the RFM95.
+
 // #define MAX_WRITEMEM RH_RF95_MAX_MESSAGE_LEN - sizeof(u16) - sizeof(u8) - sizeof(u8)
-const MAX_WRITEMEM: usize = 247;
+pub const MAX_WRITEMEM: usize = 247;
 // #define MAX_READMEM RH_RF95_MAX_MESSAGE_LEN - sizeof(u8) - sizeof(u8)
-const MAX_READMEM: usize = 249;
+pub const MAX_READMEM: usize = 249;
+// type, counter, length.
+pub const MAX_SECURE: usize = 241;
+// type, id, index, count, length.
+pub const MAX_FRAGMENT: usize = 246;
+// type, subtype, length.
+pub const MAX_CUSTOM: usize = 248;
+// type, address, readlength or status, length.
+pub const MAX_I2C: usize = 247;
+// type, cspin, mode, clock, length.
+pub const MAX_SPI: usize = 243;
+// type, pin, count.  roms are 8 bytes.
+pub const MAX_ONEWIRE_ROMS: usize = 31;
 
 #[derive(Clone, Copy, Debug)]
 #[repr(C)]
@@ -103,7 +246,7 @@ pub struct ReadmemReplyUnion {
     pub data: [u8; MAX_READMEM],
 }
 
-#[derive(Clone, Debug, Elm, ElmJson, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
 pub struct ReadmemReply {
     pub data: Vec<u8>,
 }
@@ -117,20 +260,182 @@ pub struct WritememUnion {
     pub data: [u8; MAX_WRITEMEM],
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 pub struct Writemem {
     pub address: u16,
     pub data: Vec<u8>,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+// an encrypted payload; see secure.rs.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct SecureUnion {
+    pub counter: u64,
+    pub length: u8,
+    pub data: [u8; MAX_SECURE],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct Secure {
+    pub counter: u64,
+    pub data: Vec<u8>,
+}
+
+// a piece of a payload too big for the link's mtu; see fragment.rs.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct FragmentUnion {
+    pub id: u8,
+    pub index: u8,
+    pub count: u8,
+    pub length: u8,
+    pub data: [u8; MAX_FRAGMENT],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct Fragment {
+    pub id: u8,
+    pub index: u8,
+    pub count: u8,
+    pub data: Vec<u8>,
+}
+
+// a sketch specific command or reply; see custom.rs.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct CustomUnion {
+    pub subtype: u8,
+    pub length: u8,
+    pub data: [u8; MAX_CUSTOM],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct Custom {
+    pub subtype: u8,
+    pub data: Vec<u8>,
+}
+
+// write data to a 7 bit i2c address, then read readlength bytes back.  a
+// transfer with no data and nothing to read checks if anything's there.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct I2cTransferUnion {
+    pub address: u8,
+    pub readlength: u8,
+    pub length: u8,
+    pub data: [u8; MAX_I2C],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct I2cTransfer {
+    pub address: u8,
+    pub readlength: u8,
+    pub data: Vec<u8>,
+}
+
+// status is an I2cStatus, and data what was read.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct I2cTransferReplyUnion {
+    pub address: u8,
+    pub status: u8,
+    pub length: u8,
+    pub data: [u8; MAX_I2C],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct I2cTransferReply {
+    pub address: u8,
+    pub status: u8,
+    pub data: Vec<u8>,
+}
+
+// clock data out to the device selected by cspin, in spi mode 0 to 3 at
+// clock Hz.  the reply has what came back, a byte for each byte sent.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct SpiTransferUnion {
+    pub cspin: u8,
+    pub mode: u8,
+    pub clock: u32,
+    pub length: u8,
+    pub data: [u8; MAX_SPI],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct SpiTransfer {
+    pub cspin: u8,
+    pub mode: u8,
+    pub clock: u32,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct SpiTransferReplyUnion {
+    pub cspin: u8,
+    pub length: u8,
+    pub data: [u8; MAX_SPI],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct SpiTransferReply {
+    pub cspin: u8,
+    pub data: Vec<u8>,
+}
+
+// the rom ids of the devices on a onewire pin.  roms are in the order they
+// come off the bus, family code first and crc last, like the OneWire
+// library's DeviceAddress.
+#[derive(Clone, Copy, Debug)]
+#[repr(C)]
+#[repr(packed)]
+pub struct OnewireScanReplyUnion {
+    pub pin: u8,
+    pub count: u8,
+    pub roms: [[u8; 8]; MAX_ONEWIRE_ROMS],
+}
+
+#[derive(Clone, Debug, PartialEq, Elm, ElmJson, Serialize, Deserialize)]
+pub struct OnewireScanReply {
+    pub pin: u8,
+    pub roms: Vec<[u8; 8]>,
+}
+
+// a DS18B20 on a onewire pin.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct OnewireAddress {
+    pub pin: u8,
+    pub rom: [u8; 8],
+}
+
+// degrees C.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct OnewireTemperature {
+    pub pin: u8,
+    pub rom: [u8; 8],
+    pub temperature: f32,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct ReadField {
     pub index: u16,
 }
 
-#[derive(Clone, Copy, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 #[repr(C)]
 #[repr(packed)]
 pub struct ReadFieldReply {
@@ -138,7 +443,7 @@ pub struct ReadFieldReply {
     pub offset: u16,
     pub length: u8,
     pub format: u8,
-    pub name: [u8; 25],
+    pub name: [u8; MAX_FIELDNAME],
 }
 
 #[derive(Clone, Copy)]
@@ -154,6 +459,25 @@ pub union PayloadData {
     pub remoteinfo: RemoteInfo,
     pub readfield: ReadField,
     pub readfieldreply: ReadFieldReply,
+    pub secure: SecureUnion,
+    pub fragment: FragmentUnion,
+    pub custom: CustomUnion,
+    pub writeanalog: Writeanalog,
+    pub i2ctransfer: I2cTransferUnion,
+    pub i2ctransferreply: I2cTransferReplyUnion,
+    pub spitransfer: SpiTransferUnion,
+    pub spitransferreply: SpiTransferReplyUnion,
+    pub onewirescanreply: OnewireScanReplyUnion,
+    pub onewireaddress: OnewireAddress,
+    pub onewiretemperature: OnewireTemperature,
+    pub servo: Servo,
+    pub servoreply: ServoReply,
+    pub pulsecounter: PulseCounter,
+    pub readpulsecount: ReadPulsecount,
+    pub pulsecount: PulseCount,
+    pub token: u32,
+    pub nonce: u32,
+    pub pong: Pong,
     pub failcode: u8,
     pub pin: u8,
     pub f: f32,
@@ -168,7 +492,7 @@ pub struct Payload {
     pub data: PayloadData,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, Elm, ElmJson)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
 pub enum PayloadEnum {
     PeAck,
     PeFail(u8),
@@ -189,6 +513,27 @@ pub enum PayloadEnum {
     PeReadanalogreply(AnalogPinval),
     PeReadfield(ReadField),
     PeReadfieldreply(ReadFieldReply),
+    PeSecure(Secure),
+    PeFragment(Fragment),
+    PeCustom(Custom),
+    PeWriteanalog(Writeanalog),
+    PeI2cTransfer(I2cTransfer),
+    PeI2cTransferreply(I2cTransferReply),
+    PeSpiTransfer(SpiTransfer),
+    PeSpiTransferreply(SpiTransferReply),
+    PeOnewirescan(u8),
+    PeOnewirescanreply(OnewireScanReply),
+    PeReadonewiretemperature(OnewireAddress),
+    PeReadonewiretemperaturereply(OnewireTemperature),
+    PeServo(Servo),
+    PeServoreply(ServoReply),
+    PePulsecounter(PulseCounter),
+    PeReadpulsecount(ReadPulsecount),
+    PeReadpulsecountreply(PulseCount),
+    PeReboot(u32),
+    PeFactoryReset(u32),
+    PePing(u32),
+    PePong(Pong),
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -244,12 +589,93 @@ impl From<Payload> for PayloadEnum {
                 PayloadType::PtReadfieldreply => {
                     PayloadEnum::PeReadfieldreply(payload.data.readfieldreply)
                 }
+                PayloadType::PtSecure => PayloadEnum::PeSecure(Secure {
+                    counter: payload.data.secure.counter,
+                    data: payload.data.secure.data[0..payload.data.secure.length as usize].to_vec(),
+                }),
+                PayloadType::PtFragment => PayloadEnum::PeFragment(Fragment {
+                    id: payload.data.fragment.id,
+                    index: payload.data.fragment.index,
+                    count: payload.data.fragment.count,
+                    data: payload.data.fragment.data[0..payload.data.fragment.length as usize]
+                        .to_vec(),
+                }),
+                PayloadType::PtCustom => PayloadEnum::PeCustom(Custom {
+                    subtype: payload.data.custom.subtype,
+                    data: payload.data.custom.data[0..payload.data.custom.length as usize].to_vec(),
+                }),
+                PayloadType::PtWriteanalog => PayloadEnum::PeWriteanalog(payload.data.writeanalog),
+                PayloadType::PtI2cTransfer => PayloadEnum::PeI2cTransfer(I2cTransfer {
+                    address: payload.data.i2ctransfer.address,
+                    readlength: payload.data.i2ctransfer.readlength,
+                    data: payload.data.i2ctransfer.data
+                        [0..payload.data.i2ctransfer.length as usize]
+                        .to_vec(),
+                }),
+                PayloadType::PtI2cTransferreply => {
+                    PayloadEnum::PeI2cTransferreply(I2cTransferReply {
+                        address: payload.data.i2ctransferreply.address,
+                        status: payload.data.i2ctransferreply.status,
+                        data: payload.data.i2ctransferreply.data
+                            [0..payload.data.i2ctransferreply.length as usize]
+                            .to_vec(),
+                    })
+                }
+                PayloadType::PtSpiTransfer => PayloadEnum::PeSpiTransfer(SpiTransfer {
+                    cspin: payload.data.spitransfer.cspin,
+                    mode: payload.data.spitransfer.mode,
+                    clock: payload.data.spitransfer.clock,
+                    data: payload.data.spitransfer.data
+                        [0..payload.data.spitransfer.length as usize]
+                        .to_vec(),
+                }),
+                PayloadType::PtSpiTransferreply => {
+                    PayloadEnum::PeSpiTransferreply(SpiTransferReply {
+                        cspin: payload.data.spitransferreply.cspin,
+                        data: payload.data.spitransferreply.data
+                            [0..payload.data.spitransferreply.length as usize]
+                            .to_vec(),
+                    })
+                }
+                PayloadType::PtOnewirescan => PayloadEnum::PeOnewirescan(payload.data.pin),
+                PayloadType::PtOnewirescanreply => {
+                    PayloadEnum::PeOnewirescanreply(OnewireScanReply {
+                        pin: payload.data.onewirescanreply.pin,
+                        roms: payload.data.onewirescanreply.roms
+                            [0..payload.data.onewirescanreply.count as usize]
+                            .to_vec(),
+                    })
+                }
+                PayloadType::PtReadonewiretemperature => {
+                    PayloadEnum::PeReadonewiretemperature(payload.data.onewireaddress)
+                }
+                PayloadType::PtReadonewiretemperaturereply => {
+                    PayloadEnum::PeReadonewiretemperaturereply(payload.data.onewiretemperature)
+                }
+                PayloadType::PtServo => PayloadEnum::PeServo(payload.data.servo),
+                PayloadType::PtServoreply => PayloadEnum::PeServoreply(payload.data.servoreply),
+                PayloadType::PtPulsecounter => {
+                    PayloadEnum::PePulsecounter(payload.data.pulsecounter)
+                }
+                PayloadType::PtReadpulsecount => {
+                    PayloadEnum::PeReadpulsecount(payload.data.readpulsecount)
+                }
+                PayloadType::PtReadpulsecountreply => {
+                    PayloadEnum::PeReadpulsecountreply(payload.data.pulsecount)
+                }
+                PayloadType::PtReboot => PayloadEnum::PeReboot(payload.data.token),
+                PayloadType::PtFactoryReset => PayloadEnum::PeFactoryReset(payload.data.token),
+                PayloadType::PtPing => PayloadEnum::PePing(payload.data.nonce),
+                PayloadType::PtPong => PayloadEnum::PePong(payload.data.pong),
             }
         }
     }
 }
-impl From<PayloadEnum> for Payload {
-    fn from(pe: PayloadEnum) -> Payload {
+// PayloadEnum vecs can be any length, so check them against the fixed arrays.
+impl TryFrom<PayloadEnum> for Payload {
+    type Error = BuildError;
+
+    fn try_from(pe: PayloadEnum) -> Result<Payload, BuildError> {
         let mut payload = Payload {
             payload_type: PayloadType::PtAck,
             data: PayloadData { unit: () },
@@ -291,6 +717,7 @@ impl From<PayloadEnum> for Payload {
                 payload.data.readmem = readmem
             }
             PayloadEnum::PeReadmemreply(readmemreply) => {
+                check_length("readmemreply data", readmemreply.data.len(), MAX_READMEM)?;
                 payload.payload_type = PayloadType::PtReadmemreply;
                 let mut r = ReadmemReplyUnion {
                     length: readmemreply.data.len() as u8,
@@ -305,6 +732,7 @@ impl From<PayloadEnum> for Payload {
                 payload.data.readmemreply = r
             }
             PayloadEnum::PeWritemem(writemem) => {
+                check_length("writemem data", writemem.data.len(), MAX_WRITEMEM)?;
                 payload.payload_type = PayloadType::PtWritemem;
                 let mut w = WritememUnion {
                     address: writemem.address,
@@ -348,8 +776,154 @@ impl From<PayloadEnum> for Payload {
                 payload.payload_type = PayloadType::PtReadfieldreply;
                 payload.data.readfieldreply = readfieldreply
             }
+            PayloadEnum::PeSecure(secure) => {
+                check_length("secure data", secure.data.len(), MAX_SECURE)?;
+                payload.payload_type = PayloadType::PtSecure;
+                let mut s = SecureUnion {
+                    counter: secure.counter,
+                    length: secure.data.len() as u8,
+                    data: [0; MAX_SECURE],
+                };
+                s.data[0..secure.data.len()].copy_from_slice(&secure.data);
+                payload.data.secure = s
+            }
+            PayloadEnum::PeFragment(fragment) => {
+                check_length("fragment data", fragment.data.len(), MAX_FRAGMENT)?;
+                payload.payload_type = PayloadType::PtFragment;
+                let mut f = FragmentUnion {
+                    id: fragment.id,
+                    index: fragment.index,
+                    count: fragment.count,
+                    length: fragment.data.len() as u8,
+                    data: [0; MAX_FRAGMENT],
+                };
+                f.data[0..fragment.data.len()].copy_from_slice(&fragment.data);
+                payload.data.fragment = f
+            }
+            PayloadEnum::PeCustom(custom) => {
+                check_length("custom data", custom.data.len(), MAX_CUSTOM)?;
+                payload.payload_type = PayloadType::PtCustom;
+                let mut c = CustomUnion {
+                    subtype: custom.subtype,
+                    length: custom.data.len() as u8,
+                    data: [0; MAX_CUSTOM],
+                };
+                c.data[0..custom.data.len()].copy_from_slice(&custom.data);
+                payload.data.custom = c
+            }
+            PayloadEnum::PeWriteanalog(writeanalog) => {
+                payload.payload_type = PayloadType::PtWriteanalog;
+                payload.data.writeanalog = writeanalog
+            }
+            PayloadEnum::PeI2cTransfer(transfer) => {
+                check_length("i2c data", transfer.data.len(), MAX_I2C)?;
+                payload.payload_type = PayloadType::PtI2cTransfer;
+                let mut t = I2cTransferUnion {
+                    address: transfer.address,
+                    readlength: transfer.readlength,
+                    length: transfer.data.len() as u8,
+                    data: [0; MAX_I2C],
+                };
+                t.data[0..transfer.data.len()].copy_from_slice(&transfer.data);
+                payload.data.i2ctransfer = t
+            }
+            PayloadEnum::PeI2cTransferreply(reply) => {
+                check_length("i2c data", reply.data.len(), MAX_I2C)?;
+                payload.payload_type = PayloadType::PtI2cTransferreply;
+                let mut r = I2cTransferReplyUnion {
+                    address: reply.address,
+                    status: reply.status,
+                    length: reply.data.len() as u8,
+                    data: [0; MAX_I2C],
+                };
+                r.data[0..reply.data.len()].copy_from_slice(&reply.data);
+                payload.data.i2ctransferreply = r
+            }
+            PayloadEnum::PeSpiTransfer(transfer) => {
+                check_length("spi data", transfer.data.len(), MAX_SPI)?;
+                payload.payload_type = PayloadType::PtSpiTransfer;
+                let mut t = SpiTransferUnion {
+                    cspin: transfer.cspin,
+                    mode: transfer.mode,
+                    clock: transfer.clock,
+                    length: transfer.data.len() as u8,
+                    data: [0; MAX_SPI],
+                };
+                t.data[0..transfer.data.len()].copy_from_slice(&transfer.data);
+                payload.data.spitransfer = t
+            }
+            PayloadEnum::PeSpiTransferreply(reply) => {
+                check_length("spi data", reply.data.len(), MAX_SPI)?;
+                payload.payload_type = PayloadType::PtSpiTransferreply;
+                let mut r = SpiTransferReplyUnion {
+                    cspin: reply.cspin,
+                    length: reply.data.len() as u8,
+                    data: [0; MAX_SPI],
+                };
+                r.data[0..reply.data.len()].copy_from_slice(&reply.data);
+                payload.data.spitransferreply = r
+            }
+            PayloadEnum::PeOnewirescan(pin) => {
+                payload.payload_type = PayloadType::PtOnewirescan;
+                payload.data.pin = pin
+            }
+            PayloadEnum::PeOnewirescanreply(reply) => {
+                check_length("onewire roms", reply.roms.len(), MAX_ONEWIRE_ROMS)?;
+                payload.payload_type = PayloadType::PtOnewirescanreply;
+                let mut r = OnewireScanReplyUnion {
+                    pin: reply.pin,
+                    count: reply.roms.len() as u8,
+                    roms: [[0; 8]; MAX_ONEWIRE_ROMS],
+                };
+                r.roms[0..reply.roms.len()].copy_from_slice(&reply.roms);
+                payload.data.onewirescanreply = r
+            }
+            PayloadEnum::PeReadonewiretemperature(address) => {
+                payload.payload_type = PayloadType::PtReadonewiretemperature;
+                payload.data.onewireaddress = address
+            }
+            PayloadEnum::PeReadonewiretemperaturereply(temperature) => {
+                payload.payload_type = PayloadType::PtReadonewiretemperaturereply;
+                payload.data.onewiretemperature = temperature
+            }
+            PayloadEnum::PeServo(servo) => {
+                payload.payload_type = PayloadType::PtServo;
+                payload.data.servo = servo
+            }
+            PayloadEnum::PeServoreply(servoreply) => {
+                payload.payload_type = PayloadType::PtServoreply;
+                payload.data.servoreply = servoreply
+            }
+            PayloadEnum::PePulsecounter(pulsecounter) => {
+                payload.payload_type = PayloadType::PtPulsecounter;
+                payload.data.pulsecounter = pulsecounter
+            }
+            PayloadEnum::PeReadpulsecount(readpulsecount) => {
+                payload.payload_type = PayloadType::PtReadpulsecount;
+                payload.data.readpulsecount = readpulsecount
+            }
+            PayloadEnum::PeReadpulsecountreply(pulsecount) => {
+                payload.payload_type = PayloadType::PtReadpulsecountreply;
+                payload.data.pulsecount = pulsecount
+            }
+            PayloadEnum::PeReboot(token) => {
+                payload.payload_type = PayloadType::PtReboot;
+                payload.data.token = token
+            }
+            PayloadEnum::PeFactoryReset(token) => {
+                payload.payload_type = PayloadType::PtFactoryReset;
+                payload.data.token = token
+            }
+            PayloadEnum::PePing(nonce) => {
+                payload.payload_type = PayloadType::PtPing;
+                payload.data.nonce = nonce
+            }
+            PayloadEnum::PePong(pong) => {
+                payload.payload_type = PayloadType::PtPong;
+                payload.data.pong = pong
+            }
         }
-        payload
+        Ok(payload)
     }
 }
 
@@ -370,7 +944,7 @@ impl<'de> Deserialize<'de> for Payload {
     {
         let pe = PayloadEnum::deserialize(deserializer)?;
 
-        Ok(Payload::from(pe))
+        Payload::try_from(pe).map_err(serde::de::Error::custom)
     }
 }
 
@@ -400,118 +974,1236 @@ pub enum ResultCode {
     RcRhRouterErrorNoReply,
     RcRhRouterErrorUnableToDeliver,
     RcInvalidRhRouterError,
-    RcCount, // total number of ResultCodes.
+    RcNoDevice, // nothing answered at that address.
+    RcCount,    // total number of ResultCodes.
 }
 
-// --------------------------------------------------------
-// message fns.
-// --------------------------------------------------------
-
-pub fn payload_size(p: &Payload) -> usize {
-    match p.payload_type {
-        PayloadType::PtAck => size_of::<u8>(),
-        PayloadType::PtFail => size_of::<u8>() + size_of::<u8>(),
-        PayloadType::PtPinmode => size_of::<u8>() + size_of::<Pinmode>(),
-        PayloadType::PtReadpin => size_of::<u8>() + size_of::<u8>(),
-        PayloadType::PtReadpinreply => size_of::<u8>() + size_of::<Pinval>(),
-        PayloadType::PtWritepin => size_of::<u8>() + size_of::<Pinval>(),
-        PayloadType::PtReadanalog => size_of::<u8>() + size_of::<u8>(),
-        PayloadType::PtReadanalogreply => size_of::<u8>() + size_of::<AnalogPinval>(),
-        PayloadType::PtReadmem => size_of::<u8>() + size_of::<Readmem>(),
-        PayloadType::PtReadmemreply => unsafe {
-            size_of::<u8>() + size_of::<u8>() + p.data.readmemreply.length as usize
-        },
-        PayloadType::PtWritemem => unsafe {
-            size_of::<u8>() + size_of::<u16>() + size_of::<u8>() + p.data.writemem.length as usize
-        },
-        PayloadType::PtReadinfo => size_of::<u8>(),
-        PayloadType::PtReadinforeply => size_of::<u8>() + size_of::<RemoteInfo>(),
-        PayloadType::PtReadhumidity => size_of::<u8>(),
-        PayloadType::PtReadhumidityreply => size_of::<u8>() + size_of::<f32>(),
-        PayloadType::PtReadtemperature => size_of::<u8>(),
-        PayloadType::PtReadtemperaturereply => size_of::<u8>() + size_of::<f32>(),
-        PayloadType::PtReadfield => size_of::<u8>() + size_of::<ReadField>(),
-        PayloadType::PtReadfieldreply => size_of::<u8>() + size_of::<ReadFieldReply>(),
+impl fmt::Display for ResultCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
     }
 }
 
-pub fn setup_ack(p: &mut Payload) {
-    p.payload_type = PayloadType::PtAck;
-}
+impl std::error::Error for ResultCode {}
 
-pub fn setup_fail(p: &mut Payload, rc: ResultCode) {
-    p.payload_type = PayloadType::PtFail;
-    p.data.failcode = rc as u8;
+// what Wire.endTransmission() returns, as an i2c reply's status.
+#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+#[repr(u8)]
+pub enum I2cStatus {
+    IsOk = 0,
+    IsDataTooLong = 1,
+    IsAddressNack = 2,
+    IsDataNack = 3,
+    IsOther = 4,
+    IsTimeout = 5,
 }
 
-pub fn setup_pinmode(p: &mut Payload, pin: u8, mode: u8) {
-    p.payload_type = PayloadType::PtPinmode;
-    p.data.pinmode.pin = pin;
-    p.data.pinmode.mode = mode;
+impl I2cStatus {
+    pub fn from_u8(status: u8) -> Option<I2cStatus> {
+        match status {
+            0 => Some(I2cStatus::IsOk),
+            1 => Some(I2cStatus::IsDataTooLong),
+            2 => Some(I2cStatus::IsAddressNack),
+            3 => Some(I2cStatus::IsDataNack),
+            4 => Some(I2cStatus::IsOther),
+            5 => Some(I2cStatus::IsTimeout),
+            _ => None,
+        }
+    }
 }
 
-pub fn setup_readpin(p: &mut Payload, pin: u8) {
-    p.payload_type = PayloadType::PtReadpin;
-    p.data.pin = pin;
+// which edges a pulse counter counts.  the same as attachInterrupt()'s
+// RISING, FALLING and CHANGE, plus off.
+#[derive(Eq, PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+#[repr(u8)]
+pub enum PulseEdge {
+    PulseOff = 0,
+    PulseRising = 1,
+    PulseFalling = 2,
+    PulseChange = 3,
 }
 
-pub fn setup_readpinreply(p: &mut Payload, pin: u8, state: u8) {
-    p.payload_type = PayloadType::PtReadpinreply;
-    p.data.pinval.pin = pin;
-    p.data.pinval.state = state;
+impl PulseEdge {
+    pub fn from_u8(edge: u8) -> Option<PulseEdge> {
+        match edge {
+            0 => Some(PulseEdge::PulseOff),
+            1 => Some(PulseEdge::PulseRising),
+            2 => Some(PulseEdge::PulseFalling),
+            3 => Some(PulseEdge::PulseChange),
+            _ => None,
+        }
+    }
 }
 
-pub fn setup_writepin(p: &mut Payload, pin: u8, state: u8) {
-    p.payload_type = PayloadType::PtWritepin;
-    p.data.pinval.pin = pin;
-    p.data.pinval.state = state;
-}
+// reboot and factory reset only go through with these, so a corrupt or
+// mistyped message can't wipe a node.
+pub const REBOOT_TOKEN: u32 = u32::from_le_bytes(*b"BOOT");
+pub const FACTORY_RESET_TOKEN: u32 = u32::from_le_bytes(*b"WIPE");
 
-pub fn setup_readanalog(p: &mut Payload, pin: u8) {
-    p.payload_type = PayloadType::PtReadanalog;
-    p.data.pin = pin;
+// the answer to a ping: its nonce back, and the node's millis().
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Elm, ElmJson)]
+#[repr(C)]
+#[repr(packed)]
+pub struct Pong {
+    pub nonce: u32,
+    pub millis: u32,
 }
 
-pub fn setup_readanalogreply(p: &mut Payload, pin: u8, state: u16) {
-    p.payload_type = PayloadType::PtReadanalogreply;
-    p.data.analogpinval.pin = pin;
-    p.data.analogpinval.state = state;
-}
+// --------------------------------------------------------
+// payload builders.
+// --------------------------------------------------------
 
-pub fn setup_readmem(p: &mut Payload, address: u16, length: u8) {
-    p.payload_type = PayloadType::PtReadmem;
-    p.data.readmem.address = address;
-    p.data.readmem.length = length;
+pub const MAX_FIELDNAME: usize = 25;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    // a vec or string that won't fit its fixed size array.
+    TooLong {
+        what: &'static str,
+        length: usize,
+        max: usize,
+    },
+    InvalidFieldFormat(u8),
+    // spi modes are 0 to 3.
+    InvalidSpiMode(u8),
 }
 
-pub fn setup_readmemreply(p: &mut Payload, mem: &[u8]) -> ResultCode {
-    p.payload_type = PayloadType::PtReadmemreply;
-    if mem.len() <= MAX_READMEM {
-        p.data.readmemreply.length = mem.len() as u8;
-        unsafe {
-            p.data.readmemreply.data[0..mem.len()].copy_from_slice(&mem);
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::TooLong { what, length, max } => {
+                write!(f, "{} is {} bytes, max is {}", what, length, max)
+            }
+            BuildError::InvalidFieldFormat(format) => {
+                write!(f, "invalid field format: {}", format)
+            }
+            BuildError::InvalidSpiMode(mode) => write!(f, "invalid spi mode: {}", mode),
         }
-        ResultCode::RcOk
-    } else {
-        ResultCode::RcInvalidMemLength
     }
 }
 
-pub fn setup_writemem(p: &mut Payload, address: u16, mem: &[u8]) -> ResultCode {
-    p.payload_type = PayloadType::PtWritemem;
-    if mem.len() <= MAX_WRITEMEM {
-        p.data.writemem.address = address;
-        p.data.writemem.length = mem.len() as u8;
-        unsafe {
-            p.data.writemem.data[0..mem.len()].copy_from_slice(&mem);
+impl std::error::Error for BuildError {}
+
+// the code an automato would fail with, for the setup_* fns.
+impl From<BuildError> for ResultCode {
+    fn from(e: BuildError) -> ResultCode {
+        match e {
+            BuildError::TooLong { .. } => ResultCode::RcInvalidMemLength,
+            BuildError::InvalidFieldFormat(_) => ResultCode::RcInvalidMessageType,
+            BuildError::InvalidSpiMode(_) => ResultCode::RcInvalidMessageType,
         }
-        ResultCode::RcOk
+    }
+}
+
+fn check_spi_mode(mode: u8) -> Result<(), BuildError> {
+    if mode > 3 {
+        Err(BuildError::InvalidSpiMode(mode))
     } else {
-        ResultCode::RcInvalidMemLength
+        Ok(())
     }
 }
 
-pub fn setup_readinfo(p: &mut Payload) {
+fn check_length(what: &'static str, length: usize, max: usize) -> Result<(), BuildError> {
+    if length > max {
+        Err(BuildError::TooLong { what, length, max })
+    } else {
+        Ok(())
+    }
+}
+
+// constructors for each message.  the ones that can't be built from any
+// arguments return a Result.
+impl PayloadEnum {
+    pub fn ack() -> PayloadEnum {
+        PayloadEnum::PeAck
+    }
+
+    pub fn fail(rc: ResultCode) -> PayloadEnum {
+        PayloadEnum::PeFail(rc as u8)
+    }
+
+    pub fn pinmode(pin: u8, mode: u8) -> PayloadEnum {
+        PayloadEnum::PePinmode(Pinmode { pin, mode })
+    }
+
+    pub fn readpin(pin: u8) -> PayloadEnum {
+        PayloadEnum::PeReadpin(pin)
+    }
+
+    pub fn readpinreply(pin: u8, state: u8) -> PayloadEnum {
+        PayloadEnum::PeReadpinreply(Pinval { pin, state })
+    }
+
+    pub fn writepin(pin: u8, state: u8) -> PayloadEnum {
+        PayloadEnum::PeWritepin(Pinval { pin, state })
+    }
+
+    pub fn readanalog(pin: u8) -> PayloadEnum {
+        PayloadEnum::PeReadanalog(pin)
+    }
+
+    pub fn readanalogreply(pin: u8, state: u16) -> PayloadEnum {
+        PayloadEnum::PeReadanalogreply(AnalogPinval { pin, state })
+    }
+
+    // with no frequency the pin keeps the one it has.
+    pub fn writeanalog(pin: u8, duty: u16, frequency: Option<u32>) -> PayloadEnum {
+        PayloadEnum::PeWriteanalog(Writeanalog {
+            pin,
+            duty,
+            frequency: frequency.unwrap_or(0),
+        })
+    }
+
+    pub fn i2ctransfer(
+        address: u8,
+        data: &[u8],
+        readlength: u8,
+    ) -> Result<PayloadEnum, BuildError> {
+        check_length("i2c data", data.len(), MAX_I2C)?;
+        check_length("i2c readlength", readlength as usize, MAX_I2C)?;
+        Ok(PayloadEnum::PeI2cTransfer(I2cTransfer {
+            address,
+            readlength,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn i2ctransferreply(
+        address: u8,
+        status: I2cStatus,
+        data: &[u8],
+    ) -> Result<PayloadEnum, BuildError> {
+        check_length("i2c data", data.len(), MAX_I2C)?;
+        Ok(PayloadEnum::PeI2cTransferreply(I2cTransferReply {
+            address,
+            status: status as u8,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn spitransfer(
+        cspin: u8,
+        mode: u8,
+        clock: u32,
+        data: &[u8],
+    ) -> Result<PayloadEnum, BuildError> {
+        check_spi_mode(mode)?;
+        check_length("spi data", data.len(), MAX_SPI)?;
+        Ok(PayloadEnum::PeSpiTransfer(SpiTransfer {
+            cspin,
+            mode,
+            clock,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn spitransferreply(cspin: u8, data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("spi data", data.len(), MAX_SPI)?;
+        Ok(PayloadEnum::PeSpiTransferreply(SpiTransferReply {
+            cspin,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn onewirescan(pin: u8) -> PayloadEnum {
+        PayloadEnum::PeOnewirescan(pin)
+    }
+
+    pub fn onewirescanreply(pin: u8, roms: &[[u8; 8]]) -> Result<PayloadEnum, BuildError> {
+        check_length("onewire roms", roms.len(), MAX_ONEWIRE_ROMS)?;
+        Ok(PayloadEnum::PeOnewirescanreply(OnewireScanReply {
+            pin,
+            roms: roms.to_vec(),
+        }))
+    }
+
+    pub fn readonewiretemperature(pin: u8, rom: [u8; 8]) -> PayloadEnum {
+        PayloadEnum::PeReadonewiretemperature(OnewireAddress { pin, rom })
+    }
+
+    pub fn readonewiretemperaturereply(pin: u8, rom: [u8; 8], temperature: f32) -> PayloadEnum {
+        PayloadEnum::PeReadonewiretemperaturereply(OnewireTemperature {
+            pin,
+            rom,
+            temperature,
+        })
+    }
+
+    // pulse in microseconds, and no speed for as fast as it goes.
+    pub fn servo(pin: u8, pulse: u16, speed: Option<u16>) -> PayloadEnum {
+        PayloadEnum::PeServo(Servo {
+            pin,
+            pulse,
+            speed: speed.unwrap_or(0),
+        })
+    }
+
+    // 0 to 180 degrees.
+    pub fn servo_angle(pin: u8, angle: u8, speed: Option<u16>) -> PayloadEnum {
+        PayloadEnum::servo(pin, angle_to_pulse(angle), speed)
+    }
+
+    pub fn servoreply(pin: u8, pulse: u16) -> PayloadEnum {
+        PayloadEnum::PeServoreply(ServoReply { pin, pulse })
+    }
+
+    pub fn pulsecounter(pin: u8, edge: PulseEdge) -> PayloadEnum {
+        PayloadEnum::PePulsecounter(PulseCounter {
+            pin,
+            edge: edge as u8,
+        })
+    }
+
+    pub fn readpulsecount(pin: u8, reset: bool) -> PayloadEnum {
+        PayloadEnum::PeReadpulsecount(ReadPulsecount {
+            pin,
+            reset: reset as u8,
+        })
+    }
+
+    pub fn readpulsecountreply(pin: u8, count: u32, frequency: f32) -> PayloadEnum {
+        PayloadEnum::PeReadpulsecountreply(PulseCount {
+            pin,
+            count,
+            frequency,
+        })
+    }
+
+    pub fn reboot() -> PayloadEnum {
+        PayloadEnum::PeReboot(REBOOT_TOKEN)
+    }
+
+    pub fn factoryreset() -> PayloadEnum {
+        PayloadEnum::PeFactoryReset(FACTORY_RESET_TOKEN)
+    }
+
+    pub fn ping(nonce: u32) -> PayloadEnum {
+        PayloadEnum::PePing(nonce)
+    }
+
+    pub fn pong(nonce: u32, millis: u32) -> PayloadEnum {
+        PayloadEnum::PePong(Pong { nonce, millis })
+    }
+
+    pub fn readmem(address: u16, length: u8) -> Result<PayloadEnum, BuildError> {
+        check_length("readmem length", length as usize, MAX_READMEM)?;
+        Ok(PayloadEnum::PeReadmem(Readmem { address, length }))
+    }
+
+    pub fn readmemreply(data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("readmemreply data", data.len(), MAX_READMEM)?;
+        Ok(PayloadEnum::PeReadmemreply(ReadmemReply {
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn writemem(address: u16, data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("writemem data", data.len(), MAX_WRITEMEM)?;
+        Ok(PayloadEnum::PeWritemem(Writemem {
+            address,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn readinfo() -> PayloadEnum {
+        PayloadEnum::PeReadinfo
+    }
+
+    pub fn readinforeply(
+        protoversion: f32,
+        mac_address: u64,
+        datalen: u16,
+        fieldcount: u16,
+    ) -> PayloadEnum {
+        PayloadEnum::PeReadinforeply(RemoteInfo {
+            protoversion,
+            mac_address,
+            datalen,
+            fieldcount,
+        })
+    }
+
+    pub fn readhumidity() -> PayloadEnum {
+        PayloadEnum::PeReadhumidity
+    }
+
+    pub fn readhumidityreply(humidity: f32) -> PayloadEnum {
+        PayloadEnum::PeReadhumidityreply(humidity)
+    }
+
+    pub fn readtemperature() -> PayloadEnum {
+        PayloadEnum::PeReadtemperature
+    }
+
+    pub fn readtemperaturereply(temperature: f32) -> PayloadEnum {
+        PayloadEnum::PeReadtemperaturereply(temperature)
+    }
+
+    pub fn readfield(index: u16) -> PayloadEnum {
+        PayloadEnum::PeReadfield(ReadField { index })
+    }
+
+    pub fn readfieldreply(
+        index: u16,
+        offset: u16,
+        length: u8,
+        format: FieldFormat,
+        name: &str,
+    ) -> Result<PayloadEnum, BuildError> {
+        check_length("field name", name.len(), MAX_FIELDNAME)?;
+        let mut n = [0; MAX_FIELDNAME];
+        n[0..name.len()].copy_from_slice(name.as_bytes());
+        Ok(PayloadEnum::PeReadfieldreply(ReadFieldReply {
+            index,
+            offset,
+            length,
+            format: format as u8,
+            name: n,
+        }))
+    }
+
+    pub fn secure(counter: u64, data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("secure data", data.len(), MAX_SECURE)?;
+        Ok(PayloadEnum::PeSecure(Secure {
+            counter,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn fragment(id: u8, index: u8, count: u8, data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("fragment data", data.len(), MAX_FRAGMENT)?;
+        Ok(PayloadEnum::PeFragment(Fragment {
+            id,
+            index,
+            count,
+            data: data.to_vec(),
+        }))
+    }
+
+    pub fn custom(subtype: u8, data: &[u8]) -> Result<PayloadEnum, BuildError> {
+        check_length("custom data", data.len(), MAX_CUSTOM)?;
+        Ok(PayloadEnum::PeCustom(Custom {
+            subtype,
+            data: data.to_vec(),
+        }))
+    }
+
+    // check a PayloadEnum from elsewhere, like json from a client, the way
+    // the constructors do.
+    pub fn validate(&self) -> Result<(), BuildError> {
+        match self {
+            PayloadEnum::PeReadmem(rm) => {
+                check_length("readmem length", rm.length as usize, MAX_READMEM)
+            }
+            PayloadEnum::PeReadmemreply(rmr) => {
+                check_length("readmemreply data", rmr.data.len(), MAX_READMEM)
+            }
+            PayloadEnum::PeWritemem(wm) => {
+                check_length("writemem data", wm.data.len(), MAX_WRITEMEM)
+            }
+            PayloadEnum::PeSecure(s) => check_length("secure data", s.data.len(), MAX_SECURE),
+            PayloadEnum::PeFragment(f) => check_length("fragment data", f.data.len(), MAX_FRAGMENT),
+            PayloadEnum::PeCustom(c) => check_length("custom data", c.data.len(), MAX_CUSTOM),
+            PayloadEnum::PeI2cTransfer(t) => {
+                check_length("i2c data", t.data.len(), MAX_I2C)?;
+                check_length("i2c readlength", t.readlength as usize, MAX_I2C)
+            }
+            PayloadEnum::PeI2cTransferreply(r) => check_length("i2c data", r.data.len(), MAX_I2C),
+            PayloadEnum::PeSpiTransfer(t) => {
+                check_spi_mode(t.mode)?;
+                check_length("spi data", t.data.len(), MAX_SPI)
+            }
+            PayloadEnum::PeSpiTransferreply(r) => check_length("spi data", r.data.len(), MAX_SPI),
+            PayloadEnum::PeOnewirescanreply(r) => {
+                check_length("onewire roms", r.roms.len(), MAX_ONEWIRE_ROMS)
+            }
+            PayloadEnum::PeReadfieldreply(rfr) => FieldFormat::try_from(rfr.format).map(|_| ()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// --------------------------------------------------------
+// display.  "{}" is one line, for logs; "{:#}" is a field per
+// line, with memory data as a hex dump.
+// --------------------------------------------------------
+
+// memory data as hex, 16 bytes to a line, each line indented and prefixed
+// with its offset.
+pub fn hexdump(f: &mut fmt::Formatter<'_>, data: &[u8]) -> fmt::Result {
+    for (i, line) in data.chunks(16).enumerate() {
+        write!(f, "\n  {:04x}:", i * 16)?;
+        for b in line {
+            write!(f, " {:02x}", b)?;
+        }
+    }
+    Ok(())
+}
+
+fn failcode_string(code: u8) -> String {
+    match ResultCode::from_u8(code) {
+        Some(rc) => format!("{:?}", rc),
+        None => code.to_string(),
+    }
+}
+
+fn i2cstatus_string(status: u8) -> String {
+    match I2cStatus::from_u8(status) {
+        Some(s) => format!("{:?}", s),
+        None => status.to_string(),
+    }
+}
+
+fn edge_string(edge: u8) -> String {
+    match PulseEdge::from_u8(edge) {
+        Some(e) => format!("{:?}", e),
+        None => edge.to_string(),
+    }
+}
+
+// the way the linux w1 driver writes them, less the dash.
+pub fn rom_string(rom: &[u8; 8]) -> String {
+    hex::encode(rom)
+}
+
+fn format_string(format: u8) -> String {
+    match FieldFormat::try_from(format) {
+        Ok(ff) => format!("{:?}", ff),
+        Err(_) => format.to_string(),
+    }
+}
+
+// names are zero padded, and 25 chars long when they aren't.
+pub(crate) fn name_string(name: &[u8]) -> String {
+    let end = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+    String::from_utf8_lossy(&name[0..end]).to_string()
+}
+
+// message name, fields, and memory data if any.
+type Description<'a> = (&'static str, Vec<(&'static str, String)>, Option<&'a [u8]>);
+
+impl PayloadEnum {
+    fn describe(&self) -> Description<'_> {
+        match self {
+            PayloadEnum::PeAck => ("ack", vec![], None),
+            PayloadEnum::PeFail(code) => ("fail", vec![("code", failcode_string(*code))], None),
+            PayloadEnum::PePinmode(pm) => (
+                "pinmode",
+                vec![("pin", pm.pin.to_string()), ("mode", pm.mode.to_string())],
+                None,
+            ),
+            PayloadEnum::PeReadpin(pin) => ("readpin", vec![("pin", pin.to_string())], None),
+            PayloadEnum::PeReadpinreply(pv) => (
+                "readpinreply",
+                vec![("pin", pv.pin.to_string()), ("state", pv.state.to_string())],
+                None,
+            ),
+            PayloadEnum::PeWritepin(pv) => (
+                "writepin",
+                vec![("pin", pv.pin.to_string()), ("state", pv.state.to_string())],
+                None,
+            ),
+            PayloadEnum::PeReadanalog(pin) => ("readanalog", vec![("pin", pin.to_string())], None),
+            PayloadEnum::PeReadanalogreply(apv) => (
+                "readanalogreply",
+                vec![
+                    ("pin", apv.pin.to_string()),
+                    ("state", { apv.state }.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeWriteanalog(wa) => (
+                "writeanalog",
+                vec![
+                    ("pin", wa.pin.to_string()),
+                    ("duty", { wa.duty }.to_string()),
+                    (
+                        "frequency",
+                        match wa.frequency {
+                            0 => "unchanged".to_string(),
+                            f => f.to_string(),
+                        },
+                    ),
+                ],
+                None,
+            ),
+            PayloadEnum::PeI2cTransfer(t) => (
+                "i2ctransfer",
+                vec![
+                    ("address", format!("{:#04x}", t.address)),
+                    ("readlength", t.readlength.to_string()),
+                    ("length", t.data.len().to_string()),
+                ],
+                Some(&t.data),
+            ),
+            PayloadEnum::PeI2cTransferreply(r) => (
+                "i2ctransferreply",
+                vec![
+                    ("address", format!("{:#04x}", r.address)),
+                    ("status", i2cstatus_string(r.status)),
+                    ("length", r.data.len().to_string()),
+                ],
+                Some(&r.data),
+            ),
+            PayloadEnum::PeSpiTransfer(t) => (
+                "spitransfer",
+                vec![
+                    ("cspin", t.cspin.to_string()),
+                    ("mode", t.mode.to_string()),
+                    ("clock", { t.clock }.to_string()),
+                    ("length", t.data.len().to_string()),
+                ],
+                Some(&t.data),
+            ),
+            PayloadEnum::PeSpiTransferreply(r) => (
+                "spitransferreply",
+                vec![
+                    ("cspin", r.cspin.to_string()),
+                    ("length", r.data.len().to_string()),
+                ],
+                Some(&r.data),
+            ),
+            PayloadEnum::PeOnewirescan(pin) => {
+                ("onewirescan", vec![("pin", pin.to_string())], None)
+            }
+            PayloadEnum::PeOnewirescanreply(r) => (
+                "onewirescanreply",
+                vec![
+                    ("pin", r.pin.to_string()),
+                    ("count", r.roms.len().to_string()),
+                    (
+                        "roms",
+                        r.roms.iter().map(rom_string).collect::<Vec<_>>().join(","),
+                    ),
+                ],
+                None,
+            ),
+            PayloadEnum::PeReadonewiretemperature(a) => (
+                "readonewiretemperature",
+                vec![("pin", a.pin.to_string()), ("rom", rom_string(&{ a.rom }))],
+                None,
+            ),
+            PayloadEnum::PeReadonewiretemperaturereply(t) => (
+                "readonewiretemperaturereply",
+                vec![
+                    ("pin", t.pin.to_string()),
+                    ("rom", rom_string(&{ t.rom })),
+                    ("temperature", { t.temperature }.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeServo(s) => (
+                "servo",
+                vec![
+                    ("pin", s.pin.to_string()),
+                    ("pulse", { s.pulse }.to_string()),
+                    ("angle", pulse_to_angle(s.pulse).to_string()),
+                    (
+                        "speed",
+                        match s.speed {
+                            0 => "max".to_string(),
+                            speed => speed.to_string(),
+                        },
+                    ),
+                ],
+                None,
+            ),
+            PayloadEnum::PeServoreply(s) => (
+                "servoreply",
+                vec![
+                    ("pin", s.pin.to_string()),
+                    ("pulse", { s.pulse }.to_string()),
+                    ("angle", pulse_to_angle(s.pulse).to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PePulsecounter(pc) => (
+                "pulsecounter",
+                vec![("pin", pc.pin.to_string()), ("edge", edge_string(pc.edge))],
+                None,
+            ),
+            PayloadEnum::PeReadpulsecount(rp) => (
+                "readpulsecount",
+                vec![("pin", rp.pin.to_string()), ("reset", rp.reset.to_string())],
+                None,
+            ),
+            PayloadEnum::PeReadpulsecountreply(pc) => (
+                "readpulsecountreply",
+                vec![
+                    ("pin", pc.pin.to_string()),
+                    ("count", { pc.count }.to_string()),
+                    ("frequency", { pc.frequency }.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeReboot(token) => {
+                ("reboot", vec![("token", format!("{:#010x}", token))], None)
+            }
+            PayloadEnum::PeFactoryReset(token) => (
+                "factoryreset",
+                vec![("token", format!("{:#010x}", token))],
+                None,
+            ),
+            PayloadEnum::PePing(nonce) => {
+                ("ping", vec![("nonce", format!("{:#010x}", nonce))], None)
+            }
+            PayloadEnum::PePong(p) => (
+                "pong",
+                vec![
+                    ("nonce", format!("{:#010x}", { p.nonce })),
+                    ("millis", { p.millis }.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeReadmem(rm) => (
+                "readmem",
+                vec![
+                    ("address", { rm.address }.to_string()),
+                    ("length", rm.length.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeReadmemreply(rmr) => (
+                "readmemreply",
+                vec![("length", rmr.data.len().to_string())],
+                Some(&rmr.data),
+            ),
+            PayloadEnum::PeWritemem(wm) => (
+                "writemem",
+                vec![
+                    ("address", wm.address.to_string()),
+                    ("length", wm.data.len().to_string()),
+                ],
+                Some(&wm.data),
+            ),
+            PayloadEnum::PeReadinfo => ("readinfo", vec![], None),
+            PayloadEnum::PeReadinforeply(ri) => (
+                "readinforeply",
+                vec![
+                    ("protoversion", { ri.protoversion }.to_string()),
+                    ("mac", format!("{:012x}", { ri.mac_address })),
+                    ("datalen", { ri.datalen }.to_string()),
+                    ("fieldcount", { ri.fieldcount }.to_string()),
+                ],
+                None,
+            ),
+            PayloadEnum::PeReadhumidity => ("readhumidity", vec![], None),
+            PayloadEnum::PeReadhumidityreply(h) => {
+                ("readhumidityreply", vec![("humidity", h.to_string())], None)
+            }
+            PayloadEnum::PeReadtemperature => ("readtemperature", vec![], None),
+            PayloadEnum::PeReadtemperaturereply(t) => (
+                "readtemperaturereply",
+                vec![("temperature", t.to_string())],
+                None,
+            ),
+            PayloadEnum::PeReadfield(rf) => {
+                ("readfield", vec![("index", { rf.index }.to_string())], None)
+            }
+            PayloadEnum::PeReadfieldreply(rfr) => (
+                "readfieldreply",
+                vec![
+                    ("index", { rfr.index }.to_string()),
+                    ("offset", { rfr.offset }.to_string()),
+                    ("length", rfr.length.to_string()),
+                    ("format", format_string(rfr.format)),
+                    ("name", format!("{:?}", name_string(&rfr.name))),
+                ],
+                None,
+            ),
+            PayloadEnum::PeSecure(s) => (
+                "secure",
+                vec![
+                    ("counter", s.counter.to_string()),
+                    ("length", s.data.len().to_string()),
+                ],
+                Some(&s.data),
+            ),
+            PayloadEnum::PeFragment(fr) => (
+                "fragment",
+                vec![
+                    ("id", fr.id.to_string()),
+                    ("index", fr.index.to_string()),
+                    ("count", fr.count.to_string()),
+                    ("length", fr.data.len().to_string()),
+                ],
+                Some(&fr.data),
+            ),
+            PayloadEnum::PeCustom(c) => (
+                "custom",
+                vec![
+                    ("subtype", c.subtype.to_string()),
+                    ("length", c.data.len().to_string()),
+                ],
+                Some(&c.data),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for PayloadEnum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, fields, data) = self.describe();
+        write!(f, "{}", name)?;
+        if f.alternate() {
+            for (k, v) in fields {
+                write!(f, "\n  {}: {}", k, v)?;
+            }
+            if let Some(d) = data {
+                hexdump(f, d)?;
+            }
+        } else {
+            for (k, v) in fields {
+                write!(f, " {} {}", k, v)?;
+            }
+            if let Some(d) = data {
+                write!(f, " data {}", hex::encode(d))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&PayloadEnum::from(*self), f)
+    }
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&PayloadEnum::from(*self), f)
+    }
+}
+
+// --------------------------------------------------------
+// message fns.
+// --------------------------------------------------------
+
+pub fn payload_size(p: &Payload) -> usize {
+    match p.payload_type {
+        PayloadType::PtAck => size_of::<u8>(),
+        PayloadType::PtFail => size_of::<u8>() + size_of::<u8>(),
+        PayloadType::PtPinmode => size_of::<u8>() + size_of::<Pinmode>(),
+        PayloadType::PtReadpin => size_of::<u8>() + size_of::<u8>(),
+        PayloadType::PtReadpinreply => size_of::<u8>() + size_of::<Pinval>(),
+        PayloadType::PtWritepin => size_of::<u8>() + size_of::<Pinval>(),
+        PayloadType::PtReadanalog => size_of::<u8>() + size_of::<u8>(),
+        PayloadType::PtReadanalogreply => size_of::<u8>() + size_of::<AnalogPinval>(),
+        PayloadType::PtWriteanalog => size_of::<u8>() + size_of::<Writeanalog>(),
+        PayloadType::PtI2cTransfer => unsafe {
+            // type, address, readlength, length.
+            4 * size_of::<u8>() + p.data.i2ctransfer.length as usize
+        },
+        PayloadType::PtI2cTransferreply => unsafe {
+            // type, address, status, length.
+            4 * size_of::<u8>() + p.data.i2ctransferreply.length as usize
+        },
+        PayloadType::PtSpiTransfer => unsafe {
+            // type, cspin, mode, clock, length.
+            3 * size_of::<u8>()
+                + size_of::<u32>()
+                + size_of::<u8>()
+                + p.data.spitransfer.length as usize
+        },
+        PayloadType::PtSpiTransferreply => unsafe {
+            // type, cspin, length.
+            3 * size_of::<u8>() + p.data.spitransferreply.length as usize
+        },
+        PayloadType::PtOnewirescan => size_of::<u8>() + size_of::<u8>(),
+        PayloadType::PtOnewirescanreply => unsafe {
+            // type, pin, count.
+            3 * size_of::<u8>() + p.data.onewirescanreply.count as usize * size_of::<[u8; 8]>()
+        },
+        PayloadType::PtReadonewiretemperature => size_of::<u8>() + size_of::<OnewireAddress>(),
+        PayloadType::PtReadonewiretemperaturereply => {
+            size_of::<u8>() + size_of::<OnewireTemperature>()
+        }
+        PayloadType::PtServo => size_of::<u8>() + size_of::<Servo>(),
+        PayloadType::PtServoreply => size_of::<u8>() + size_of::<ServoReply>(),
+        PayloadType::PtPulsecounter => size_of::<u8>() + size_of::<PulseCounter>(),
+        PayloadType::PtReadpulsecount => size_of::<u8>() + size_of::<ReadPulsecount>(),
+        PayloadType::PtReadpulsecountreply => size_of::<u8>() + size_of::<PulseCount>(),
+        PayloadType::PtReboot => size_of::<u8>() + size_of::<u32>(),
+        PayloadType::PtFactoryReset => size_of::<u8>() + size_of::<u32>(),
+        PayloadType::PtPing => size_of::<u8>() + size_of::<u32>(),
+        PayloadType::PtPong => size_of::<u8>() + size_of::<Pong>(),
+        PayloadType::PtReadmem => size_of::<u8>() + size_of::<Readmem>(),
+        PayloadType::PtReadmemreply => unsafe {
+            size_of::<u8>() + size_of::<u8>() + p.data.readmemreply.length as usize
+        },
+        PayloadType::PtWritemem => unsafe {
+            size_of::<u8>() + size_of::<u16>() + size_of::<u8>() + p.data.writemem.length as usize
+        },
+        PayloadType::PtReadinfo => size_of::<u8>(),
+        PayloadType::PtReadinforeply => size_of::<u8>() + size_of::<RemoteInfo>(),
+        PayloadType::PtReadhumidity => size_of::<u8>(),
+        PayloadType::PtReadhumidityreply => size_of::<u8>() + size_of::<f32>(),
+        PayloadType::PtReadtemperature => size_of::<u8>(),
+        PayloadType::PtReadtemperaturereply => size_of::<u8>() + size_of::<f32>(),
+        PayloadType::PtReadfield => size_of::<u8>() + size_of::<ReadField>(),
+        PayloadType::PtReadfieldreply => size_of::<u8>() + size_of::<ReadFieldReply>(),
+        PayloadType::PtSecure => unsafe {
+            size_of::<u8>() + size_of::<u64>() + size_of::<u8>() + p.data.secure.length as usize
+        },
+        PayloadType::PtFragment => unsafe {
+            // type, id, index, count, length.
+            5 * size_of::<u8>() + p.data.fragment.length as usize
+        },
+        PayloadType::PtCustom => unsafe {
+            size_of::<u8>() + size_of::<u8>() + size_of::<u8>() + p.data.custom.length as usize
+        },
+    }
+}
+
+// the wire bytes of a payload, payload_size(p) long.
+pub fn encode_payload(p: &Payload) -> Vec<u8> {
+    let mb = Msgbuf { payload: *p };
+    unsafe { mb.buf[0..payload_size(p)].to_vec() }
+}
+
+// check the type byte and length fields of untrusted bytes before treating
+// them as a Payload.  bytes past the end of the payload are ignored.
+pub fn decode_payload(buf: &[u8]) -> Result<Payload, ResultCode> {
+    let payload_type = match buf.first().and_then(|b| PayloadType::from_u8(*b)) {
+        Some(pt) => pt,
+        None => return Err(ResultCode::RcInvalidMessageType),
+    };
+
+    // length bytes index the fixed size arrays.
+    match payload_type {
+        PayloadType::PtReadmemreply if buf.len() < 2 || buf[1] as usize > MAX_READMEM => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtWritemem if buf.len() < 4 || buf[3] as usize > MAX_WRITEMEM => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtSecure if buf.len() < 10 || buf[9] as usize > MAX_SECURE => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtFragment if buf.len() < 5 || buf[4] as usize > MAX_FRAGMENT => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtCustom if buf.len() < 3 || buf[2] as usize > MAX_CUSTOM => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtI2cTransfer | PayloadType::PtI2cTransferreply
+            if buf.len() < 4 || buf[3] as usize > MAX_I2C =>
+        {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtSpiTransfer if buf.len() < 8 || buf[7] as usize > MAX_SPI => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtSpiTransferreply if buf.len() < 3 || buf[2] as usize > MAX_SPI => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        PayloadType::PtOnewirescanreply if buf.len() < 3 || buf[2] as usize > MAX_ONEWIRE_ROMS => {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        _ => (),
+    }
+
+    let len = std::cmp::min(buf.len(), RH_RF95_MAX_MESSAGE_LEN);
+    let mut mb = Msgbuf {
+        buf: [0; RH_RF95_MAX_MESSAGE_LEN],
+    };
+    unsafe {
+        mb.buf[0..len].copy_from_slice(&buf[0..len]);
+        if payload_size(&mb.payload) > len {
+            return Err(ResultCode::RcInvalidMessageType);
+        }
+        Ok(mb.payload)
+    }
+}
+
+pub fn setup_ack(p: &mut Payload) {
+    p.payload_type = PayloadType::PtAck;
+}
+
+pub fn setup_fail(p: &mut Payload, rc: ResultCode) {
+    p.payload_type = PayloadType::PtFail;
+    p.data.failcode = rc as u8;
+}
+
+pub fn setup_pinmode(p: &mut Payload, pin: u8, mode: u8) {
+    p.payload_type = PayloadType::PtPinmode;
+    p.data.pinmode.pin = pin;
+    p.data.pinmode.mode = mode;
+}
+
+pub fn setup_readpin(p: &mut Payload, pin: u8) {
+    p.payload_type = PayloadType::PtReadpin;
+    p.data.pin = pin;
+}
+
+pub fn setup_readpinreply(p: &mut Payload, pin: u8, state: u8) {
+    p.payload_type = PayloadType::PtReadpinreply;
+    p.data.pinval.pin = pin;
+    p.data.pinval.state = state;
+}
+
+pub fn setup_writepin(p: &mut Payload, pin: u8, state: u8) {
+    p.payload_type = PayloadType::PtWritepin;
+    p.data.pinval.pin = pin;
+    p.data.pinval.state = state;
+}
+
+pub fn setup_readanalog(p: &mut Payload, pin: u8) {
+    p.payload_type = PayloadType::PtReadanalog;
+    p.data.pin = pin;
+}
+
+pub fn setup_readanalogreply(p: &mut Payload, pin: u8, state: u16) {
+    p.payload_type = PayloadType::PtReadanalogreply;
+    p.data.analogpinval.pin = pin;
+    p.data.analogpinval.state = state;
+}
+
+// a frequency of 0 leaves the pin's frequency as it was.
+pub fn setup_writeanalog(p: &mut Payload, pin: u8, duty: u16, frequency: u32) {
+    p.payload_type = PayloadType::PtWriteanalog;
+    p.data.writeanalog.pin = pin;
+    p.data.writeanalog.duty = duty;
+    p.data.writeanalog.frequency = frequency;
+}
+
+pub fn setup_readmem(p: &mut Payload, address: u16, length: u8) {
+    p.payload_type = PayloadType::PtReadmem;
+    p.data.readmem.address = address;
+    p.data.readmem.length = length;
+}
+
+// on failure p is left unchanged.
+pub fn setup_readmemreply(p: &mut Payload, mem: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("readmemreply data", mem.len(), MAX_READMEM) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtReadmemreply;
+    p.data.readmemreply.length = mem.len() as u8;
+    unsafe {
+        p.data.readmemreply.data[0..mem.len()].copy_from_slice(mem);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_writemem(p: &mut Payload, address: u16, mem: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("writemem data", mem.len(), MAX_WRITEMEM) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtWritemem;
+    p.data.writemem.address = address;
+    p.data.writemem.length = mem.len() as u8;
+    unsafe {
+        p.data.writemem.data[0..mem.len()].copy_from_slice(mem);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_secure(p: &mut Payload, counter: u64, data: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("secure data", data.len(), MAX_SECURE) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtSecure;
+    p.data.secure.counter = counter;
+    p.data.secure.length = data.len() as u8;
+    unsafe {
+        p.data.secure.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_fragment(p: &mut Payload, id: u8, index: u8, count: u8, data: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("fragment data", data.len(), MAX_FRAGMENT) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtFragment;
+    p.data.fragment.id = id;
+    p.data.fragment.index = index;
+    p.data.fragment.count = count;
+    p.data.fragment.length = data.len() as u8;
+    unsafe {
+        p.data.fragment.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_custom(p: &mut Payload, subtype: u8, data: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("custom data", data.len(), MAX_CUSTOM) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtCustom;
+    p.data.custom.subtype = subtype;
+    p.data.custom.length = data.len() as u8;
+    unsafe {
+        p.data.custom.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_i2ctransfer(p: &mut Payload, address: u8, data: &[u8], readlength: u8) -> ResultCode {
+    if let Err(e) = check_length("i2c data", data.len(), MAX_I2C) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtI2cTransfer;
+    p.data.i2ctransfer.address = address;
+    p.data.i2ctransfer.readlength = readlength;
+    p.data.i2ctransfer.length = data.len() as u8;
+    unsafe {
+        p.data.i2ctransfer.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_i2ctransferreply(
+    p: &mut Payload,
+    address: u8,
+    status: I2cStatus,
+    data: &[u8],
+) -> ResultCode {
+    if let Err(e) = check_length("i2c data", data.len(), MAX_I2C) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtI2cTransferreply;
+    p.data.i2ctransferreply.address = address;
+    p.data.i2ctransferreply.status = status as u8;
+    p.data.i2ctransferreply.length = data.len() as u8;
+    unsafe {
+        p.data.i2ctransferreply.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_spitransfer(
+    p: &mut Payload,
+    cspin: u8,
+    mode: u8,
+    clock: u32,
+    data: &[u8],
+) -> ResultCode {
+    if let Err(e) = check_spi_mode(mode).and(check_length("spi data", data.len(), MAX_SPI)) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtSpiTransfer;
+    p.data.spitransfer.cspin = cspin;
+    p.data.spitransfer.mode = mode;
+    p.data.spitransfer.clock = clock;
+    p.data.spitransfer.length = data.len() as u8;
+    unsafe {
+        p.data.spitransfer.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+// on failure p is left unchanged.
+pub fn setup_spitransferreply(p: &mut Payload, cspin: u8, data: &[u8]) -> ResultCode {
+    if let Err(e) = check_length("spi data", data.len(), MAX_SPI) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtSpiTransferreply;
+    p.data.spitransferreply.cspin = cspin;
+    p.data.spitransferreply.length = data.len() as u8;
+    unsafe {
+        p.data.spitransferreply.data[0..data.len()].copy_from_slice(data);
+    }
+    ResultCode::RcOk
+}
+
+pub fn setup_onewirescan(p: &mut Payload, pin: u8) {
+    p.payload_type = PayloadType::PtOnewirescan;
+    p.data.pin = pin;
+}
+
+// on failure p is left unchanged.
+pub fn setup_onewirescanreply(p: &mut Payload, pin: u8, roms: &[[u8; 8]]) -> ResultCode {
+    if let Err(e) = check_length("onewire roms", roms.len(), MAX_ONEWIRE_ROMS) {
+        return e.into();
+    }
+    p.payload_type = PayloadType::PtOnewirescanreply;
+    p.data.onewirescanreply.pin = pin;
+    p.data.onewirescanreply.count = roms.len() as u8;
+    unsafe {
+        p.data.onewirescanreply.roms[0..roms.len()].copy_from_slice(roms);
+    }
+    ResultCode::RcOk
+}
+
+pub fn setup_readonewiretemperature(p: &mut Payload, pin: u8, rom: [u8; 8]) {
+    p.payload_type = PayloadType::PtReadonewiretemperature;
+    p.data.onewireaddress.pin = pin;
+    p.data.onewireaddress.rom = rom;
+}
+
+pub fn setup_readonewiretemperaturereply(p: &mut Payload, pin: u8, rom: [u8; 8], temperature: f32) {
+    p.payload_type = PayloadType::PtReadonewiretemperaturereply;
+    p.data.onewiretemperature.pin = pin;
+    p.data.onewiretemperature.rom = rom;
+    p.data.onewiretemperature.temperature = temperature;
+}
+
+// a speed of 0 moves as fast as the servo goes.
+pub fn setup_servo(p: &mut Payload, pin: u8, pulse: u16, speed: u16) {
+    p.payload_type = PayloadType::PtServo;
+    p.data.servo.pin = pin;
+    p.data.servo.pulse = pulse;
+    p.data.servo.speed = speed;
+}
+
+pub fn setup_servoreply(p: &mut Payload, pin: u8, pulse: u16) {
+    p.payload_type = PayloadType::PtServoreply;
+    p.data.servoreply.pin = pin;
+    p.data.servoreply.pulse = pulse;
+}
+
+pub fn setup_pulsecounter(p: &mut Payload, pin: u8, edge: PulseEdge) {
+    p.payload_type = PayloadType::PtPulsecounter;
+    p.data.pulsecounter.pin = pin;
+    p.data.pulsecounter.edge = edge as u8;
+}
+
+pub fn setup_readpulsecount(p: &mut Payload, pin: u8, reset: bool) {
+    p.payload_type = PayloadType::PtReadpulsecount;
+    p.data.readpulsecount.pin = pin;
+    p.data.readpulsecount.reset = reset as u8;
+}
+
+pub fn setup_readpulsecountreply(p: &mut Payload, pin: u8, count: u32, frequency: f32) {
+    p.payload_type = PayloadType::PtReadpulsecountreply;
+    p.data.pulsecount.pin = pin;
+    p.data.pulsecount.count = count;
+    p.data.pulsecount.frequency = frequency;
+}
+
+pub fn setup_reboot(p: &mut Payload) {
+    p.payload_type = PayloadType::PtReboot;
+    p.data.token = REBOOT_TOKEN;
+}
+
+pub fn setup_factoryreset(p: &mut Payload) {
+    p.payload_type = PayloadType::PtFactoryReset;
+    p.data.token = FACTORY_RESET_TOKEN;
+}
+
+pub fn setup_ping(p: &mut Payload, nonce: u32) {
+    p.payload_type = PayloadType::PtPing;
+    p.data.nonce = nonce;
+}
+
+pub fn setup_pong(p: &mut Payload, nonce: u32, millis: u32) {
+    p.payload_type = PayloadType::PtPong;
+    p.data.pong.nonce = nonce;
+    p.data.pong.millis = millis;
+}
+
+pub fn setup_readinfo(p: &mut Payload) {
     p.payload_type = PayloadType::PtReadinfo;
 }
 
@@ -541,15 +2233,19 @@ pub fn setup_readfieldreply(
     length: u8,
     format: FieldFormat,
     name: &[u8],
-) {
+) -> ResultCode {
+    if let Err(e) = check_length("field name", name.len(), MAX_FIELDNAME) {
+        return e.into();
+    }
     p.payload_type = PayloadType::PtReadfieldreply;
     p.data.readfieldreply.index = index;
     p.data.readfieldreply.offset = offset;
     p.data.readfieldreply.length = length;
     p.data.readfieldreply.format = format as u8;
-    unsafe {
-        p.data.readfieldreply.name[0..name.len()].copy_from_slice(&name);
-    }
+    let mut n = [0; MAX_FIELDNAME];
+    n[0..name.len()].copy_from_slice(name);
+    p.data.readfieldreply.name = n;
+    ResultCode::RcOk
 }
 
 pub fn setup_readhumidity(p: &mut Payload) {
@@ -570,164 +2266,200 @@ pub fn setup_readtemperaturereply(p: &mut Payload, temperature: f32) {
     p.data.f = temperature;
 }
 
-pub unsafe fn print_payload(p: &Payload) {
-    println!("message payload");
+// --------------------------------------------------------
+// serial framing: 'm', id, payload length, payload.  the id
+// is the destination on the way to the gateway, and the
+// source on the way back.
+// --------------------------------------------------------
 
-    match p.payload_type {
-        PayloadType::PtAck => {
-            println!("PtAck");
-        }
-        PayloadType::PtFail => {
-            println!("PtFail; ");
-            println!("code: {}", { p.data.failcode });
-            // println!(resultString((ResultCode)p.data.failcode));
-        }
-        PayloadType::PtPinmode => {
-            println!("PtPinmode");
-            println!("pin: {}", { p.data.pinmode.pin });
-            println!("mode: {}", { p.data.pinmode.mode });
-        }
-        PayloadType::PtReadpin => {
-            println!("PtReadpin");
-            println!("pin: {}", { p.data.pin });
-        }
-        PayloadType::PtReadpinreply => {
-            println!("PtReadpinreply");
-            println!("pin: {}", { p.data.pinval.pin });
-            println!("state: {}", { p.data.pinval.state });
-        }
-        PayloadType::PtWritepin => {
-            println!("PtWritepin");
-            println!("pin: {}", { p.data.pinval.pin });
-            println!("state: {}", { p.data.pinval.state });
-        }
-        PayloadType::PtReadanalog => {
-            println!("PtReadanalog");
-            println!("pin: {}", { p.data.pin });
-        }
-        PayloadType::PtReadanalogreply => {
-            println!("PtReadanalogreply");
-            println!("pin: {}", { p.data.pin });
-            println!("state: {}", { p.data.analogpinval.state });
-        }
-        PayloadType::PtReadmem => {
-            println!("PtReadmem");
-            println!("address{}", { p.data.readmem.address });
-            println!("length{}", { p.data.readmem.length });
-        }
-        PayloadType::PtReadmemreply => {
-            println!("PtReadmemreply");
-            println!("length: {}", { p.data.readmemreply.length });
-            println!("values");
-            println!("offset: decimal, hex ");
-            for i in 0..p.data.readmemreply.length as usize {
-                let c = p.data.readmemreply.data[i];
-                println!("{}: {}, {:#X}", i, c, c);
-            }
-        }
-        PayloadType::PtWritemem => {
-            println!("PtWritemem");
-            println!("address {}", { p.data.writemem.address });
-            println!("length {}", { p.data.writemem.length });
-            println!("offset: decimal, hex ");
-            for i in 0..p.data.writemem.length as usize {
-                let c = p.data.writemem.data[i];
-                println!("{}: {}, {:#X}", i, c, c);
-            }
-        }
-        PayloadType::PtReadinfo => {
-            println!("PtReadinfo");
-        }
-        PayloadType::PtReadinforeply => {
-            println!("PtReadinforeply");
-            println!("protoversion:{}", { p.data.remoteinfo.protoversion });
-            println!("macAddress:{}", { p.data.remoteinfo.mac_address });
-            println!("datalen:{}", { p.data.remoteinfo.datalen });
-            println!("fieldcount:{}", { p.data.remoteinfo.fieldcount });
-        }
-        PayloadType::PtReadhumidity => {
-            println!("PtReadhumidity");
-        }
-        PayloadType::PtReadhumidityreply => {
-            println!("PtReadhumidityreply");
-            println!("humidity:{}", { p.data.f });
-        }
-        PayloadType::PtReadtemperature => {
-            println!("PtReadtemperature");
-        }
-        PayloadType::PtReadtemperaturereply => {
-            println!("PtReadtemperaturereply");
-            println!("temperature:{}", { p.data.f });
-        }
+// how long read_message keeps at a port, and how much noise it puts up with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadLimits {
+    // for a message to start.  the port's own timeout bounds each read, so
+    // the deadline can be overrun by up to that much.
+    pub deadline: Duration,
+    // once the 'm' is in, how long the rest of the frame gets, if that runs
+    // past the deadline.  a short deadline for polling doesn't cut off a
+    // frame that starts near the end of it.
+    pub frame: Duration,
+    // bytes discarded looking for the 'm' that starts a frame.
+    pub max_skip: usize,
+}
 
-        PayloadType::PtReadfield => {
-            println!("PtReadfield");
-            println!("index: {}", { p.data.readfieldreply.index });
-        }
-        PayloadType::PtReadfieldreply => {
-            println!("PtReadfieldreply");
-            println!("index: {}", { p.data.readfieldreply.index });
-            println!("offset: {}", { p.data.readfieldreply.offset });
-            println!("length: {}", p.data.readfieldreply.length);
-            println!("format: {:?}", p.data.readfieldreply.format);
-            print!("name: ");
-            for i in 0..p.data.readfieldreply.name.len() {
-                print!("{}", p.data.readfieldreply.name[i] as char);
-            }
-            println!("");
+impl Default for ReadLimits {
+    fn default() -> ReadLimits {
+        ReadLimits {
+            deadline: Duration::from_millis(1000),
+            // a full frame at 9600 baud, with room to spare.
+            frame: Duration::from_millis(500),
+            max_skip: 2 * RH_RF95_MAX_MESSAGE_LEN,
         }
     }
 }
 
-pub unsafe fn write_message(
-    port: &mut dyn serialport::SerialPort,
-    msg: &Msgbuf,
-    toid: u8,
-) -> Result<(), serialport::Error> {
-    let sz = payload_size(&msg.payload);
+#[derive(Clone, Copy, Debug)]
+pub struct Frame {
+    pub id: u8,
+    pub payload: Payload,
+    // noise bytes discarded before the frame started.
+    pub skipped: usize,
+}
 
-    port.write(&['m' as u8])?;
-    port.write(&[toid as u8])?;
-    port.write(&[sz as u8])?;
-    port.write(&msg.buf[0..sz + 1])?;
+fn invalid_data(description: String) -> serialport::Error {
+    serialport::Error::new(
+        serialport::ErrorKind::Io(std::io::ErrorKind::InvalidData),
+        description,
+    )
+}
 
-    Ok(())
+fn timed_out(description: String) -> serialport::Error {
+    serialport::Error::new(
+        serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut),
+        description,
+    )
 }
 
-pub unsafe fn read_message(
-    port: &mut dyn serialport::SerialPort,
-    msg: &mut Msgbuf,
-    fromid: &mut u8,
+// the bytes write_message puts on the wire: 'm', id, length, payload.
+pub fn frame_bytes(payload: &Payload, id: u8) -> Vec<u8> {
+    let mut frame = vec![b'm', id, payload_size(payload) as u8];
+    frame.extend(encode_payload(payload));
+    frame
+}
+
+pub fn write_message<W: Write + ?Sized>(
+    port: &mut W,
+    payload: &Payload,
+    toid: u8,
 ) -> Result<(), serialport::Error> {
-    let mut monobuf = [0; 1];
+    port.write_all(&frame_bytes(payload, toid))?;
+    Ok(())
+}
 
-    // println!("readexact 1 rs {:?}", port.read_exact(&mut monobuf));
-    // println!("readexact 1 {}", monobuf[0]);
-    // if monobuf[0] as char != 'm' {
-    //     return Ok(false);
-    // }
+// one byte, retrying reads that time out.  None once 'until' passes.
+fn read_byte<R: Read + ?Sized>(
+    port: &mut R,
+    until: Instant,
+) -> Result<Option<u8>, serialport::Error> {
+    let mut b = [0; 1];
     loop {
-        println!("readexact 1 rs {:?}", port.read_exact(&mut monobuf)?);
-        println!("readexact 1 {}", monobuf[0]);
-        // port.read_exact(&mut monobuf)?;
-        if monobuf[0] as char == 'm' {
-            break;
+        if Instant::now() > until {
+            return Ok(None);
+        }
+        match port.read(&mut b) {
+            Ok(0) => {
+                return Err(serialport::Error::new(
+                    serialport::ErrorKind::Io(std::io::ErrorKind::UnexpectedEof),
+                    "end of input",
+                ))
+            }
+            Ok(_) => return Ok(Some(b[0])),
+            Err(e) => match e.kind() {
+                std::io::ErrorKind::TimedOut
+                | std::io::ErrorKind::WouldBlock
+                | std::io::ErrorKind::Interrupted => (),
+                _ => return Err(e.into()),
+            },
         }
     }
+}
 
-    // port.read_exact(&mut monobuf)?;
-    println!("readexact 2 rs {:?}", port.read_exact(&mut monobuf)?);
-    println!("readexact 2 {}", monobuf[0]);
-    *fromid = monobuf[0];
+// read the bytes of the next frame into 'raw', from the 'm' on, skipping any
+// noise before it.  returns how much noise was skipped.  raw keeps whatever
+// was read even when this fails, so a misbehaving node can be captured.
+pub fn read_frame<R: Read + ?Sized>(
+    port: &mut R,
+    limits: &ReadLimits,
+    raw: &mut Vec<u8>,
+) -> Result<usize, serialport::Error> {
+    let until = Instant::now() + limits.deadline;
 
-    // port.read_exact(&mut monobuf)?;
-    println!("readexact 3 rs {:?}", port.read_exact(&mut monobuf));
-    println!("readexact 3 {}", monobuf[0]);
-    let sz = monobuf[0] as usize;
+    let mut skipped = 0;
+    loop {
+        match read_byte(port, until)? {
+            Some(b'm') => break,
+            Some(_) => {
+                skipped += 1;
+                if skipped > limits.max_skip {
+                    return Err(invalid_data(format!(
+                        "no message start in {} bytes of noise",
+                        skipped
+                    )));
+                }
+            }
+            None => {
+                return Err(timed_out(format!(
+                    "no message within {:?}, {} noise bytes skipped",
+                    limits.deadline, skipped
+                )))
+            }
+        }
+    }
+    raw.push(b'm');
 
-    if sz > 0 {
-        port.read_exact(&mut msg.buf[0..sz])?;
+    let until = until.max(Instant::now() + limits.frame);
+    frame_byte(port, until, raw)?;
+    let sz = frame_byte(port, until, raw)?;
+    if sz as usize > RH_RF95_MAX_MESSAGE_LEN {
+        return Err(invalid_data(format!(
+            "message length {} exceeds {}",
+            sz, RH_RF95_MAX_MESSAGE_LEN
+        )));
     }
 
-    Ok(())
+    for _ in 0..sz {
+        frame_byte(port, until, raw)?;
+    }
+    Ok(skipped)
+}
+
+// the next byte of the frame in 'raw'.
+fn frame_byte<R: Read + ?Sized>(
+    port: &mut R,
+    until: Instant,
+    raw: &mut Vec<u8>,
+) -> Result<u8, serialport::Error> {
+    match read_byte(port, until)? {
+        Some(b) => {
+            raw.push(b);
+            Ok(b)
+        }
+        None => Err(timed_out(format!(
+            "frame cut short after {} bytes",
+            raw.len()
+        ))),
+    }
+}
+
+// a frame as read_frame reads it, back to its id and payload.  the length
+// byte and payload are checked, since they come from the radio.
+pub fn decode_frame(raw: &[u8]) -> Result<(u8, Payload), serialport::Error> {
+    match raw {
+        [b'm', id, sz, payload @ ..] if *sz as usize == payload.len() => {
+            match decode_payload(payload) {
+                Ok(p) => Ok((*id, p)),
+                Err(rc) => Err(invalid_data(format!("invalid payload: {}", rc))),
+            }
+        }
+        [b'm', _, sz, payload @ ..] => Err(invalid_data(format!(
+            "message length {} but {} bytes",
+            sz,
+            payload.len()
+        ))),
+        _ => Err(invalid_data("not a frame".to_string())),
+    }
+}
+
+// read the next frame, skipping any noise before it.
+pub fn read_message<R: Read + ?Sized>(
+    port: &mut R,
+    limits: &ReadLimits,
+) -> Result<Frame, serialport::Error> {
+    let mut raw = Vec::new();
+    let skipped = read_frame(port, limits, &mut raw)?;
+    let (id, payload) = decode_frame(&raw)?;
+    Ok(Frame {
+        id,
+        payload,
+        skipped,
+    })
 }
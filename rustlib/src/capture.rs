@@ -0,0 +1,114 @@
+use crate::automatomsg::{decode_frame, hexdump};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::time::SystemTime;
+
+// --------------------------------------------------------
+// capture files: a record of the frames sent to and received
+// from the automato network, one json CaptureRecord per line.
+// --------------------------------------------------------
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Send,
+    Receive,
+    // nothing whole arrived before the timeout.
+    Timeout,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CaptureRecord {
+    // milliseconds since the unix epoch.
+    pub timestamp: u64,
+    pub direction: Direction,
+    // destination id for Send, source id for Receive, and for Timeout the
+    // destination of the last Send.
+    pub address: u8,
+    // the bytes as they went over the wire: 'm', id, length, payload.  for a
+    // Receive that doesn't decode, or a Timeout that cut a frame off,
+    // whatever arrived.
+    pub frame: Vec<u8>,
+}
+
+impl CaptureRecord {
+    pub fn now(direction: Direction, address: u8, frame: Vec<u8>) -> CaptureRecord {
+        CaptureRecord {
+            timestamp: now_millis(),
+            direction,
+            address,
+            frame,
+        }
+    }
+}
+
+// "{}" gives the timestamp, direction, address and payload on one line; "{:#}"
+// adds the payload fields and the raw frame bytes.
+impl fmt::Display for CaptureRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arrow = match self.direction {
+            Direction::Send => "->",
+            Direction::Receive => "<-",
+            Direction::Timeout => "..",
+        };
+        write!(f, "{} {} {} ", self.timestamp, arrow, self.address)?;
+        match (self.direction, decode_frame(&self.frame)) {
+            (Direction::Timeout, _) if self.frame.is_empty() => return write!(f, "timeout"),
+            (Direction::Timeout, _) if f.alternate() => write!(f, "timeout mid frame")?,
+            (Direction::Timeout, _) => {
+                return write!(f, "timeout mid frame {}", hex::encode(&self.frame))
+            }
+            (_, Ok((_, p))) => fmt::Display::fmt(&p, f)?,
+            (_, Err(e)) if f.alternate() => write!(f, "invalid frame ({})", e.description)?,
+            (_, Err(e)) => {
+                return write!(
+                    f,
+                    "invalid frame ({}) {}",
+                    e.description,
+                    hex::encode(&self.frame)
+                )
+            }
+        }
+        if f.alternate() {
+            write!(f, "\n  frame:")?;
+            hexdump(f, &self.frame)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub struct CaptureWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(out: W) -> CaptureWriter<W> {
+        CaptureWriter { out }
+    }
+
+    // records are flushed as they are written, so a crash doesn't lose the
+    // traffic leading up to it.
+    pub fn write_record(&mut self, record: &CaptureRecord) -> Result<(), std::io::Error> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()
+    }
+}
+
+pub fn read_capture<R: BufRead>(input: R) -> Result<Vec<CaptureRecord>, std::io::Error> {
+    let mut records = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            records.push(serde_json::from_str(line.as_str())?);
+        }
+    }
+    Ok(records)
+}
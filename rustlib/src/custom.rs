@@ -0,0 +1,308 @@
+use crate::automatomsg::{BuildError, Custom, PayloadEnum};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+
+// --------------------------------------------------------
+// PtCustom payloads carry a subtype and a body that only
+// the sketch knows the layout of.  a codec for a subtype
+// turns the body into json and back, so custom commands can
+// be built by name and show up decoded.
+// --------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CodecError {
+    // no codec by that name.
+    UnknownName(String),
+    // a subtype or name that's already registered.
+    Duplicate(String),
+    // body bytes or json the codec can't make sense of.
+    BadBody(String),
+    // json that isn't a PayloadEnum.
+    BadJson(String),
+    Build(BuildError),
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::UnknownName(name) => write!(f, "no codec named '{}'", name),
+            CodecError::Duplicate(what) => write!(f, "{} is already registered", what),
+            CodecError::BadBody(e) => write!(f, "bad custom body: {}", e),
+            CodecError::BadJson(e) => write!(f, "bad payload json: {}", e),
+            CodecError::Build(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<BuildError> for CodecError {
+    fn from(e: BuildError) -> CodecError {
+        CodecError::Build(e)
+    }
+}
+
+// the body of one subtype as a rust type.  to_body and from_body should
+// match the sketch's packed struct, little endian like the ESP32.
+pub trait CustomBody: Serialize + DeserializeOwned {
+    const SUBTYPE: u8;
+    const NAME: &'static str;
+
+    fn to_body(&self) -> Vec<u8>;
+    fn from_body(body: &[u8]) -> Result<Self, CodecError>;
+
+    fn to_payload(&self) -> Result<PayloadEnum, BuildError> {
+        PayloadEnum::custom(Self::SUBTYPE, &self.to_body())
+    }
+
+    // None if it's some other subtype.
+    fn from_payload(pe: &PayloadEnum) -> Option<Result<Self, CodecError>> {
+        match pe {
+            PayloadEnum::PeCustom(c) if c.subtype == Self::SUBTYPE => {
+                Some(Self::from_body(&c.data))
+            }
+            _ => None,
+        }
+    }
+}
+
+// a codec as the registry keeps it, working in json.
+pub trait CustomCodec: Send + Sync {
+    fn subtype(&self) -> u8;
+    fn name(&self) -> &str;
+    fn decode(&self, body: &[u8]) -> Result<Value, CodecError>;
+    fn encode(&self, body: &Value) -> Result<Vec<u8>, CodecError>;
+}
+
+struct Typed<T>(PhantomData<fn() -> T>);
+
+impl<T: CustomBody> CustomCodec for Typed<T> {
+    fn subtype(&self) -> u8 {
+        T::SUBTYPE
+    }
+
+    fn name(&self) -> &str {
+        T::NAME
+    }
+
+    fn decode(&self, body: &[u8]) -> Result<Value, CodecError> {
+        serde_json::to_value(T::from_body(body)?).map_err(|e| CodecError::BadBody(e.to_string()))
+    }
+
+    fn encode(&self, body: &Value) -> Result<Vec<u8>, CodecError> {
+        let t: T =
+            serde_json::from_value(body.clone()).map_err(|e| CodecError::BadBody(e.to_string()))?;
+        Ok(t.to_body())
+    }
+}
+
+#[derive(Default)]
+pub struct CodecRegistry {
+    codecs: BTreeMap<u8, Box<dyn CustomCodec>>,
+}
+
+impl CodecRegistry {
+    pub fn new() -> CodecRegistry {
+        CodecRegistry::default()
+    }
+
+    pub fn register<T: CustomBody + 'static>(&mut self) -> Result<(), CodecError> {
+        self.register_codec(Box::new(Typed::<T>(PhantomData)))
+    }
+
+    // for codecs that aren't a CustomBody.
+    pub fn register_codec(&mut self, codec: Box<dyn CustomCodec>) -> Result<(), CodecError> {
+        if self.codecs.contains_key(&codec.subtype()) {
+            return Err(CodecError::Duplicate(format!(
+                "subtype {}",
+                codec.subtype()
+            )));
+        }
+        if self.by_name(codec.name()).is_some() {
+            return Err(CodecError::Duplicate(codec.name().to_string()));
+        }
+        self.codecs.insert(codec.subtype(), codec);
+        Ok(())
+    }
+
+    pub fn codec(&self, subtype: u8) -> Option<&dyn CustomCodec> {
+        self.codecs.get(&subtype).map(|c| c.as_ref())
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&dyn CustomCodec> {
+        self.codecs
+            .values()
+            .find(|c| c.name() == name)
+            .map(|c| c.as_ref())
+    }
+
+    pub fn codecs(&self) -> impl Iterator<Item = &dyn CustomCodec> {
+        self.codecs.values().map(|c| c.as_ref())
+    }
+
+    // a custom payload from a codec name and json body.
+    pub fn build(&self, name: &str, body: &Value) -> Result<PayloadEnum, CodecError> {
+        let codec = self
+            .by_name(name)
+            .ok_or_else(|| CodecError::UnknownName(name.to_string()))?;
+        Ok(PayloadEnum::custom(codec.subtype(), &codec.encode(body)?)?)
+    }
+
+    // the codec name and json body, if there's a codec for the subtype.
+    pub fn decode(&self, c: &Custom) -> Option<Result<(&str, Value), CodecError>> {
+        self.codec(c.subtype)
+            .map(|codec| codec.decode(&c.data).map(|body| (codec.name(), body)))
+    }
+
+    // PayloadEnum json, plus "name" and "body" for custom payloads that
+    // decode.  the subtype and data are still there, so it reads back as a
+    // plain PayloadEnum.
+    pub fn to_json(&self, pe: &PayloadEnum) -> Result<Value, serde_json::Error> {
+        let mut v = serde_json::to_value(pe)?;
+        if let PayloadEnum::PeCustom(c) = pe {
+            if let (Some(Ok((name, body))), Some(obj)) = (
+                self.decode(c),
+                v.get_mut("PeCustom").and_then(Value::as_object_mut),
+            ) {
+                obj.insert("name".to_string(), Value::String(name.to_string()));
+                obj.insert("body".to_string(), body);
+            }
+        }
+        Ok(v)
+    }
+
+    // PayloadEnum json, where a custom payload can be given as "name" and
+    // "body" instead of "subtype" and "data".
+    pub fn from_json(&self, v: &Value) -> Result<PayloadEnum, CodecError> {
+        if let Some(c) = v.get("PeCustom") {
+            if let (None, Some(name), Some(body)) = (
+                c.get("data"),
+                c.get("name").and_then(Value::as_str),
+                c.get("body"),
+            ) {
+                return self.build(name, body);
+            }
+        }
+        serde_json::from_value(v.clone()).map_err(|e| CodecError::BadJson(e.to_string()))
+    }
+
+    // a payload that displays custom bodies decoded.
+    pub fn display<'a>(&'a self, pe: &'a PayloadEnum) -> Decoded<'a> {
+        Decoded {
+            registry: self,
+            payload: pe,
+        }
+    }
+}
+
+// like PayloadEnum's Display; "{:#}" is a field per line.
+pub struct Decoded<'a> {
+    registry: &'a CodecRegistry,
+    payload: &'a PayloadEnum,
+}
+
+impl fmt::Display for Decoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (c, name, body) = match self.payload {
+            PayloadEnum::PeCustom(c) => match self.registry.decode(c) {
+                Some(Ok((name, body))) => (c, name, body),
+                _ => return fmt::Display::fmt(self.payload, f),
+            },
+            _ => return fmt::Display::fmt(self.payload, f),
+        };
+
+        let mut fields = vec![
+            ("subtype".to_string(), c.subtype.to_string()),
+            ("name".to_string(), name.to_string()),
+        ];
+        match body {
+            Value::Object(m) => fields.extend(m.into_iter().map(|(k, v)| (k, v.to_string()))),
+            v => fields.push(("body".to_string(), v.to_string())),
+        }
+
+        write!(f, "custom")?;
+        for (k, v) in fields {
+            if f.alternate() {
+                write!(f, "\n  {}: {}", k, v)?;
+            } else {
+                write!(f, " {} {}", k, v)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn body_bytes<const N: usize>(name: &str, body: &[u8]) -> Result<[u8; N], CodecError> {
+    <[u8; N]>::try_from(body).map_err(|_| {
+        CodecError::BadBody(format!(
+            "{} body is {} bytes, should be {}",
+            name,
+            body.len(),
+            N
+        ))
+    })
+}
+
+// --------------------------------------------------------
+// codecs for the sketches' custom commands.  matomsg and
+// matoserver decode with these; add a sketch's codecs here.
+// --------------------------------------------------------
+
+// humidor-remote.ino: run the humidifier for a while.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HumidifyCycle {
+    pub seconds: u16,
+}
+
+impl CustomBody for HumidifyCycle {
+    const SUBTYPE: u8 = 1;
+    const NAME: &'static str = "humidifycycle";
+
+    fn to_body(&self) -> Vec<u8> {
+        self.seconds.to_le_bytes().to_vec()
+    }
+
+    fn from_body(body: &[u8]) -> Result<HumidifyCycle, CodecError> {
+        Ok(HumidifyCycle {
+            seconds: u16::from_le_bytes(body_bytes(Self::NAME, body)?),
+        })
+    }
+}
+
+// humidor-remote.ino: what the humidifier is up to.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HumidorStatus {
+    pub humidifying: bool,
+    pub cycles: u32,
+}
+
+impl CustomBody for HumidorStatus {
+    const SUBTYPE: u8 = 2;
+    const NAME: &'static str = "humidorstatus";
+
+    fn to_body(&self) -> Vec<u8> {
+        let mut body = vec![self.humidifying as u8];
+        body.extend_from_slice(&self.cycles.to_le_bytes());
+        body
+    }
+
+    fn from_body(body: &[u8]) -> Result<HumidorStatus, CodecError> {
+        let b: [u8; 5] = body_bytes(Self::NAME, body)?;
+        Ok(HumidorStatus {
+            humidifying: b[0] != 0,
+            cycles: u32::from_le_bytes([b[1], b[2], b[3], b[4]]),
+        })
+    }
+}
+
+pub fn sketch_codecs() -> CodecRegistry {
+    let mut r = CodecRegistry::new();
+    r.register::<HumidifyCycle>().expect("unique codec");
+    r.register::<HumidorStatus>().expect("unique codec");
+    r
+}
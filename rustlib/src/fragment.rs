@@ -0,0 +1,202 @@
+use crate::automatomsg::{
+    decode_payload, encode_payload, payload_size, Payload, PayloadEnum, PayloadType, ResultCode,
+    RH_BROADCAST_ADDRESS, RH_RF95_MAX_MESSAGE_LEN,
+};
+use crate::transport::Transport;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------
+// links with a smaller mtu than the RFM95's, like the
+// RFM69's 60 bytes.  payloads that don't fit are split into
+// PtFragment payloads, and put back together at the other
+// end.  payloads that do fit go as they are.
+// --------------------------------------------------------
+
+// type, id, index, count, length.
+pub const FRAGMENT_HEADER: usize = 5;
+// room for at least one byte of data per fragment.
+pub const MIN_MTU: usize = FRAGMENT_HEADER + 1;
+
+// the mtu of one node's radio, as it appears in config files.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeMtu {
+    pub id: u8,
+    pub mtu: usize,
+}
+
+// messages can't be bigger than the serial frames that carry them.
+pub fn check_mtu(mtu: usize) -> Result<(), serialport::Error> {
+    if (MIN_MTU..=RH_RF95_MAX_MESSAGE_LEN).contains(&mtu) {
+        Ok(())
+    } else {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::InvalidInput,
+            format!(
+                "mtu {} is outside {}-{}",
+                mtu, MIN_MTU, RH_RF95_MAX_MESSAGE_LEN
+            ),
+        ))
+    }
+}
+
+// p split into payloads of at most mtu bytes, all tagged with 'id'.  mtu
+// should pass check_mtu.
+pub fn fragment(p: &Payload, id: u8, mtu: usize) -> Vec<Payload> {
+    if payload_size(p) <= mtu {
+        return vec![*p];
+    }
+    let bytes = encode_payload(p);
+    let chunks: Vec<&[u8]> = bytes.chunks(mtu - FRAGMENT_HEADER).collect();
+    let count = chunks.len() as u8;
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(index, chunk)| {
+            // chunks are at most RH_RF95_MAX_MESSAGE_LEN - FRAGMENT_HEADER.
+            let pe = PayloadEnum::fragment(id, index as u8, count, chunk).expect("fragment fits");
+            Payload::try_from(pe).expect("fragment fits")
+        })
+        .collect()
+}
+
+// the fragments of one message so far.
+struct Partial {
+    id: u8,
+    pieces: Vec<Option<Vec<u8>>>,
+}
+
+// puts back together the fragments from one sender.  a fragment of a new
+// message drops what there is of the last one, so a lost fragment doesn't
+// hold on to memory.
+#[derive(Default)]
+pub struct Reassembly {
+    partial: Option<Partial>,
+}
+
+impl Reassembly {
+    // the whole payload once the last fragment arrives.  payloads that
+    // aren't fragments pass straight through.
+    pub fn add(&mut self, p: &Payload) -> Result<Option<Payload>, ResultCode> {
+        let f = match PayloadEnum::from(*p) {
+            PayloadEnum::PeFragment(f) => f,
+            _ => return Ok(Some(*p)),
+        };
+        if f.index >= f.count {
+            return Err(ResultCode::RcInvalidMessageType);
+        }
+
+        let mut partial = match self.partial.take() {
+            Some(partial) if partial.id == f.id && partial.pieces.len() == f.count as usize => {
+                partial
+            }
+            _ => Partial {
+                id: f.id,
+                pieces: vec![None; f.count as usize],
+            },
+        };
+        partial.pieces[f.index as usize] = Some(f.data);
+
+        let length: usize = partial.pieces.iter().flatten().map(|d| d.len()).sum();
+        if length > RH_RF95_MAX_MESSAGE_LEN {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        if partial.pieces.iter().any(|d| d.is_none()) {
+            self.partial = Some(partial);
+            return Ok(None);
+        }
+
+        let bytes: Vec<u8> = partial.pieces.into_iter().flatten().flatten().collect();
+        let whole = decode_payload(&bytes)?;
+        if payload_size(&whole) != bytes.len() {
+            return Err(ResultCode::RcInvalidMemLength);
+        }
+        if whole.payload_type == PayloadType::PtFragment {
+            return Err(ResultCode::RcInvalidMessageType);
+        }
+        Ok(Some(whole))
+    }
+}
+
+// --------------------------------------------------------
+// fragment payloads to nodes with a small mtu, and put
+// back together fragmented replies from any node.
+// --------------------------------------------------------
+
+pub struct FragmentingTransport {
+    inner: Box<dyn Transport>,
+    mtus: HashMap<u8, usize>,
+    next_id: u8,
+    reassembly: HashMap<u8, Reassembly>,
+}
+
+impl FragmentingTransport {
+    // nodes not in 'mtus' get whole payloads, as for the RFM95.
+    pub fn new(
+        inner: Box<dyn Transport>,
+        mtus: &[NodeMtu],
+    ) -> Result<FragmentingTransport, serialport::Error> {
+        let mut m = HashMap::new();
+        for nm in mtus {
+            check_mtu(nm.mtu)?;
+            m.insert(nm.id, nm.mtu);
+        }
+        Ok(FragmentingTransport {
+            inner,
+            mtus: m,
+            next_id: 0,
+            reassembly: HashMap::new(),
+        })
+    }
+}
+
+impl Transport for FragmentingTransport {
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        // a broadcast has to fit every radio.
+        let mtu = if toid == RH_BROADCAST_ADDRESS {
+            self.mtus.values().min().copied()
+        } else {
+            self.mtus.get(&toid).copied()
+        }
+        .unwrap_or(RH_RF95_MAX_MESSAGE_LEN);
+        let fragments = fragment(payload, self.next_id, mtu);
+        if fragments.len() > 1 {
+            self.next_id = self.next_id.wrapping_add(1);
+        }
+        for f in fragments.iter() {
+            self.inner.send(f, toid)?;
+        }
+        Ok(())
+    }
+
+    // keeps reading until a whole payload is in.
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        loop {
+            let (fromid, payload) = self.inner.receive()?;
+            match self.reassembly.entry(fromid).or_default().add(&payload) {
+                Ok(Some(p)) => return Ok((fromid, p)),
+                Ok(None) => (),
+                Err(rc) => {
+                    return Err(serialport::Error::new(
+                        serialport::ErrorKind::Io(std::io::ErrorKind::InvalidData),
+                        format!("bad fragment from {}: {}", fromid, rc),
+                    ))
+                }
+            }
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+}
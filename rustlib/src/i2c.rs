@@ -0,0 +1,141 @@
+use crate::automatomsg::{BuildError, I2cStatus, Payload, PayloadEnum};
+use crate::transport::Transport;
+use log::warn;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::ErrorKind;
+
+// --------------------------------------------------------
+// talking to i2c devices on a node's bus from the host,
+// for trying out a sensor before the sketch knows it.
+// --------------------------------------------------------
+
+// 0x00-0x07 and 0x78-0x7f are reserved.
+pub const FIRST_ADDRESS: u8 = 0x08;
+pub const LAST_ADDRESS: u8 = 0x77;
+
+// a lost request or reply shouldn't hide a device, so scan asks twice.
+pub const SCAN_TRIES: usize = 2;
+
+#[derive(Debug)]
+pub enum I2cError {
+    Transport(serialport::Error),
+    Build(BuildError),
+    // the node answered with PeFail.
+    Failed(u8),
+    UnexpectedReply(u8, PayloadEnum),
+    // the transfer got to the bus, which said no.  address, status.
+    Bus(u8, u8),
+}
+
+impl fmt::Display for I2cError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            I2cError::Transport(e) => write!(f, "{}", e),
+            I2cError::Build(e) => write!(f, "{}", e),
+            I2cError::Failed(code) => write!(f, "{}", PayloadEnum::PeFail(*code)),
+            I2cError::UnexpectedReply(id, pe) => {
+                write!(f, "unexpected reply from {}: {}", id, pe)
+            }
+            I2cError::Bus(address, status) => match I2cStatus::from_u8(*status) {
+                Some(s) => write!(f, "i2c {:#04x}: {:?}", address, s),
+                None => write!(f, "i2c {:#04x}: status {}", address, status),
+            },
+        }
+    }
+}
+
+impl std::error::Error for I2cError {}
+
+impl From<serialport::Error> for I2cError {
+    fn from(e: serialport::Error) -> I2cError {
+        I2cError::Transport(e)
+    }
+}
+
+impl From<BuildError> for I2cError {
+    fn from(e: BuildError) -> I2cError {
+        I2cError::Build(e)
+    }
+}
+
+// write data to the device at address on node id's bus, then read
+// readlength bytes.  returns the bus status along with what was read.
+fn request(
+    t: &mut dyn Transport,
+    id: u8,
+    address: u8,
+    data: &[u8],
+    readlength: u8,
+) -> Result<(u8, Vec<u8>), I2cError> {
+    let request = PayloadEnum::i2ctransfer(address, data, readlength)?;
+    t.send(&Payload::try_from(request)?, id)?;
+    loop {
+        let (fromid, reply) = t.receive()?;
+        match PayloadEnum::from(reply) {
+            PayloadEnum::PeI2cTransferreply(r) if fromid == id && r.address == address => {
+                return Ok((r.status, r.data))
+            }
+            // a late reply to an earlier request; ours may still be coming.
+            PayloadEnum::PeI2cTransferreply(r) if fromid == id => {
+                warn!("skipping stale i2c reply for {:#04x}", r.address)
+            }
+            PayloadEnum::PeFail(code) if fromid == id => return Err(I2cError::Failed(code)),
+            reply => return Err(I2cError::UnexpectedReply(fromid, reply)),
+        }
+    }
+}
+
+// like request(), but anything other than IsOk is an error.
+pub fn transfer(
+    t: &mut dyn Transport,
+    id: u8,
+    address: u8,
+    data: &[u8],
+    readlength: u8,
+) -> Result<Vec<u8>, I2cError> {
+    match request(t, id, address, data, readlength)? {
+        (status, read) if status == I2cStatus::IsOk as u8 => Ok(read),
+        (status, _) => Err(I2cError::Bus(address, status)),
+    }
+}
+
+// what a scan of a node's bus heard.
+#[derive(Debug, Default, PartialEq)]
+pub struct Scan {
+    // addresses that answer an empty write.
+    pub found: Vec<u8>,
+    // addresses with no clear answer after SCAN_TRIES: no reply, a PeFail,
+    // or a bus status other than ok or nack.  a device could be there.
+    pub missed: Vec<u8>,
+}
+
+// scan node id's bus, a transfer to each address.  only a broken transport
+// ends the scan early; on a lossy mesh some addresses end up in 'missed'.
+pub fn scan(t: &mut dyn Transport, id: u8) -> Result<Scan, I2cError> {
+    let mut scan = Scan::default();
+    for address in FIRST_ADDRESS..=LAST_ADDRESS {
+        let mut status = None;
+        for _ in 0..SCAN_TRIES {
+            match request(t, id, address, &[], 0) {
+                Ok((s, _)) if s == I2cStatus::IsOk as u8 || s == I2cStatus::IsAddressNack as u8 => {
+                    status = Some(s);
+                    break;
+                }
+                Ok((s, _)) => warn!("{}", I2cError::Bus(address, s)),
+                Err(I2cError::Transport(e))
+                    if e.kind == serialport::ErrorKind::Io(ErrorKind::TimedOut) => {}
+                Err(e @ I2cError::Failed(_)) | Err(e @ I2cError::UnexpectedReply(_, _)) => {
+                    warn!("i2c {:#04x}: {}", address, e)
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        match status {
+            Some(s) if s == I2cStatus::IsOk as u8 => scan.found.push(address),
+            Some(_) => (),
+            None => scan.missed.push(address),
+        }
+    }
+    Ok(scan)
+}
@@ -1 +1,12 @@
 pub mod automatomsg;
+pub mod capture;
+pub mod custom;
+pub mod fragment;
+pub mod i2c;
+pub mod memory;
+pub mod mesh;
+pub mod ping;
+pub mod schema;
+pub mod secure;
+pub mod simulator;
+pub mod transport;
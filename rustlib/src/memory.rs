@@ -0,0 +1,327 @@
+use crate::automatomsg::{
+    name_string, BuildError, FieldFormat, Payload, PayloadEnum, MAX_READMEM, MAX_WRITEMEM,
+};
+use crate::transport::Transport;
+use std::convert::TryFrom;
+use std::fmt;
+
+pub use automato_derive::AutomatoMemory;
+
+// --------------------------------------------------------
+// a host side copy of a sketch's ServerData.  derive
+// AutomatoMemory on a struct with the same fields in the
+// same order, and it's laid out the way the ESP32's C
+// compiler lays out ServerData.  verify() checks that
+// against the node's memoryMap[], through readfield.
+// --------------------------------------------------------
+
+#[derive(Debug)]
+pub enum MemoryError {
+    Transport(serialport::Error),
+    Build(BuildError),
+    // the node answered with PeFail.
+    Failed(u8),
+    UnexpectedReply(u8, PayloadEnum),
+    // the struct doesn't match the node's ServerData or memoryMap[].
+    Mismatch(String),
+}
+
+impl fmt::Display for MemoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryError::Transport(e) => write!(f, "{}", e),
+            MemoryError::Build(e) => write!(f, "{}", e),
+            MemoryError::Failed(code) => write!(f, "{}", PayloadEnum::PeFail(*code)),
+            MemoryError::UnexpectedReply(id, pe) => {
+                write!(f, "unexpected reply from {}: {}", id, pe)
+            }
+            MemoryError::Mismatch(e) => write!(f, "memory map mismatch: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+impl From<serialport::Error> for MemoryError {
+    fn from(e: serialport::Error) -> MemoryError {
+        MemoryError::Transport(e)
+    }
+}
+
+impl From<BuildError> for MemoryError {
+    fn from(e: BuildError) -> MemoryError {
+        MemoryError::Build(e)
+    }
+}
+
+// a field type, and how the ESP32 stores it.
+pub trait MemoryValue: Sized {
+    const FORMAT: FieldFormat;
+    const SIZE: usize;
+    const ALIGN: usize;
+
+    // bytes is SIZE long.
+    fn encode(&self, bytes: &mut [u8]);
+    fn decode(bytes: &[u8]) -> Self;
+}
+
+macro_rules! memory_value {
+    ($t:ty, $format:expr) => {
+        impl MemoryValue for $t {
+            const FORMAT: FieldFormat = $format;
+            const SIZE: usize = std::mem::size_of::<$t>();
+            const ALIGN: usize = std::mem::size_of::<$t>();
+
+            fn encode(&self, bytes: &mut [u8]) {
+                bytes.copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn decode(bytes: &[u8]) -> $t {
+                <$t>::from_le_bytes(bytes.try_into().expect("field length"))
+            }
+        }
+    };
+}
+
+memory_value!(f32, FieldFormat::FfFloat);
+memory_value!(u8, FieldFormat::FfUint8);
+memory_value!(u16, FieldFormat::FfUint16);
+memory_value!(u32, FieldFormat::FfUint32);
+memory_value!(i8, FieldFormat::FfInt8);
+memory_value!(i16, FieldFormat::FfInt16);
+memory_value!(i32, FieldFormat::FfInt32);
+// no field format for these, but they're in some ServerDatas.
+memory_value!(u64, FieldFormat::FfOther);
+memory_value!(i64, FieldFormat::FfOther);
+memory_value!(f64, FieldFormat::FfOther);
+
+impl<const N: usize> MemoryValue for [u8; N] {
+    const FORMAT: FieldFormat = FieldFormat::FfOther;
+    const SIZE: usize = N;
+    const ALIGN: usize = 1;
+
+    fn encode(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(self);
+    }
+
+    fn decode(bytes: &[u8]) -> [u8; N] {
+        bytes.try_into().expect("field length")
+    }
+}
+
+// a char[N], ff_char on the C++ side.  zero padded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FixedStr<const N: usize>(pub [u8; N]);
+
+impl<const N: usize> FixedStr<N> {
+    pub fn new(s: &str) -> Result<FixedStr<N>, BuildError> {
+        if s.len() > N {
+            return Err(BuildError::TooLong {
+                what: "string field",
+                length: s.len(),
+                max: N,
+            });
+        }
+        let mut b = [0; N];
+        b[0..s.len()].copy_from_slice(s.as_bytes());
+        Ok(FixedStr(b))
+    }
+}
+
+impl<const N: usize> Default for FixedStr<N> {
+    fn default() -> FixedStr<N> {
+        FixedStr([0; N])
+    }
+}
+
+impl<const N: usize> fmt::Display for FixedStr<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", name_string(&self.0))
+    }
+}
+
+impl<const N: usize> MemoryValue for FixedStr<N> {
+    const FORMAT: FieldFormat = FieldFormat::FfString;
+    const SIZE: usize = N;
+    const ALIGN: usize = 1;
+
+    fn encode(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&self.0);
+    }
+
+    fn decode(bytes: &[u8]) -> FixedStr<N> {
+        FixedStr(bytes.try_into().expect("field length"))
+    }
+}
+
+// where a field with this alignment goes, if the last one ended at 'end'.
+pub const fn align_up(end: usize, align: usize) -> usize {
+    end.div_ceil(align) * align
+}
+
+// offsets of fields with these sizes and alignments, and the size of the
+// struct, padded like C would pad it.
+pub const fn layout<const N: usize>(fields: [(usize, usize); N]) -> ([usize; N], usize) {
+    let mut offsets = [0; N];
+    let mut end: usize = 0;
+    let mut align: usize = 1;
+    let mut i = 0;
+    while i < N {
+        let (size, a) = fields[i];
+        assert!(size <= u8::MAX as usize, "fields are at most 255 bytes");
+        offsets[i] = align_up(end, a);
+        end = offsets[i] + size;
+        if a > align {
+            align = a;
+        }
+        i += 1;
+    }
+    assert!(
+        end <= u16::MAX as usize,
+        "ServerData is at most 65535 bytes"
+    );
+    (offsets, align_up(end, align))
+}
+
+// like map_field() in the sketches.  unmapped fields take up space in
+// ServerData but aren't in memoryMap[].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MemoryField {
+    pub name: &'static str,
+    pub offset: u16,
+    pub length: u8,
+    pub format: FieldFormat,
+    pub mapped: bool,
+}
+
+pub trait AutomatoMemory: Sized {
+    const FIELDS: &'static [MemoryField];
+    // sizeof(ServerData).
+    const SIZE: usize;
+
+    // bytes is SIZE long.
+    fn encode(&self, bytes: &mut [u8]);
+    fn decode(bytes: &[u8]) -> Self;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0; Self::SIZE];
+        self.encode(&mut bytes);
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, MemoryError> {
+        if bytes.len() != Self::SIZE {
+            return Err(MemoryError::Mismatch(format!(
+                "{} bytes, ServerData is {}",
+                bytes.len(),
+                Self::SIZE
+            )));
+        }
+        Ok(Self::decode(bytes))
+    }
+
+    // check the struct against the node's datalen and memoryMap[].  do this
+    // once before read() and write(), which take the layout on faith.
+    fn verify(t: &mut dyn Transport, id: u8) -> Result<(), MemoryError> {
+        verify_fields(t, id, Self::SIZE, Self::FIELDS)
+    }
+
+    // all of ServerData, in as few readmems as will fit.
+    fn read(t: &mut dyn Transport, id: u8) -> Result<Self, MemoryError> {
+        let mut bytes = Vec::with_capacity(Self::SIZE);
+        while bytes.len() < Self::SIZE {
+            let length = (Self::SIZE - bytes.len()).min(MAX_READMEM);
+            let request = PayloadEnum::readmem(bytes.len() as u16, length as u8)?;
+            match request_reply(t, id, request)? {
+                PayloadEnum::PeReadmemreply(rm) if rm.data.len() == length => {
+                    bytes.extend_from_slice(&rm.data)
+                }
+                reply => return Err(MemoryError::UnexpectedReply(id, reply)),
+            }
+        }
+        Self::from_bytes(&bytes)
+    }
+
+    // all of ServerData, unmapped fields and padding included.
+    fn write(&self, t: &mut dyn Transport, id: u8) -> Result<(), MemoryError> {
+        let bytes = self.to_bytes();
+        for (i, chunk) in bytes.chunks(MAX_WRITEMEM).enumerate() {
+            let request = PayloadEnum::writemem((i * MAX_WRITEMEM) as u16, chunk)?;
+            match request_reply(t, id, request)? {
+                PayloadEnum::PeAck => (),
+                reply => return Err(MemoryError::UnexpectedReply(id, reply)),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn request_reply(
+    t: &mut dyn Transport,
+    id: u8,
+    request: PayloadEnum,
+) -> Result<PayloadEnum, MemoryError> {
+    t.send(&Payload::try_from(request)?, id)?;
+    let (fromid, reply) = t.receive()?;
+    match PayloadEnum::from(reply) {
+        PayloadEnum::PeFail(code) if fromid == id => Err(MemoryError::Failed(code)),
+        reply if fromid == id => Ok(reply),
+        reply => Err(MemoryError::UnexpectedReply(fromid, reply)),
+    }
+}
+
+fn verify_fields(
+    t: &mut dyn Transport,
+    id: u8,
+    size: usize,
+    fields: &[MemoryField],
+) -> Result<(), MemoryError> {
+    let info = match request_reply(t, id, PayloadEnum::readinfo())? {
+        PayloadEnum::PeReadinforeply(info) => info,
+        reply => return Err(MemoryError::UnexpectedReply(id, reply)),
+    };
+    if info.datalen as usize != size {
+        return Err(MemoryError::Mismatch(format!(
+            "ServerData is {} bytes, struct is {}",
+            { info.datalen },
+            size
+        )));
+    }
+
+    let mut unseen: Vec<&MemoryField> = fields.iter().filter(|f| f.mapped).collect();
+    for index in 0..info.fieldcount {
+        let rfr = match request_reply(t, id, PayloadEnum::readfield(index))? {
+            PayloadEnum::PeReadfieldreply(rfr) => rfr,
+            reply => return Err(MemoryError::UnexpectedReply(id, reply)),
+        };
+        let name = name_string(&rfr.name);
+        let i = unseen.iter().position(|f| f.name == name).ok_or_else(|| {
+            MemoryError::Mismatch(format!("'{}' isn't a mapped field of the struct", name))
+        })?;
+        let f = unseen.remove(i);
+        if (rfr.offset, rfr.length, rfr.format) != (f.offset, f.length, f.format as u8) {
+            let format = match FieldFormat::try_from(rfr.format) {
+                Ok(ff) => format!("{:?}", ff),
+                Err(_) => rfr.format.to_string(),
+            };
+            return Err(MemoryError::Mismatch(format!(
+                "'{}' is offset {} length {} {} on the node, offset {} length {} {:?} in the struct",
+                name,
+                { rfr.offset },
+                rfr.length,
+                format,
+                f.offset,
+                f.length,
+                f.format
+            )));
+        }
+    }
+
+    match unseen.first() {
+        Some(f) => Err(MemoryError::Mismatch(format!(
+            "'{}' isn't in the node's memoryMap[]",
+            f.name
+        ))),
+        None => Ok(()),
+    }
+}
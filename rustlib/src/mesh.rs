@@ -0,0 +1,258 @@
+use crate::automatomsg::{
+    payload_size, Payload, PayloadEnum, ResultCode, RH_BROADCAST_ADDRESS, RH_RF95_MAX_MESSAGE_LEN,
+};
+use crate::fragment::{check_mtu, fragment, Reassembly};
+use crate::simulator::{NodeConfig, Simulator};
+use crate::transport::Transport;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------
+// a simulated lora gateway with several simulated automatos
+// behind it.  faults are drawn from a seeded rng, so a given
+// config and sequence of messages always plays out the same.
+// --------------------------------------------------------
+
+// per node link problems.  probabilities are 0.0 - 1.0.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Faults {
+    // delay before a reply arrives at the gateway.
+    pub latency_ms: u64,
+    // the request or reply vanishes; nothing comes back.
+    pub loss: f64,
+    // RHRouter failures, reported by the gateway as PtFail.
+    pub no_route: f64,
+    pub timeout: f64,
+    pub unable_to_deliver: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeshConfig {
+    pub seed: u64,
+    pub nodes: Vec<NodeConfig>,
+}
+
+impl Default for MeshConfig {
+    fn default() -> MeshConfig {
+        MeshConfig {
+            seed: 0,
+            nodes: vec![NodeConfig::default()],
+        }
+    }
+}
+
+struct MeshNode {
+    sim: Simulator,
+    faults: Faults,
+    mtu: usize,
+    reassembly: Reassembly,
+    next_id: u8,
+}
+
+pub struct SimulatedMesh {
+    nodes: Vec<MeshNode>,
+    rng: StdRng,
+    // replies in arrival order, with their arrival time on the mesh clock.
+    pending: VecDeque<(Duration, u8, Payload)>,
+    timeout: Duration,
+    // the mesh keeps its own time.  waiting on a reply moves the clock on
+    // rather than sleeping, so simulated runs are fast and repeatable.
+    epoch: Instant,
+    clock: Duration,
+}
+
+fn fail(rc: ResultCode) -> Payload {
+    Payload::try_from(PayloadEnum::fail(rc)).expect("fail fits")
+}
+
+impl SimulatedMesh {
+    pub fn new(config: &MeshConfig) -> Result<SimulatedMesh, Box<dyn Error>> {
+        let mut nodes: Vec<MeshNode> = Vec::new();
+        for nc in config.nodes.iter() {
+            if nodes.iter().any(|n| n.sim.address == nc.address) {
+                return Err(format!("duplicate node address: {}", nc.address).into());
+            }
+            if nc.address == RH_BROADCAST_ADDRESS {
+                return Err(format!("{} is the broadcast address", nc.address).into());
+            }
+            let mtu = nc.mtu.unwrap_or(RH_RF95_MAX_MESSAGE_LEN);
+            check_mtu(mtu)?;
+            nodes.push(MeshNode {
+                sim: Simulator::new(nc)?,
+                faults: nc.faults.clone(),
+                mtu,
+                reassembly: Reassembly::default(),
+                next_id: 0,
+            });
+        }
+        Ok(SimulatedMesh {
+            nodes,
+            rng: StdRng::seed_from_u64(config.seed),
+            pending: VecDeque::new(),
+            timeout: Duration::from_millis(1000),
+            epoch: Instant::now(),
+            clock: Duration::ZERO,
+        })
+    }
+
+    // a mesh from a toml MeshConfig file.
+    pub fn open(path: &Path) -> Result<SimulatedMesh, Box<dyn Error>> {
+        let config: MeshConfig = toml::from_str(fs::read_to_string(path)?.as_str())?;
+        SimulatedMesh::new(&config)
+    }
+
+    pub fn node(&mut self, address: u8) -> Option<&mut Simulator> {
+        self.nodes
+            .iter_mut()
+            .find(|n| n.sim.address == address)
+            .map(|n| &mut n.sim)
+    }
+
+    // what the gateway hears back for a message to 'toid', if anything.
+    // broadcasts aren't acknowledged, so their failures go unreported.
+    fn deliver(
+        &mut self,
+        request: &Payload,
+        toid: u8,
+        broadcast: bool,
+    ) -> Option<(Duration, Vec<Payload>)> {
+        let rng = &mut self.rng;
+        let node = match self.nodes.iter_mut().find(|n| n.sim.address == toid) {
+            Some(n) => n,
+            None => {
+                return Some((
+                    Duration::from_millis(0),
+                    vec![fail(ResultCode::RcRhRouterErrorNoRoute)],
+                ))
+            }
+        };
+        // too big for the node's radio to send at all.
+        if payload_size(request) > node.mtu {
+            if broadcast {
+                return None;
+            }
+            return Some((
+                Duration::from_millis(0),
+                vec![fail(ResultCode::RcRhRouterErrorInvalidLength)],
+            ));
+        }
+        let f = &node.faults;
+        let latency = Duration::from_millis(f.latency_ms);
+
+        // one draw per message, split between the failure kinds.
+        let roll: f64 = rng.gen();
+        let mut p = f.loss;
+        if roll < p {
+            return None;
+        }
+        for (chance, rc) in [
+            (f.no_route, ResultCode::RcRhRouterErrorNoRoute),
+            (f.timeout, ResultCode::RcRhRouterErrorTimeout),
+            (
+                f.unable_to_deliver,
+                ResultCode::RcRhRouterErrorUnableToDeliver,
+            ),
+        ] {
+            p += chance;
+            if roll < p {
+                if broadcast {
+                    return None;
+                }
+                return Some((latency, vec![fail(rc)]));
+            }
+        }
+
+        // the node only answers once it has every fragment.
+        let request = match node.reassembly.add(request) {
+            Ok(Some(p)) => p,
+            Ok(None) => return None,
+            Err(rc) => {
+                let reply = Payload::try_from(node.sim.fail(rc)).expect("fail fits");
+                return Some((latency, vec![reply]));
+            }
+        };
+        let reply = Payload::try_from(node.sim.handle(&PayloadEnum::from(request)))
+            .unwrap_or_else(|e| fail(e.into()));
+        let replies = fragment(&reply, node.next_id, node.mtu);
+        if replies.len() > 1 {
+            node.next_id = node.next_id.wrapping_add(1);
+        }
+        Some((latency, replies))
+    }
+
+    // move the mesh clock on, as when serving in real time.
+    pub fn advance(&mut self, d: Duration) {
+        self.clock += d;
+    }
+
+    // a reply that has already arrived, without waiting.
+    pub fn poll(&mut self) -> Option<(u8, Payload)> {
+        match self.pending.front() {
+            Some((at, _, _)) if *at <= self.clock => {
+                self.pending.pop_front().map(|(_, id, p)| (id, p))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Transport for SimulatedMesh {
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        let broadcast = toid == RH_BROADCAST_ADDRESS;
+        let ids: Vec<u8> = if broadcast {
+            self.nodes.iter().map(|n| n.sim.address).collect()
+        } else {
+            vec![toid]
+        };
+        for id in ids {
+            if let Some((latency, replies)) = self.deliver(payload, id, broadcast) {
+                let at = self.clock + latency;
+                for reply in replies {
+                    // keep arrival order when latencies differ.
+                    let idx = self.pending.partition_point(|(t, _, _)| *t <= at);
+                    self.pending.insert(idx, (at, id, reply));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // replies slower than the timeout stay queued, and turn up on a later receive.
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        match self.pending.front() {
+            Some((at, _, _)) if *at <= self.clock + self.timeout => {
+                let (at, id, p) = self.pending.pop_front().unwrap();
+                self.clock = self.clock.max(at);
+                Ok((id, p))
+            }
+            _ => {
+                self.clock += self.timeout;
+                Err(serialport::Error::new(
+                    serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut),
+                    "Operation timed out",
+                ))
+            }
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn now(&self) -> Instant {
+        self.epoch + self.clock
+    }
+}
@@ -0,0 +1,98 @@
+use crate::automatomsg::{BuildError, Payload, PayloadEnum, Pong};
+use crate::transport::Transport;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::ErrorKind;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------
+// the cheapest request there is, for checking a node is
+// up and how long the mesh takes to get there and back.
+// --------------------------------------------------------
+
+#[derive(Debug)]
+pub enum PingError {
+    Transport(serialport::Error),
+    Build(BuildError),
+    // the node answered with PeFail, and no pong came.
+    Failed(u8),
+}
+
+impl fmt::Display for PingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PingError::Transport(e) => write!(f, "{}", e),
+            PingError::Build(e) => write!(f, "{}", e),
+            PingError::Failed(code) => write!(f, "{}", PayloadEnum::PeFail(*code)),
+        }
+    }
+}
+
+impl std::error::Error for PingError {}
+
+impl From<serialport::Error> for PingError {
+    fn from(e: serialport::Error) -> PingError {
+        PingError::Transport(e)
+    }
+}
+
+impl From<BuildError> for PingError {
+    fn from(e: BuildError) -> PingError {
+        PingError::Build(e)
+    }
+}
+
+// ping node id.  returns the round trip time along with the pong, which has
+// the node's millis().  anything else that arrives within the transport's
+// timeout is passed over, like the late pong of an earlier ping.  the
+// transport's timeout is left as it was.
+pub fn ping(t: &mut dyn Transport, id: u8) -> Result<(Duration, Pong), PingError> {
+    // a fresh nonce, so a late pong from an earlier ping doesn't count.
+    let nonce = rand::random::<u32>();
+    let timeout = t.timeout();
+    let start = t.now();
+    t.send(&Payload::try_from(PayloadEnum::ping(nonce))?, id)?;
+    let pong = await_pong(t, id, nonce, start + timeout);
+    let rtt = t.now() - start;
+    t.set_timeout(timeout)?;
+    Ok((rtt, pong?))
+}
+
+fn await_pong(
+    t: &mut dyn Transport,
+    id: u8,
+    nonce: u32,
+    deadline: Instant,
+) -> Result<Pong, PingError> {
+    // a fail from the node only counts if its pong never comes; it may be
+    // left over from an earlier request.
+    let mut failed = None;
+    // the first receive gets the transport's own timeout, so there's always
+    // one, even when the timeout is zero, as for a replay.
+    loop {
+        match t.receive() {
+            Ok((fromid, reply)) => match PayloadEnum::from(reply) {
+                PayloadEnum::PePong(pong) if fromid == id && pong.nonce == nonce => {
+                    return Ok(pong)
+                }
+                PayloadEnum::PeFail(code) if fromid == id => failed = Some(code),
+                _ => (),
+            },
+            Err(e) if e.kind == serialport::ErrorKind::Io(ErrorKind::TimedOut) => break,
+            Err(e) if e.kind == serialport::ErrorKind::Io(ErrorKind::InvalidData) => (),
+            Err(e) => return Err(e.into()),
+        }
+        let left = deadline.saturating_duration_since(t.now());
+        if left.is_zero() {
+            break;
+        }
+        t.set_timeout(left)?;
+    }
+    Err(match failed {
+        Some(code) => PingError::Failed(code),
+        None => PingError::Transport(serialport::Error::new(
+            serialport::ErrorKind::Io(ErrorKind::TimedOut),
+            format!("no pong from {}", id),
+        )),
+    })
+}
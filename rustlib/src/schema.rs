@@ -0,0 +1,336 @@
+use crate::automatomsg::{FieldFormat, PayloadEnum, ReadFieldReply, MAX_FIELDNAME};
+use crate::memory::align_up;
+use crate::simulator::MapField;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// --------------------------------------------------------
+// a sketch's ServerData and memoryMap[], written down once.
+// matoschema makes the C++ for the sketch from it, and the
+// host can label fields without asking the node.
+//
+//   [[fields]]
+//   name = "name"
+//   type = "char[25]"
+//
+//   [[fields]]
+//   name = "macAddress"
+//   type = "uint64_t"
+//   unmapped = true
+// --------------------------------------------------------
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SchemaField {
+    pub name: String,
+    // the C type: float, double, (u)int8_t through (u)int64_t, or
+    // char[N] and uint8_t[N].
+    #[serde(rename = "type")]
+    pub ctype: String,
+    // in ServerData, but not memoryMap[].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub unmapped: bool,
+}
+
+// which schema a node's sketch uses, as it appears in config files.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeSchema {
+    pub id: u8,
+    pub file: PathBuf,
+}
+
+// ServerData's members, in order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Schema {
+    pub fields: Vec<SchemaField>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CType {
+    Char(usize),
+    Bytes(usize),
+    Float,
+    Double,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+}
+
+impl CType {
+    pub fn parse(s: &str) -> Result<CType, Box<dyn Error>> {
+        let s = s.trim();
+        if let Some((base, rest)) = s.split_once('[') {
+            let n = rest
+                .strip_suffix(']')
+                .ok_or_else(|| format!("bad array type '{}'", s))?
+                .trim()
+                .parse::<usize>()?;
+            if n == 0 || n > u8::MAX as usize {
+                return Err(format!("'{}' should be 1 to 255 long", s).into());
+            }
+            return match base.trim() {
+                "char" => Ok(CType::Char(n)),
+                "uint8_t" => Ok(CType::Bytes(n)),
+                _ => Err(format!("no arrays of '{}'", base.trim()).into()),
+            };
+        }
+        match s {
+            "float" => Ok(CType::Float),
+            "double" => Ok(CType::Double),
+            "uint8_t" => Ok(CType::Uint8),
+            "uint16_t" => Ok(CType::Uint16),
+            "uint32_t" => Ok(CType::Uint32),
+            "uint64_t" => Ok(CType::Uint64),
+            "int8_t" => Ok(CType::Int8),
+            "int16_t" => Ok(CType::Int16),
+            "int32_t" => Ok(CType::Int32),
+            "int64_t" => Ok(CType::Int64),
+            _ => Err(format!("unknown type '{}'", s).into()),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            CType::Char(n) | CType::Bytes(n) => *n,
+            CType::Uint8 | CType::Int8 => 1,
+            CType::Uint16 | CType::Int16 => 2,
+            CType::Float | CType::Uint32 | CType::Int32 => 4,
+            CType::Double | CType::Uint64 | CType::Int64 => 8,
+        }
+    }
+
+    // on the ESP32 everything is aligned to its size, arrays to their
+    // elements.
+    pub fn align(&self) -> usize {
+        match self {
+            CType::Char(_) | CType::Bytes(_) => 1,
+            t => t.size(),
+        }
+    }
+
+    pub fn format(&self) -> FieldFormat {
+        match self {
+            CType::Char(_) => FieldFormat::FfString,
+            CType::Float => FieldFormat::FfFloat,
+            CType::Uint8 => FieldFormat::FfUint8,
+            CType::Uint16 => FieldFormat::FfUint16,
+            CType::Uint32 => FieldFormat::FfUint32,
+            CType::Int8 => FieldFormat::FfInt8,
+            CType::Int16 => FieldFormat::FfInt16,
+            CType::Int32 => FieldFormat::FfInt32,
+            CType::Bytes(_) | CType::Double | CType::Uint64 | CType::Int64 => FieldFormat::FfOther,
+        }
+    }
+
+    // a struct member of this type.
+    pub fn declare(&self, name: &str) -> String {
+        match self {
+            CType::Char(n) => format!("char {}[{}]", name, n),
+            CType::Bytes(n) => format!("uint8_t {}[{}]", name, n),
+            CType::Float => format!("float {}", name),
+            CType::Double => format!("double {}", name),
+            CType::Uint8 => format!("uint8_t {}", name),
+            CType::Uint16 => format!("uint16_t {}", name),
+            CType::Uint32 => format!("uint32_t {}", name),
+            CType::Uint64 => format!("uint64_t {}", name),
+            CType::Int8 => format!("int8_t {}", name),
+            CType::Int16 => format!("int16_t {}", name),
+            CType::Int32 => format!("int32_t {}", name),
+            CType::Int64 => format!("int64_t {}", name),
+        }
+    }
+}
+
+// the field_format names in Automato.h.
+fn ff_name(format: FieldFormat) -> &'static str {
+    match format {
+        FieldFormat::FfString => "ff_char",
+        FieldFormat::FfFloat => "ff_float",
+        FieldFormat::FfUint8 => "ff_uint8",
+        FieldFormat::FfUint16 => "ff_uint16",
+        FieldFormat::FfUint32 => "ff_uint32",
+        FieldFormat::FfInt8 => "ff_int8",
+        FieldFormat::FfInt16 => "ff_int16",
+        FieldFormat::FfInt32 => "ff_int32",
+        FieldFormat::FfOther => "ff_other",
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+            chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    }
+}
+
+// a ServerData member and where it goes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlacedField {
+    pub name: String,
+    pub ctype: CType,
+    pub offset: u16,
+    pub mapped: bool,
+}
+
+// a schema laid out like the ESP32's C compiler would.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryMap {
+    // sizeof(ServerData).
+    pub datalen: u16,
+    pub fields: Vec<PlacedField>,
+}
+
+impl Schema {
+    // toml, or json if the file ends in .json.
+    pub fn load(path: &Path) -> Result<Schema, Box<dyn Error>> {
+        let s = fs::read_to_string(path)?;
+        if path.extension().is_some_and(|e| e == "json") {
+            Ok(serde_json::from_str(&s)?)
+        } else {
+            Ok(toml::from_str(&s)?)
+        }
+    }
+
+    pub fn layout(&self) -> Result<MemoryMap, Box<dyn Error>> {
+        let mut fields: Vec<PlacedField> = Vec::new();
+        let mut end = 0;
+        let mut align = 1;
+        for f in self.fields.iter() {
+            if !is_identifier(&f.name) {
+                return Err(format!("'{}' isn't a C identifier", f.name).into());
+            }
+            if fields.iter().any(|p| p.name == f.name) {
+                return Err(format!("'{}' is in the schema twice", f.name).into());
+            }
+            if !f.unmapped && f.name.len() > MAX_FIELDNAME {
+                return Err(format!(
+                    "field name '{}' is longer than {} bytes",
+                    f.name, MAX_FIELDNAME
+                )
+                .into());
+            }
+            let ctype = CType::parse(&f.ctype).map_err(|e| format!("field '{}': {}", f.name, e))?;
+            let offset = align_up(end, ctype.align());
+            end = offset + ctype.size();
+            align = align.max(ctype.align());
+            if end > u16::MAX as usize {
+                return Err("ServerData is more than 65535 bytes".into());
+            }
+            fields.push(PlacedField {
+                name: f.name.clone(),
+                ctype,
+                offset: offset as u16,
+                mapped: !f.unmapped,
+            });
+        }
+        if !fields.iter().any(|f| f.mapped) {
+            return Err("the schema has no mapped fields".into());
+        }
+        Ok(MemoryMap {
+            datalen: align_up(end, align) as u16,
+            fields,
+        })
+    }
+}
+
+impl MemoryMap {
+    pub fn mapped(&self) -> impl Iterator<Item = &PlacedField> {
+        self.fields.iter().filter(|f| f.mapped)
+    }
+
+    // memoryMap[], for a simulator NodeConfig.
+    pub fn map_fields(&self) -> Vec<MapField> {
+        self.mapped()
+            .map(|f| MapField {
+                name: f.name.clone(),
+                offset: f.offset,
+                length: f.ctype.size() as u8,
+                format: f.ctype.format(),
+                value: None,
+            })
+            .collect()
+    }
+
+    // what the node would answer to readfield, for each index.
+    pub fn readfield_replies(&self) -> Vec<ReadFieldReply> {
+        self.mapped()
+            .enumerate()
+            .filter_map(|(i, f)| {
+                match PayloadEnum::readfieldreply(
+                    i as u16,
+                    f.offset,
+                    f.ctype.size() as u8,
+                    f.ctype.format(),
+                    &f.name,
+                ) {
+                    Ok(PayloadEnum::PeReadfieldreply(rfr)) => Some(rfr),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    // a header with the ServerData struct and memoryMap[], and static_asserts
+    // that the compiler laid it out the way the host expects.
+    pub fn to_cpp(&self, source: &str) -> String {
+        let mut s = String::new();
+        // writing to a String doesn't fail.
+        let _ = self.write_cpp(&mut s, source);
+        s
+    }
+
+    fn write_cpp(&self, s: &mut String, source: &str) -> std::fmt::Result {
+        writeln!(s, "// generated by matoschema from {}; don't edit.", source)?;
+        writeln!(s, "// -*- mode: C++ -*-")?;
+        writeln!(s)?;
+        writeln!(s, "#ifndef SERVERDATA_H")?;
+        writeln!(s, "#define SERVERDATA_H")?;
+        writeln!(s)?;
+        writeln!(s, "#include <stddef.h>")?;
+        writeln!(s, "#include <stdint.h>")?;
+        writeln!(s, "#include <Automato.h>")?;
+        writeln!(s)?;
+        writeln!(s, "struct ServerData {{")?;
+        for f in self.fields.iter() {
+            writeln!(s, "  {};", f.ctype.declare(&f.name))?;
+        }
+        writeln!(s, "}};")?;
+        writeln!(s)?;
+        writeln!(s, "MapField memoryMap[] =")?;
+        for (i, f) in self.mapped().enumerate() {
+            writeln!(
+                s,
+                "  {} map_field(ServerData, {}, {})",
+                if i == 0 { '{' } else { ',' },
+                f.name,
+                ff_name(f.ctype.format())
+            )?;
+        }
+        writeln!(s, "  }};")?;
+        writeln!(s)?;
+        writeln!(
+            s,
+            "static_assert(sizeof(ServerData) == {}, \"ServerData isn't laid out like the schema\");",
+            self.datalen
+        )?;
+        for f in self.fields.iter() {
+            writeln!(
+                s,
+                "static_assert(offsetof(ServerData, {}) == {}, \"ServerData isn't laid out like the schema\");",
+                f.name, f.offset
+            )?;
+        }
+        writeln!(s)?;
+        writeln!(s, "#endif")
+    }
+}
@@ -0,0 +1,306 @@
+use crate::automatomsg::{
+    decode_payload, encode_payload, Payload, PayloadEnum, PayloadType, ResultCode, MAX_SECURE,
+};
+use crate::capture::now_millis;
+use crate::transport::Transport;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{aead, ChaCha20Poly1305, Nonce};
+use num_traits::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------
+// authenticated encryption for payloads that go over the
+// air.  each node has its own pre-shared key.  payloads are
+// sealed with ChaCha20-Poly1305 into a PtSecure payload,
+// along with a counter that has to go up with every message
+// from a sender, so recorded messages can't be replayed.
+// several hosts can share a node's key; each has its own id,
+// in the top byte of its counters.
+// --------------------------------------------------------
+
+pub const KEY_LEN: usize = 32;
+pub const TAG_LEN: usize = 16;
+// the biggest payload that still fits once sealed.
+pub const MAX_SEALED: usize = MAX_SECURE - TAG_LEN;
+
+// where a host's id goes in its counters.  hosts sharing a key then never use
+// the same nonce, and a node checks each host's counters separately.
+const HOST_SHIFT: u32 = 56;
+
+#[derive(Clone)]
+pub struct Key([u8; KEY_LEN]);
+
+impl Key {
+    pub fn new(bytes: [u8; KEY_LEN]) -> Key {
+        Key(bytes)
+    }
+
+    // 64 hex digits.
+    pub fn from_hex(s: &str) -> Result<Key, SecureError> {
+        let bytes = hex::decode(s.trim()).map_err(|e| SecureError::BadKey(e.to_string()))?;
+        let bytes = <[u8; KEY_LEN]>::try_from(bytes.as_slice()).map_err(|_| {
+            SecureError::BadKey(format!(
+                "key is {} bytes, should be {}",
+                bytes.len(),
+                KEY_LEN
+            ))
+        })?;
+        Ok(Key(bytes))
+    }
+}
+
+// keep keys out of logs.
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Key(..)")
+    }
+}
+
+// a key for one node, as it appears in config files.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NodeKey {
+    pub id: u8,
+    pub key: String,
+}
+
+// configs get logged.
+impl fmt::Debug for NodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NodeKey {{ id: {}, key: .. }}", self.id)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SecureError {
+    BadKey(String),
+    // too big to seal.
+    TooLong(usize),
+    // a plain payload where a sealed one was required.
+    NotSecure(PayloadType),
+    // wrong key, wrong address, or tampered with.
+    Forged,
+    Replayed { counter: u64, last: u64 },
+    // authentic, but not a valid payload once opened.
+    Invalid,
+}
+
+impl fmt::Display for SecureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecureError::BadKey(e) => write!(f, "bad key: {}", e),
+            SecureError::TooLong(len) => {
+                write!(f, "payload is {} bytes, max to seal is {}", len, MAX_SEALED)
+            }
+            SecureError::NotSecure(pt) => write!(f, "unsealed {:?} from a secured node", pt),
+            SecureError::Forged => write!(f, "payload failed authentication"),
+            SecureError::Replayed { counter, last } => {
+                write!(f, "replayed payload: counter {} after {}", counter, last)
+            }
+            SecureError::Invalid => write!(f, "sealed payload is invalid"),
+        }
+    }
+}
+
+impl std::error::Error for SecureError {}
+
+impl From<SecureError> for serialport::Error {
+    fn from(e: SecureError) -> serialport::Error {
+        serialport::Error::new(
+            serialport::ErrorKind::Io(std::io::ErrorKind::InvalidData),
+            e.to_string(),
+        )
+    }
+}
+
+// which end sealed a payload, and for a host, its id.  which end is part of
+// the nonce, so the host and node never use the same nonce with their shared
+// key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sender {
+    Host(u8),
+    Node,
+}
+
+// one end of a secured link to node 'address'.
+pub struct Link {
+    cipher: ChaCha20Poly1305,
+    me: Sender,
+    address: u8,
+    counter: u64,
+    // the last counter accepted from each sender at the other end, by the
+    // counter's top byte.  only kept in memory: once a node restarts, a
+    // request recorded earlier is accepted again, until that host sends
+    // something newer.
+    last: HashMap<u8, u64>,
+}
+
+impl Link {
+    pub fn new(key: &Key, me: Sender, address: u8) -> Link {
+        // start from the clock, so the counter keeps going up across
+        // restarts and nonces aren't reused.
+        let start = now_millis() & ((1 << HOST_SHIFT) - 1);
+        let counter = match me {
+            Sender::Host(id) => (id as u64) << HOST_SHIFT | start,
+            Sender::Node => start,
+        };
+        Link {
+            cipher: ChaCha20Poly1305::new(&key.0.into()),
+            me,
+            address,
+            counter,
+            last: HashMap::new(),
+        }
+    }
+
+    fn nonce(counter: u64, sender: Sender) -> Nonce {
+        let mut n = [0; 12];
+        n[0..8].copy_from_slice(&counter.to_le_bytes());
+        n[8] = match sender {
+            Sender::Host(_) => 0,
+            Sender::Node => 1,
+        };
+        n.into()
+    }
+
+    pub fn seal(&mut self, p: &Payload) -> Result<Payload, SecureError> {
+        let plain = encode_payload(p);
+        if plain.len() > MAX_SEALED {
+            return Err(SecureError::TooLong(plain.len()));
+        }
+        self.counter += 1;
+        // the address is authenticated too, so a message can't be passed on
+        // to another node with the same key.
+        let sealed = self
+            .cipher
+            .encrypt(
+                &Link::nonce(self.counter, self.me),
+                aead::Payload {
+                    msg: &plain,
+                    aad: &[self.address],
+                },
+            )
+            .expect("chacha20poly1305 only fails on huge messages");
+        // MAX_SEALED leaves room for the tag.
+        let pe = PayloadEnum::secure(self.counter, &sealed).expect("sealed payload fits");
+        Ok(Payload::try_from(pe).expect("sealed payload fits"))
+    }
+
+    pub fn open(&mut self, p: &Payload) -> Result<Payload, SecureError> {
+        let s = match PayloadEnum::from(*p) {
+            PayloadEnum::PeSecure(s) => s,
+            _ => return Err(SecureError::NotSecure(p.payload_type)),
+        };
+        let sender = (s.counter >> HOST_SHIFT) as u8;
+        if let Some(&last) = self.last.get(&sender) {
+            if s.counter <= last {
+                return Err(SecureError::Replayed {
+                    counter: s.counter,
+                    last,
+                });
+            }
+        }
+        let them = match self.me {
+            Sender::Host(_) => Sender::Node,
+            Sender::Node => Sender::Host(sender),
+        };
+        let plain = self
+            .cipher
+            .decrypt(
+                &Link::nonce(s.counter, them),
+                aead::Payload {
+                    msg: &s.data,
+                    aad: &[self.address],
+                },
+            )
+            .map_err(|_| SecureError::Forged)?;
+        let inner = decode_payload(&plain).map_err(|_| SecureError::Invalid)?;
+        if inner.payload_type == PayloadType::PtSecure {
+            return Err(SecureError::Invalid);
+        }
+        self.last.insert(sender, s.counter);
+        Ok(inner)
+    }
+}
+
+// --------------------------------------------------------
+// seal payloads to nodes that have keys, and open their
+// replies.  nodes without keys pass through unchanged.
+// broadcasts go out unsealed, as no one key covers every
+// node, so keyed nodes don't act on them.
+// --------------------------------------------------------
+
+pub struct SecureTransport {
+    inner: Box<dyn Transport>,
+    links: HashMap<u8, Link>,
+}
+
+impl SecureTransport {
+    // 'host' tells this host apart from others with the same keys; each
+    // needs its own.
+    pub fn new(
+        inner: Box<dyn Transport>,
+        keys: &[NodeKey],
+        host: u8,
+    ) -> Result<SecureTransport, SecureError> {
+        let mut links = HashMap::new();
+        for nk in keys {
+            links.insert(
+                nk.id,
+                Link::new(&Key::from_hex(&nk.key)?, Sender::Host(host), nk.id),
+            );
+        }
+        Ok(SecureTransport { inner, links })
+    }
+}
+
+impl Transport for SecureTransport {
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        match self.links.get_mut(&toid) {
+            Some(link) => self.inner.send(&link.seal(payload)?, toid),
+            None => self.inner.send(payload, toid),
+        }
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        let (fromid, payload) = self.inner.receive()?;
+        match self.links.get_mut(&fromid) {
+            // router errors come from the gateway, which has no keys.  a node
+            // seals its own fails, so any other unsealed one is forged.
+            Some(_) if router_error(&payload) => Ok((fromid, payload)),
+            Some(link) => Ok((fromid, link.open(&payload)?)),
+            None => Ok((fromid, payload)),
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+}
+
+fn router_error(p: &Payload) -> bool {
+    match PayloadEnum::from(*p) {
+        PayloadEnum::PeFail(code) => matches!(
+            ResultCode::from_u8(code),
+            Some(
+                ResultCode::RcRhRouterErrorInvalidLength
+                    | ResultCode::RcRhRouterErrorNoRoute
+                    | ResultCode::RcRhRouterErrorTimeout
+                    | ResultCode::RcRhRouterErrorNoReply
+                    | ResultCode::RcRhRouterErrorUnableToDeliver
+            )
+        ),
+        _ => false,
+    }
+}
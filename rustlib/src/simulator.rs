@@ -0,0 +1,672 @@
+use crate::automatomsg::{
+    AnalogPinval, FieldFormat, I2cStatus, Payload, PayloadEnum, Pinval, PulseEdge, ReadmemReply,
+    RemoteInfo, ResultCode, SpiTransferReply, FACTORY_RESET_TOKEN, MAX_FIELDNAME, MAX_I2C,
+    MAX_ONEWIRE_ROMS, MAX_READMEM, MAX_SPI, REBOOT_TOKEN, SERVO_MAX_PULSE, SERVO_MIN_PULSE,
+};
+use crate::mesh::Faults;
+use crate::secure::{Key, Link, Sender};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::time::Instant;
+
+// --------------------------------------------------------
+// a software automato.  answers messages the way the
+// automato library's doRemoteControl() does, from a memory
+// map like the sketches' ServerData and memoryMap[].
+// --------------------------------------------------------
+
+// ESP32 gpio numbers run 0-39.
+pub const NUM_PINS: usize = 40;
+
+// what analogWrite() uses on the ESP32 when it's not told otherwise.
+pub const DEFAULT_PWM_FREQUENCY: u32 = 1000;
+
+// like map_field() in the sketches, plus an optional initial value.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MapField {
+    pub name: String,
+    pub offset: u16,
+    pub length: u8,
+    pub format: FieldFormat,
+    // parsed according to format; strings are copied in and zero padded.
+    pub value: Option<String>,
+}
+
+// something on the node's i2c bus, with registers like most sensors: the
+// first byte written picks a register, and reads and writes go on from
+// there.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct I2cDevice {
+    pub address: u8,
+    // initial register values, as hex.
+    pub registers: String,
+}
+
+// something on the node's spi bus, selected by cspin.  the first byte is a
+// register, with the top bit set to read from it rather than write to it, and
+// the bytes after go to or come from the registers after that.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SpiDevice {
+    pub cspin: u8,
+    // initial register values, as hex.
+    pub registers: String,
+}
+
+// a DS18B20 on a onewire pin.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnewireDevice {
+    pub pin: u8,
+    // 16 hex digits, family code first.
+    pub rom: String,
+    pub temperature: f32,
+}
+
+// a square wave on a pin, like a flow meter's, for pulse counters to count.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PulseSource {
+    pub pin: u8,
+    pub frequency: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NodeConfig {
+    pub address: u8,
+    pub protoversion: f32,
+    pub mac_address: u64,
+    // sizeof(ServerData).
+    pub datalen: u16,
+    pub temperature: f32,
+    pub humidity: f32,
+    // pre-shared key, 64 hex digits.  with a key only sealed requests are
+    // answered.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+    // largest message the node's radio takes, if smaller than the RFM95's.
+    // bigger payloads are fragmented.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtu: Option<usize>,
+    // only used when the node is part of a SimulatedMesh.
+    #[serde(default)]
+    pub faults: Faults,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub i2c: Vec<I2cDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spi: Vec<SpiDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub onewire: Vec<OnewireDevice>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pulses: Vec<PulseSource>,
+    // last, so toml writes it after the plain values.
+    pub fields: Vec<MapField>,
+}
+
+impl Default for NodeConfig {
+    // the ServerData of serialtolora.ino.
+    fn default() -> NodeConfig {
+        NodeConfig {
+            address: 1,
+            protoversion: 1.0,
+            mac_address: 0x7c9ebd3a5f10,
+            datalen: 56,
+            temperature: 22.5,
+            humidity: 45.0,
+            key: None,
+            mtu: None,
+            faults: Faults::default(),
+            i2c: Vec::new(),
+            spi: Vec::new(),
+            onewire: Vec::new(),
+            pulses: Vec::new(),
+            fields: vec![
+                MapField {
+                    name: "name".to_string(),
+                    offset: 0,
+                    length: 25,
+                    format: FieldFormat::FfString,
+                    value: Some("test 1 2 3".to_string()),
+                },
+                MapField {
+                    name: "targettemp".to_string(),
+                    offset: 28,
+                    length: 4,
+                    format: FieldFormat::FfFloat,
+                    value: Some("42.0".to_string()),
+                },
+                MapField {
+                    name: "loops".to_string(),
+                    offset: 40,
+                    length: 4,
+                    format: FieldFormat::FfInt32,
+                    value: Some("0".to_string()),
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PinState {
+    pub mode: u8,
+    pub state: u8,
+    pub analog: u16,
+    // pwm output; a frequency of 0 is no pwm yet.
+    pub duty: u16,
+    pub frequency: u32,
+    // servo pulse width in microseconds; 0 is no servo.
+    pub servo: u16,
+    // the PulseSource frequency, if any.
+    pub signal: f32,
+    // the edge a pulse counter counts, and when it started.
+    pub counter: Option<(PulseEdge, Instant)>,
+}
+
+impl PinState {
+    // edges of the signal since the counter started, and pulses a second.
+    fn pulse_count(&self, edge: PulseEdge, start: Instant) -> (u32, f32) {
+        let edges = match edge {
+            PulseEdge::PulseChange => 2.0,
+            _ => 1.0,
+        };
+        let elapsed = start.elapsed().as_secs_f32();
+        let count = (self.signal * edges * elapsed) as u32;
+        if elapsed > 0.0 {
+            (count, count as f32 / edges / elapsed)
+        } else {
+            (count, 0.0)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct I2cRegisters {
+    pub address: u8,
+    pub registers: Vec<u8>,
+    // the register the next read or write starts at.
+    pub pointer: usize,
+}
+
+impl I2cRegisters {
+    // the register pointer wraps around at the end.
+    fn transfer(&mut self, data: &[u8], readlength: usize) -> Vec<u8> {
+        if self.registers.is_empty() {
+            // nothing to read, so the bus stays high.
+            return vec![0xff; readlength];
+        }
+        if let Some((pointer, rest)) = data.split_first() {
+            self.pointer = *pointer as usize % self.registers.len();
+            for b in rest {
+                self.registers[self.pointer] = *b;
+                self.pointer = (self.pointer + 1) % self.registers.len();
+            }
+        }
+        (0..readlength)
+            .map(|_| {
+                let b = self.registers[self.pointer];
+                self.pointer = (self.pointer + 1) % self.registers.len();
+                b
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpiRegisters {
+    pub cspin: u8,
+    pub registers: Vec<u8>,
+}
+
+impl SpiRegisters {
+    // a byte back for each byte sent; 0xff while the register is sent, and
+    // for writes.
+    fn transfer(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut out = vec![0xff; data.len()];
+        if self.registers.is_empty() {
+            return out;
+        }
+        if let Some((command, rest)) = data.split_first() {
+            let read = command & 0x80 != 0;
+            let mut register = (command & 0x7f) as usize % self.registers.len();
+            for (i, b) in rest.iter().enumerate() {
+                if read {
+                    out[i + 1] = self.registers[register];
+                } else {
+                    self.registers[register] = *b;
+                }
+                register = (register + 1) % self.registers.len();
+            }
+        }
+        out
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OnewireSensor {
+    pub pin: u8,
+    pub rom: [u8; 8],
+    pub temperature: f32,
+}
+
+pub struct Simulator {
+    pub address: u8,
+    pub protoversion: f32,
+    pub mac_address: u64,
+    pub fields: Vec<MapField>,
+    pub memory: Vec<u8>,
+    // the memory map as configured.  ServerData lives in RAM, so the sketch
+    // sets this up again on every boot.
+    pub factory: Vec<u8>,
+    pub pins: [PinState; NUM_PINS],
+    pub temperature: f32,
+    pub humidity: f32,
+    pub i2c: Vec<I2cRegisters>,
+    pub spi: Vec<SpiRegisters>,
+    pub onewire: Vec<OnewireSensor>,
+    pub link: Option<Link>,
+    // power on, for millis().
+    pub started: Instant,
+}
+
+// the bytes of a field value, as the ESP32 would store them.
+pub fn encode_value(
+    format: FieldFormat,
+    length: usize,
+    value: &str,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    let bytes = match format {
+        FieldFormat::FfString => {
+            if value.len() > length {
+                return Err(format!("'{}' is longer than {} bytes", value, length).into());
+            }
+            let mut b = value.as_bytes().to_vec();
+            b.resize(length, 0);
+            b
+        }
+        FieldFormat::FfFloat => value.parse::<f32>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfUint8 => value.parse::<u8>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfUint16 => value.parse::<u16>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfUint32 => value.parse::<u32>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfInt8 => value.parse::<i8>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfInt16 => value.parse::<i16>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfInt32 => value.parse::<i32>()?.to_le_bytes().to_vec(),
+        FieldFormat::FfOther => hex::decode(value)?,
+    };
+    if bytes.len() != length {
+        return Err(format!("'{}' is {} bytes, field is {}", value, bytes.len(), length).into());
+    }
+    Ok(bytes)
+}
+
+impl Simulator {
+    pub fn new(config: &NodeConfig) -> Result<Simulator, Box<dyn Error>> {
+        let mut memory = vec![0; config.datalen as usize];
+        for f in config.fields.iter() {
+            if f.name.len() > MAX_FIELDNAME {
+                return Err(format!(
+                    "field name '{}' is longer than {} bytes",
+                    f.name, MAX_FIELDNAME
+                )
+                .into());
+            }
+            let start = f.offset as usize;
+            let end = start + f.length as usize;
+            if end > memory.len() {
+                return Err(format!("field '{}' is past the end of memory", f.name).into());
+            }
+            if let Some(v) = &f.value {
+                memory[start..end].copy_from_slice(&encode_value(f.format, f.length as usize, v)?);
+            }
+        }
+
+        let mut i2c: Vec<I2cRegisters> = Vec::new();
+        for d in config.i2c.iter() {
+            if d.address > 0x7f {
+                return Err(format!("i2c address {:#04x} is more than 7 bits", d.address).into());
+            }
+            if i2c.iter().any(|r| r.address == d.address) {
+                return Err(format!("two i2c devices at {:#04x}", d.address).into());
+            }
+            i2c.push(I2cRegisters {
+                address: d.address,
+                registers: hex::decode(&d.registers)?,
+                pointer: 0,
+            });
+        }
+
+        let mut spi: Vec<SpiRegisters> = Vec::new();
+        for d in config.spi.iter() {
+            if d.cspin as usize >= NUM_PINS {
+                return Err(format!("spi cspin {} isn't a pin", d.cspin).into());
+            }
+            if spi.iter().any(|r| r.cspin == d.cspin) {
+                return Err(format!("two spi devices on cspin {}", d.cspin).into());
+            }
+            spi.push(SpiRegisters {
+                cspin: d.cspin,
+                registers: hex::decode(&d.registers)?,
+            });
+        }
+
+        let mut onewire: Vec<OnewireSensor> = Vec::new();
+        for d in config.onewire.iter() {
+            if d.pin as usize >= NUM_PINS {
+                return Err(format!("onewire pin {} isn't a pin", d.pin).into());
+            }
+            let rom: [u8; 8] = hex::decode(&d.rom)?
+                .try_into()
+                .map_err(|_| format!("onewire rom '{}' isn't 8 bytes", d.rom))?;
+            if onewire.iter().any(|s| s.pin == d.pin && s.rom == rom) {
+                return Err(format!("two onewire devices with rom {}", d.rom).into());
+            }
+            if onewire.iter().filter(|s| s.pin == d.pin).count() == MAX_ONEWIRE_ROMS {
+                return Err(format!("more than {} onewire devices", MAX_ONEWIRE_ROMS).into());
+            }
+            onewire.push(OnewireSensor {
+                pin: d.pin,
+                rom,
+                temperature: d.temperature,
+            });
+        }
+
+        let mut pins = [PinState::default(); NUM_PINS];
+        for s in config.pulses.iter() {
+            match pins.get_mut(s.pin as usize) {
+                Some(ps) => ps.signal = s.frequency,
+                None => return Err(format!("pulse pin {} isn't a pin", s.pin).into()),
+            }
+        }
+
+        Ok(Simulator {
+            address: config.address,
+            protoversion: config.protoversion,
+            mac_address: config.mac_address,
+            fields: config.fields.clone(),
+            factory: memory.clone(),
+            memory,
+            pins,
+            temperature: config.temperature,
+            humidity: config.humidity,
+            i2c,
+            spi,
+            onewire,
+            link: match &config.key {
+                Some(k) => Some(Link::new(&Key::from_hex(k)?, Sender::Node, config.address)),
+                None => None,
+            },
+            started: Instant::now(),
+        })
+    }
+
+    // pins and memory go back to how they were at power on.  pins keep their
+    // signals, which come from outside the node.
+    fn reboot(&mut self) {
+        self.started = Instant::now();
+        self.memory.copy_from_slice(&self.factory);
+        for ps in self.pins.iter_mut() {
+            *ps = PinState {
+                signal: ps.signal,
+                ..PinState::default()
+            };
+        }
+    }
+
+    fn pin(&mut self, pin: u8) -> Result<&mut PinState, ResultCode> {
+        self.pins
+            .get_mut(pin as usize)
+            .ok_or(ResultCode::RcInvalidPinNumber)
+    }
+
+    fn mem_range(&self, address: u16, length: usize) -> Result<std::ops::Range<usize>, ResultCode> {
+        let start = address as usize;
+        if start + length > self.memory.len() {
+            Err(ResultCode::RcInvalidMemAddress)
+        } else {
+            Ok(start..start + length)
+        }
+    }
+
+    // the reply to a message.  errors are returned to the sender as PeFail.
+    pub fn handle(&mut self, request: &PayloadEnum) -> PayloadEnum {
+        let reply = if self.link.is_some() {
+            self.secure_reply(request)
+        } else {
+            self.reply(request)
+        };
+        match reply {
+            Ok(pe) => pe,
+            Err(rc) => self.fail(rc),
+        }
+    }
+
+    // PeFail, sealed if the node has a key, so no one else can fake one.
+    // sealed even for a request that couldn't be opened.
+    pub fn fail(&mut self, rc: ResultCode) -> PayloadEnum {
+        let pe = PayloadEnum::PeFail(rc as u8);
+        match &mut self.link {
+            Some(link) => PayloadEnum::from(
+                link.seal(&Payload::try_from(pe).expect("fail fits"))
+                    .expect("fail fits sealed"),
+            ),
+            None => pe,
+        }
+    }
+
+    // open the request and seal the reply.  errors from the opened request
+    // are sealed like any other reply.
+    fn secure_reply(&mut self, request: &PayloadEnum) -> Result<PayloadEnum, ResultCode> {
+        let mut link = self.link.take().ok_or(ResultCode::RcOperationForbidden)?;
+        let reply = self.sealed_reply(&mut link, request);
+        self.link = Some(link);
+        reply
+    }
+
+    fn sealed_reply(
+        &mut self,
+        link: &mut Link,
+        request: &PayloadEnum,
+    ) -> Result<PayloadEnum, ResultCode> {
+        let opened = link
+            .open(&Payload::try_from(request.clone())?)
+            .map_err(|_| ResultCode::RcOperationForbidden)?;
+        let reply = match self.reply(&PayloadEnum::from(opened)) {
+            Ok(pe) => pe,
+            Err(rc) => PayloadEnum::PeFail(rc as u8),
+        };
+        let sealed = link
+            .seal(&Payload::try_from(reply)?)
+            .map_err(|_| ResultCode::RcInvalidMemLength)?;
+        Ok(PayloadEnum::from(sealed))
+    }
+
+    fn reply(&mut self, request: &PayloadEnum) -> Result<PayloadEnum, ResultCode> {
+        match request {
+            PayloadEnum::PePinmode(pm) => {
+                self.pin(pm.pin)?.mode = pm.mode;
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeReadpin(pin) => {
+                let state = self.pin(*pin)?.state;
+                Ok(PayloadEnum::PeReadpinreply(Pinval { pin: *pin, state }))
+            }
+            PayloadEnum::PeWritepin(pv) => {
+                self.pin(pv.pin)?.state = pv.state;
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeReadanalog(pin) => {
+                let state = self.pin(*pin)?.analog;
+                Ok(PayloadEnum::PeReadanalogreply(AnalogPinval {
+                    pin: *pin,
+                    state,
+                }))
+            }
+            PayloadEnum::PeWriteanalog(wa) => {
+                let ps = self.pin(wa.pin)?;
+                ps.duty = wa.duty;
+                if wa.frequency != 0 {
+                    ps.frequency = wa.frequency;
+                } else if ps.frequency == 0 {
+                    ps.frequency = DEFAULT_PWM_FREQUENCY;
+                }
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeI2cTransfer(t) => {
+                if t.data.len() > MAX_I2C || t.readlength as usize > MAX_I2C {
+                    return Err(ResultCode::RcInvalidMemLength);
+                }
+                // nobody home is an answer from the bus, not a failed request.
+                Ok(match self.i2c.iter_mut().find(|r| r.address == t.address) {
+                    Some(r) => PayloadEnum::i2ctransferreply(
+                        t.address,
+                        I2cStatus::IsOk,
+                        &r.transfer(&t.data, t.readlength as usize),
+                    )?,
+                    None => {
+                        PayloadEnum::i2ctransferreply(t.address, I2cStatus::IsAddressNack, &[])?
+                    }
+                })
+            }
+            PayloadEnum::PeSpiTransfer(t) => {
+                if t.mode > 3 {
+                    return Err(ResultCode::RcInvalidMessageType);
+                }
+                if t.data.len() > MAX_SPI {
+                    return Err(ResultCode::RcInvalidMemLength);
+                }
+                self.pin(t.cspin)?;
+                // with nothing selected, miso stays high.
+                let data = match self.spi.iter_mut().find(|r| r.cspin == t.cspin) {
+                    Some(r) => r.transfer(&t.data),
+                    None => vec![0xff; t.data.len()],
+                };
+                Ok(PayloadEnum::PeSpiTransferreply(SpiTransferReply {
+                    cspin: t.cspin,
+                    data,
+                }))
+            }
+            PayloadEnum::PeOnewirescan(pin) => {
+                self.pin(*pin)?;
+                let roms: Vec<[u8; 8]> = self
+                    .onewire
+                    .iter()
+                    .filter(|s| s.pin == *pin)
+                    .map(|s| s.rom)
+                    .collect();
+                Ok(PayloadEnum::onewirescanreply(*pin, &roms)?)
+            }
+            PayloadEnum::PeReadonewiretemperature(a) => {
+                self.pin(a.pin)?;
+                let s = self
+                    .onewire
+                    .iter()
+                    .find(|s| s.pin == a.pin && s.rom == a.rom)
+                    .ok_or(ResultCode::RcNoDevice)?;
+                Ok(PayloadEnum::readonewiretemperaturereply(
+                    s.pin,
+                    s.rom,
+                    s.temperature,
+                ))
+            }
+            // the servo gets there at once, whatever the speed.
+            PayloadEnum::PeServo(s) => {
+                let pulse = s.pulse.clamp(SERVO_MIN_PULSE, SERVO_MAX_PULSE);
+                self.pin(s.pin)?.servo = pulse;
+                Ok(PayloadEnum::servoreply(s.pin, pulse))
+            }
+            PayloadEnum::PePulsecounter(pc) => {
+                let edge = PulseEdge::from_u8(pc.edge).ok_or(ResultCode::RcInvalidMessageType)?;
+                self.pin(pc.pin)?.counter = match edge {
+                    PulseEdge::PulseOff => None,
+                    edge => Some((edge, Instant::now())),
+                };
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeReadpulsecount(rp) => {
+                let ps = self.pin(rp.pin)?;
+                // a pin that isn't counting has nothing to say.
+                let (edge, start) = ps.counter.ok_or(ResultCode::RcOperationForbidden)?;
+                let (count, frequency) = ps.pulse_count(edge, start);
+                if rp.reset != 0 {
+                    ps.counter = Some((edge, Instant::now()));
+                }
+                Ok(PayloadEnum::readpulsecountreply(rp.pin, count, frequency))
+            }
+            PayloadEnum::PeReboot(token) => {
+                if *token != REBOOT_TOKEN {
+                    return Err(ResultCode::RcOperationForbidden);
+                }
+                self.reboot();
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeFactoryReset(token) => {
+                if *token != FACTORY_RESET_TOKEN {
+                    return Err(ResultCode::RcOperationForbidden);
+                }
+                // nothing is saved outside ServerData to wipe, so this is a
+                // reboot here.
+                self.reboot();
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PePing(nonce) => {
+                // millis() wraps after 49 days, and so does this.
+                let millis = self.started.elapsed().as_millis() as u32;
+                Ok(PayloadEnum::pong(*nonce, millis))
+            }
+            PayloadEnum::PeReadmem(rm) => {
+                if rm.length as usize > MAX_READMEM {
+                    return Err(ResultCode::RcInvalidMemLength);
+                }
+                let range = self.mem_range(rm.address, rm.length as usize)?;
+                Ok(PayloadEnum::PeReadmemreply(ReadmemReply {
+                    data: self.memory[range].to_vec(),
+                }))
+            }
+            PayloadEnum::PeWritemem(wm) => {
+                let range = self.mem_range(wm.address, wm.data.len())?;
+                self.memory[range].copy_from_slice(&wm.data);
+                Ok(PayloadEnum::PeAck)
+            }
+            PayloadEnum::PeReadinfo => Ok(PayloadEnum::PeReadinforeply(RemoteInfo {
+                protoversion: self.protoversion,
+                mac_address: self.mac_address,
+                datalen: self.memory.len() as u16,
+                fieldcount: self.fields.len() as u16,
+            })),
+            PayloadEnum::PeReadhumidity => Ok(PayloadEnum::PeReadhumidityreply(self.humidity)),
+            PayloadEnum::PeReadtemperature => {
+                Ok(PayloadEnum::PeReadtemperaturereply(self.temperature))
+            }
+            PayloadEnum::PeReadfield(rf) => {
+                let f = self
+                    .fields
+                    .get(rf.index as usize)
+                    .ok_or(ResultCode::RcInvalidMemAddress)?;
+                Ok(PayloadEnum::readfieldreply(
+                    rf.index, f.offset, f.length, f.format, &f.name,
+                )?)
+            }
+            // replies and acks aren't requests.
+            PayloadEnum::PeAck
+            | PayloadEnum::PeFail(_)
+            | PayloadEnum::PeReadpinreply(_)
+            | PayloadEnum::PeReadmemreply(_)
+            | PayloadEnum::PeReadinforeply(_)
+            | PayloadEnum::PeReadhumidityreply(_)
+            | PayloadEnum::PeReadtemperaturereply(_)
+            | PayloadEnum::PeReadanalogreply(_)
+            | PayloadEnum::PeReadfieldreply(_)
+            | PayloadEnum::PeI2cTransferreply(_)
+            | PayloadEnum::PeSpiTransferreply(_)
+            | PayloadEnum::PeOnewirescanreply(_)
+            | PayloadEnum::PeReadonewiretemperaturereply(_)
+            | PayloadEnum::PeServoreply(_)
+            | PayloadEnum::PeReadpulsecountreply(_)
+            | PayloadEnum::PePong(_) => Err(ResultCode::RcInvalidMessageType),
+            // without a key there's no opening it.
+            PayloadEnum::PeSecure(_) => Err(ResultCode::RcOperationForbidden),
+            // the radio puts fragments back together before they get here.
+            PayloadEnum::PeFragment(_) => Err(ResultCode::RcInvalidMessageType),
+            // custom commands are up to each sketch.
+            PayloadEnum::PeCustom(_) => Err(ResultCode::RcInvalidMessageType),
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use crate::automatomsg::{
+    decode_frame, frame_bytes, read_frame, write_message, Payload, PayloadEnum, Pong, ReadLimits,
+};
+use crate::capture::{read_capture, CaptureRecord, CaptureWriter, Direction};
+use log::warn;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// --------------------------------------------------------
+// a link to the automato network.  usually a serial port
+// to a lora gateway, but could be a recording, a simulator, etc.
+// --------------------------------------------------------
+
+pub trait Transport: Send {
+    // send a payload to automato 'toid'.
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error>;
+
+    // wait for the next payload, returning the id of the sender.
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error>;
+
+    // like receive, along with the frame as it came off the wire: 'm', id,
+    // length, payload.  the bytes are there even when receive fails partway
+    // through a frame.  transports without a wire make the frame up from the
+    // payload.
+    fn receive_raw(&mut self) -> (Vec<u8>, Result<(u8, Payload), serialport::Error>) {
+        let r = self.receive();
+        let raw = match &r {
+            Ok((fromid, payload)) => frame_bytes(payload, *fromid),
+            Err(_) => Vec::new(),
+        };
+        (raw, r)
+    }
+
+    // how long receive() waits before giving up.
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error>;
+
+    fn timeout(&self) -> Duration;
+
+    // the transport's clock, for timing replies.  simulated transports keep
+    // their own time.
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// as long as a reply can take: RHMesh's own timeout is 4000ms.  also the
+// longest a broadcast's replies are worth waiting for.
+pub const REPLY_TIMEOUT: Duration = Duration::from_millis(4420);
+
+// every reply that arrives within 'window', by sender id.  for broadcasts to
+// RH_BROADCAST_ADDRESS, which each automato in range answers.  only the first
+// reply from a sender is kept, and bad messages are skipped so one broken
+// node doesn't hide the rest.  the transport's timeout is left as it was.
+pub fn collect_replies(
+    t: &mut dyn Transport,
+    window: Duration,
+) -> Result<BTreeMap<u8, Payload>, serialport::Error> {
+    let timeout = t.timeout();
+    let deadline = t.now() + window;
+    let replies = gather_replies(t, deadline);
+    t.set_timeout(timeout)?;
+    replies
+}
+
+fn gather_replies(
+    t: &mut dyn Transport,
+    deadline: Instant,
+) -> Result<BTreeMap<u8, Payload>, serialport::Error> {
+    let mut replies = BTreeMap::new();
+    loop {
+        let left = deadline.saturating_duration_since(t.now());
+        if left.is_zero() {
+            return Ok(replies);
+        }
+        t.set_timeout(left)?;
+        match t.receive() {
+            Ok((fromid, payload)) => {
+                replies.entry(fromid).or_insert(payload);
+            }
+            Err(e) if e.kind == serialport::ErrorKind::Io(ErrorKind::TimedOut) => {
+                return Ok(replies)
+            }
+            Err(e) if e.kind == serialport::ErrorKind::Io(ErrorKind::InvalidData) => {
+                warn!("skipping bad reply: {}", e)
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// --------------------------------------------------------
+// serial port, speaking the 'm' id len payload framing
+// of serialtolora.ino.
+// --------------------------------------------------------
+
+pub struct SerialTransport {
+    pub port: Box<dyn serialport::SerialPort>,
+    // receive's deadline is the transport timeout.
+    pub limits: ReadLimits,
+}
+
+// the port timeout for each read; short, so receive doesn't run much past
+// its deadline.
+const READ_POLL: Duration = Duration::from_millis(50);
+
+impl SerialTransport {
+    // the port's own timeout isn't used; reads poll it, and 'limits' says how
+    // long receive waits.
+    pub fn new(port: Box<dyn serialport::SerialPort>, limits: ReadLimits) -> SerialTransport {
+        SerialTransport { port, limits }
+    }
+
+    fn read(&mut self, raw: &mut Vec<u8>) -> Result<(u8, Payload), serialport::Error> {
+        self.port
+            .set_timeout(std::cmp::min(self.limits.deadline, READ_POLL))?;
+        let skipped = read_frame(&mut *self.port, &self.limits, raw)?;
+        if skipped > 0 {
+            warn!("{} noise bytes skipped before a message", skipped);
+        }
+        decode_frame(raw)
+    }
+}
+
+impl Transport for SerialTransport {
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        write_message(&mut *self.port, payload, toid)
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        self.receive_raw().1
+    }
+
+    fn receive_raw(&mut self) -> (Vec<u8>, Result<(u8, Payload), serialport::Error>) {
+        let mut raw = Vec::new();
+        let r = self.read(&mut raw);
+        (raw, r)
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error> {
+        self.limits.deadline = timeout;
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        self.limits.deadline
+    }
+}
+
+// --------------------------------------------------------
+// pass through to another transport, saving the traffic
+// to a capture file.  frames are recorded as they went
+// over the wire, bad ones and timeouts included, so this
+// goes right on top of the serial port.
+// --------------------------------------------------------
+
+pub struct RecordingTransport {
+    inner: Box<dyn Transport>,
+    capture: CaptureWriter<File>,
+    // the destination of the last send, for timeout records.
+    last_toid: u8,
+}
+
+impl RecordingTransport {
+    pub fn new(inner: Box<dyn Transport>, capture: CaptureWriter<File>) -> RecordingTransport {
+        RecordingTransport {
+            inner,
+            capture,
+            last_toid: 0,
+        }
+    }
+
+    // record to a new capture file, replacing any existing one.
+    pub fn create(
+        inner: Box<dyn Transport>,
+        path: &Path,
+    ) -> Result<RecordingTransport, std::io::Error> {
+        Ok(RecordingTransport::new(
+            inner,
+            CaptureWriter::new(File::create(path)?),
+        ))
+    }
+}
+
+impl Transport for RecordingTransport {
+    // recorded first, so a send that fails is in the capture too.
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        let frame = frame_bytes(payload, toid);
+        self.capture
+            .write_record(&CaptureRecord::now(Direction::Send, toid, frame))?;
+        self.last_toid = toid;
+        self.inner.send(payload, toid)
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        self.receive_raw().1
+    }
+
+    fn receive_raw(&mut self) -> (Vec<u8>, Result<(u8, Payload), serialport::Error>) {
+        let (raw, r) = self.inner.receive_raw();
+        let record = match &r {
+            Ok((fromid, _)) => CaptureRecord::now(Direction::Receive, *fromid, raw.clone()),
+            Err(e) if e.kind == serialport::ErrorKind::Io(ErrorKind::TimedOut) => {
+                CaptureRecord::now(Direction::Timeout, self.last_toid, raw.clone())
+            }
+            // a frame that doesn't decode; its id is still there.
+            Err(_) if raw.len() > 1 => CaptureRecord::now(Direction::Receive, raw[1], raw.clone()),
+            Err(_) => return (raw, r),
+        };
+        match self.capture.write_record(&record) {
+            Ok(()) => (raw, r),
+            Err(e) => (raw, Err(e.into())),
+        }
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> Result<(), serialport::Error> {
+        self.inner.set_timeout(timeout)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.timeout()
+    }
+
+    fn now(&self) -> Instant {
+        self.inner.now()
+    }
+}
+
+// --------------------------------------------------------
+// play back a capture file.  each sent message is answered
+// with the replies that followed the same message to the
+// same address in the recording, bad frames and all.
+// --------------------------------------------------------
+
+// ping() picks a fresh nonce every time, so a ping matches any recorded
+// ping to the same address, and the recorded pong gets the new nonce.
+fn ping_nonce(frame: &[u8]) -> Option<u32> {
+    match decode_frame(frame).map(|(_, p)| PayloadEnum::from(p)) {
+        Ok(PayloadEnum::PePing(nonce)) => Some(nonce),
+        _ => None,
+    }
+}
+
+fn renonce(frame: &[u8], nonce: u32) -> Vec<u8> {
+    let pong = match decode_frame(frame).map(|(id, p)| (id, PayloadEnum::from(p))) {
+        Ok((id, PayloadEnum::PePong(pong))) => Payload::try_from(PayloadEnum::PePong(Pong {
+            nonce,
+            millis: pong.millis,
+        }))
+        .map(|p| frame_bytes(&p, id)),
+        _ => return frame.to_vec(),
+    };
+    pong.unwrap_or_else(|_| frame.to_vec())
+}
+
+struct Exchange {
+    address: u8,
+    request: Vec<u8>,
+    replies: Vec<(u8, Vec<u8>)>,
+    used: bool,
+}
+
+pub struct ReplayTransport {
+    exchanges: Vec<Exchange>,
+    pending: VecDeque<(u8, Vec<u8>)>,
+}
+
+impl ReplayTransport {
+    pub fn new(records: Vec<CaptureRecord>) -> ReplayTransport {
+        let mut exchanges: Vec<Exchange> = Vec::new();
+        // replies recorded before the first send are delivered straight away.
+        let mut pending = VecDeque::new();
+        for r in records {
+            match (r.direction, exchanges.last_mut()) {
+                (Direction::Send, _) => exchanges.push(Exchange {
+                    address: r.address,
+                    request: r.frame,
+                    replies: Vec::new(),
+                    used: false,
+                }),
+                (Direction::Receive, Some(ex)) => ex.replies.push((r.address, r.frame)),
+                (Direction::Receive, None) => pending.push_back((r.address, r.frame)),
+                // a request without replies already times out.
+                (Direction::Timeout, _) => (),
+            }
+        }
+        ReplayTransport { exchanges, pending }
+    }
+
+    pub fn open(path: &Path) -> Result<ReplayTransport, std::io::Error> {
+        let records = read_capture(BufReader::new(File::open(path)?))?;
+        Ok(ReplayTransport::new(records))
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn send(&mut self, payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        let request = frame_bytes(payload, toid);
+        let nonce = ping_nonce(&request);
+        let matches = |ex: &&mut Exchange| {
+            ex.address == toid
+                && (ex.request == request || nonce.is_some() && ping_nonce(&ex.request).is_some())
+        };
+
+        // take recorded exchanges in order; once they're all used up, a repeated
+        // request gets the last recorded answer.
+        let found = match self
+            .exchanges
+            .iter_mut()
+            .filter(matches)
+            .find(|ex| !ex.used)
+        {
+            Some(ex) => Some(ex),
+            None => self.exchanges.iter_mut().filter(matches).last(),
+        };
+
+        // no match is like sending to an automato that never answers.
+        if let Some(ex) = found {
+            ex.used = true;
+            self.pending
+                .extend(ex.replies.iter().map(|(fromid, frame)| match nonce {
+                    Some(n) => (*fromid, renonce(frame, n)),
+                    None => (*fromid, frame.clone()),
+                }));
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        self.receive_raw().1
+    }
+
+    fn receive_raw(&mut self) -> (Vec<u8>, Result<(u8, Payload), serialport::Error>) {
+        match self.pending.pop_front() {
+            Some((fromid, frame)) => {
+                let r = decode_frame(&frame).map(|(_, payload)| (fromid, payload));
+                (frame, r)
+            }
+            None => (
+                Vec::new(),
+                Err(serialport::Error::new(
+                    serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut),
+                    "no recorded reply",
+                )),
+            ),
+        }
+    }
+
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    // replies are there straight away, or not at all.
+    fn timeout(&self) -> Duration {
+        Duration::ZERO
+    }
+}
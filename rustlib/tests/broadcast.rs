@@ -0,0 +1,80 @@
+mod common;
+
+use automato::automatomsg::{PayloadEnum, PayloadType, RH_BROADCAST_ADDRESS};
+use automato::mesh::{Faults, MeshConfig, SimulatedMesh};
+use automato::transport::{collect_replies, Transport};
+use common::{mesh, node, payload};
+use std::time::Duration;
+
+fn broadcast(t: &mut dyn Transport) {
+    t.send(&payload(PayloadEnum::readinfo()), RH_BROADCAST_ADDRESS)
+        .unwrap();
+}
+
+#[test]
+fn every_node_answers() {
+    let mut mesh = mesh(vec![
+        node(1, Faults::default()),
+        node(3, Faults::default()),
+        node(
+            5,
+            Faults {
+                latency_ms: 30,
+                ..Faults::default()
+            },
+        ),
+        // broadcasts aren't acknowledged, so router failures are silent.
+        node(
+            7,
+            Faults {
+                no_route: 1.0,
+                ..Faults::default()
+            },
+        ),
+    ]);
+    mesh.set_timeout(Duration::from_millis(1234)).unwrap();
+
+    broadcast(&mut mesh);
+    let start = mesh.now();
+    let replies = collect_replies(&mut mesh, Duration::from_millis(100)).unwrap();
+    // the window runs on the mesh clock.
+    assert_eq!(mesh.now() - start, Duration::from_millis(100));
+
+    assert_eq!(replies.keys().copied().collect::<Vec<u8>>(), vec![1, 3, 5]);
+    assert!(replies
+        .values()
+        .all(|p| p.payload_type == PayloadType::PtReadinforeply));
+    assert_eq!(mesh.timeout(), Duration::from_millis(1234));
+}
+
+#[test]
+fn window_leaves_out_late_replies() {
+    let mut mesh = mesh(vec![
+        node(1, Faults::default()),
+        node(
+            3,
+            Faults {
+                latency_ms: 200,
+                ..Faults::default()
+            },
+        ),
+    ]);
+
+    broadcast(&mut mesh);
+    let replies = collect_replies(&mut mesh, Duration::from_millis(50)).unwrap();
+    assert_eq!(replies.keys().copied().collect::<Vec<u8>>(), vec![1]);
+
+    // the slow one is still on its way.
+    mesh.set_timeout(Duration::from_millis(200)).unwrap();
+    let (fromid, _) = mesh.receive().unwrap();
+    assert_eq!(fromid, 3);
+}
+
+#[test]
+fn no_node_at_broadcast_address() {
+    let config = MeshConfig {
+        nodes: vec![node(RH_BROADCAST_ADDRESS, Faults::default())],
+        ..MeshConfig::default()
+    };
+    assert!(SimulatedMesh::new(&config).is_err());
+}
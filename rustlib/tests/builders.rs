@@ -0,0 +1,101 @@
+use automato::automatomsg as am;
+use automato::automatomsg::{
+    BuildError, FieldFormat, Msgbuf, Payload, PayloadEnum, ResultCode, MAX_READMEM, MAX_WRITEMEM,
+};
+use std::convert::TryFrom;
+
+fn too_long(what: &'static str, length: usize, max: usize) -> Result<PayloadEnum, BuildError> {
+    Err(BuildError::TooLong { what, length, max })
+}
+
+#[test]
+fn builders_check_lengths() {
+    let big = [0; 300];
+    assert_eq!(
+        PayloadEnum::writemem(10, &big[0..MAX_WRITEMEM + 1]),
+        too_long("writemem data", 248, 247)
+    );
+    assert!(PayloadEnum::writemem(10, &big[0..MAX_WRITEMEM]).is_ok());
+    assert_eq!(
+        PayloadEnum::readmemreply(&big),
+        too_long("readmemreply data", 300, 249)
+    );
+    assert_eq!(
+        PayloadEnum::readmem(0, 250),
+        too_long("readmem length", 250, 249)
+    );
+    assert!(PayloadEnum::readmem(0, MAX_READMEM as u8).is_ok());
+    assert_eq!(
+        PayloadEnum::readfieldreply(0, 0, 4, FieldFormat::FfFloat, "lowertargethumidity_26chrs"),
+        too_long("field name", 26, 25)
+    );
+}
+
+#[test]
+fn builders_match_setup() {
+    // a builder and its setup_* fn put the same bytes on the wire.
+    let pe = PayloadEnum::readfieldreply(7, 77, 20, FieldFormat::FfUint32, "wat").unwrap();
+    let mut mb = Msgbuf {
+        buf: [0xff; am::RH_RF95_MAX_MESSAGE_LEN],
+    };
+    let rc = am::setup_readfieldreply(
+        unsafe { &mut mb.payload },
+        7,
+        77,
+        20,
+        FieldFormat::FfUint32,
+        b"wat",
+    );
+    assert_eq!(rc, ResultCode::RcOk);
+    assert_eq!(
+        am::encode_payload(&Payload::try_from(pe).unwrap()),
+        am::encode_payload(unsafe { &mb.payload })
+    );
+}
+
+#[test]
+fn setup_leaves_payload_on_failure() {
+    let mut p = Payload::try_from(PayloadEnum::readpin(5)).unwrap();
+    let before = am::encode_payload(&p);
+    assert_eq!(
+        am::setup_writemem(&mut p, 0, &[0; 248]),
+        ResultCode::RcInvalidMemLength
+    );
+    assert_eq!(
+        am::setup_readmemreply(&mut p, &[0; 250]),
+        ResultCode::RcInvalidMemLength
+    );
+    assert_eq!(
+        am::setup_readfieldreply(&mut p, 0, 0, 1, FieldFormat::FfUint8, &[b'a'; 26]),
+        ResultCode::RcInvalidMemLength
+    );
+    assert_eq!(am::encode_payload(&p), before);
+}
+
+#[test]
+fn validate_checks_field_format() {
+    let mut pe = PayloadEnum::readfieldreply(0, 0, 1, FieldFormat::FfOther, "x").unwrap();
+    assert_eq!(pe.validate(), Ok(()));
+    if let PayloadEnum::PeReadfieldreply(ref mut rfr) = pe {
+        rfr.format = 9;
+    }
+    assert_eq!(pe.validate(), Err(BuildError::InvalidFieldFormat(9)));
+}
+
+#[test]
+fn spi_modes() {
+    assert!(PayloadEnum::spitransfer(5, 3, 1_000_000, &[0]).is_ok());
+    assert_eq!(
+        PayloadEnum::spitransfer(5, 4, 1_000_000, &[0]),
+        Err(BuildError::InvalidSpiMode(4))
+    );
+    let mut p = Payload::try_from(PayloadEnum::ack()).unwrap();
+    assert_eq!(
+        am::setup_spitransfer(&mut p, 5, 4, 1_000_000, &[0]),
+        ResultCode::RcInvalidMessageType
+    );
+    assert_eq!(
+        am::setup_spitransfer(&mut p, 5, 0, 1_000_000, &[0; 244]),
+        ResultCode::RcInvalidMemLength
+    );
+}
@@ -0,0 +1,131 @@
+use automato::automatomsg as am;
+use automato::automatomsg::{Payload, PayloadEnum, Pinval};
+use automato::capture::{read_capture, CaptureRecord, Direction};
+use automato::transport::{RecordingTransport, Transport};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::BufReader;
+use std::time::Duration;
+
+// answers every message with an ack from the addressee.
+struct AckTransport {
+    pending: VecDeque<u8>,
+}
+
+impl Transport for AckTransport {
+    fn send(&mut self, _payload: &Payload, toid: u8) -> Result<(), serialport::Error> {
+        self.pending.push_back(toid);
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        match self.pending.pop_front() {
+            Some(id) => Ok((id, Payload::try_from(PayloadEnum::PeAck).unwrap())),
+            None => Err(serialport::Error::new(
+                serialport::ErrorKind::Io(std::io::ErrorKind::TimedOut),
+                "no reply",
+            )),
+        }
+    }
+
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[test]
+fn record_and_read_back() {
+    let path = std::env::temp_dir().join(format!("automato-capture-{}.jsonl", std::process::id()));
+    let writepin =
+        Payload::try_from(PayloadEnum::PeWritepin(Pinval { pin: 15, state: 1 })).unwrap();
+    {
+        let inner = Box::new(AckTransport {
+            pending: VecDeque::new(),
+        });
+        let mut rt = RecordingTransport::create(inner, &path).unwrap();
+        rt.send(&writepin, 3).unwrap();
+        let (fromid, _) = rt.receive().unwrap();
+        assert_eq!(fromid, 3);
+        // a timeout is recorded against the last destination.
+        assert!(rt.receive().is_err());
+    }
+
+    let records = read_capture(BufReader::new(File::open(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let strip = |r: &CaptureRecord| (r.direction, r.address, r.frame.clone());
+    assert_eq!(
+        records.iter().map(strip).collect::<Vec<_>>(),
+        vec![
+            (Direction::Send, 3, am::frame_bytes(&writepin, 3)),
+            (Direction::Receive, 3, vec![b'm', 3, 1, 0]),
+            (Direction::Timeout, 3, vec![]),
+        ]
+    );
+    assert!(records[0].timestamp <= records[1].timestamp);
+}
+
+// a node sending garbage: a frame of an unknown type, then half a frame.
+struct BadNode {
+    pending: VecDeque<(Vec<u8>, std::io::ErrorKind)>,
+}
+
+impl Transport for BadNode {
+    fn send(&mut self, _payload: &Payload, _toid: u8) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        self.receive_raw().1
+    }
+
+    fn receive_raw(&mut self) -> (Vec<u8>, Result<(u8, Payload), serialport::Error>) {
+        let (raw, kind) = self.pending.pop_front().unwrap();
+        let e = serialport::Error::new(serialport::ErrorKind::Io(kind), "bad");
+        (raw, Err(e))
+    }
+
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[test]
+fn records_bad_frames() {
+    let path =
+        std::env::temp_dir().join(format!("automato-badframes-{}.jsonl", std::process::id()));
+    {
+        let inner = Box::new(BadNode {
+            pending: VecDeque::from(vec![
+                (vec![b'm', 9, 2, 99, 1], std::io::ErrorKind::InvalidData),
+                (vec![b'm', 9, 5, 12], std::io::ErrorKind::TimedOut),
+            ]),
+        });
+        let mut rt = RecordingTransport::create(inner, &path).unwrap();
+        rt.send(&Payload::try_from(PayloadEnum::PeReadhumidity).unwrap(), 9)
+            .unwrap();
+        assert!(rt.receive().is_err());
+        assert!(rt.receive().is_err());
+    }
+
+    let records = read_capture(BufReader::new(File::open(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let strip = |r: &CaptureRecord| (r.direction, r.address, r.frame.clone());
+    assert_eq!(
+        records.iter().skip(1).map(strip).collect::<Vec<_>>(),
+        vec![
+            (Direction::Receive, 9, vec![b'm', 9, 2, 99, 1]),
+            (Direction::Timeout, 9, vec![b'm', 9, 5, 12]),
+        ]
+    );
+}
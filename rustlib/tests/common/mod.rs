@@ -0,0 +1,41 @@
+// fixtures shared by the integration tests.  each test file is a crate of its
+// own, and uses only some of them.
+#![allow(dead_code)]
+
+use automato::automatomsg::{Payload, PayloadEnum};
+use automato::mesh::{Faults, MeshConfig, SimulatedMesh};
+use automato::simulator::NodeConfig;
+use automato::transport::Transport;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+pub fn payload(pe: PayloadEnum) -> Payload {
+    Payload::try_from(pe).unwrap()
+}
+
+pub fn node(address: u8, faults: Faults) -> NodeConfig {
+    NodeConfig {
+        address,
+        faults,
+        ..NodeConfig::default()
+    }
+}
+
+pub fn mesh(nodes: Vec<NodeConfig>) -> SimulatedMesh {
+    seeded_mesh(0, nodes)
+}
+
+// a mesh whose faults are drawn from 'seed'.
+pub fn seeded_mesh(seed: u64, nodes: Vec<NodeConfig>) -> SimulatedMesh {
+    let mut mesh = SimulatedMesh::new(&MeshConfig { seed, nodes }).unwrap();
+    mesh.set_timeout(Duration::from_millis(20)).unwrap();
+    mesh
+}
+
+// send 'pe' to 'toid', and the reply if one comes in time.
+pub fn request(t: &mut dyn Transport, pe: PayloadEnum, toid: u8) -> Option<(u8, PayloadEnum)> {
+    t.send(&payload(pe), toid).unwrap();
+    t.receive()
+        .ok()
+        .map(|(fromid, p)| (fromid, PayloadEnum::from(p)))
+}
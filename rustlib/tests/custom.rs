@@ -0,0 +1,151 @@
+use automato::automatomsg::PayloadEnum;
+use automato::custom::{
+    sketch_codecs, CodecError, CodecRegistry, CustomBody, HumidifyCycle, HumidorStatus,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[test]
+fn typed_bodies() {
+    let pe = HumidifyCycle { seconds: 30 }.to_payload().unwrap();
+    assert_eq!(pe, PayloadEnum::custom(1, &[30, 0]).unwrap());
+    assert_eq!(
+        HumidifyCycle::from_payload(&pe),
+        Some(Ok(HumidifyCycle { seconds: 30 }))
+    );
+    // some other subtype.
+    assert_eq!(HumidorStatus::from_payload(&pe), None);
+    assert!(matches!(
+        HumidorStatus::from_body(&[1, 2]),
+        Err(CodecError::BadBody(_))
+    ));
+}
+
+#[test]
+fn build_by_name() {
+    let codecs = sketch_codecs();
+    assert_eq!(
+        codecs
+            .build("humidifycycle", &json!({ "seconds": 300 }))
+            .unwrap(),
+        PayloadEnum::custom(1, &[0x2c, 0x01]).unwrap()
+    );
+    assert_eq!(
+        codecs.build("dehumidify", &json!({})).unwrap_err(),
+        CodecError::UnknownName("dehumidify".to_string())
+    );
+    assert!(matches!(
+        codecs.build("humidifycycle", &json!({ "minutes": 5 })),
+        Err(CodecError::BadBody(_))
+    ));
+}
+
+#[test]
+fn json_both_ways() {
+    let codecs = sketch_codecs();
+    let pe = HumidorStatus {
+        humidifying: true,
+        cycles: 7,
+    }
+    .to_payload()
+    .unwrap();
+
+    let v = codecs.to_json(&pe).unwrap();
+    assert_eq!(
+        v,
+        json!({ "PeCustom": {
+            "subtype": 2,
+            "data": [1, 7, 0, 0, 0],
+            "name": "humidorstatus",
+            "body": { "humidifying": true, "cycles": 7 },
+        }})
+    );
+    // still reads as a plain PayloadEnum.
+    assert_eq!(
+        serde_json::from_value::<PayloadEnum>(v.clone()).unwrap(),
+        pe
+    );
+    assert_eq!(codecs.from_json(&v).unwrap(), pe);
+
+    // or by name alone.
+    let named = json!({ "PeCustom": {
+        "name": "humidorstatus",
+        "body": { "humidifying": true, "cycles": 7 },
+    }});
+    assert_eq!(codecs.from_json(&named).unwrap(), pe);
+
+    // unknown subtypes and other payloads are left alone.
+    let unknown = PayloadEnum::custom(99, &[1, 2, 3]).unwrap();
+    assert_eq!(
+        codecs.to_json(&unknown).unwrap(),
+        serde_json::to_value(&unknown).unwrap()
+    );
+    assert_eq!(
+        codecs.from_json(&json!("PeReadhumidity")).unwrap(),
+        PayloadEnum::PeReadhumidity
+    );
+}
+
+#[test]
+fn display_decoded() {
+    let codecs = sketch_codecs();
+    let pe = HumidorStatus {
+        humidifying: false,
+        cycles: 12,
+    }
+    .to_payload()
+    .unwrap();
+    // body fields come out in json order, which is sorted.
+    assert_eq!(
+        codecs.display(&pe).to_string(),
+        "custom subtype 2 name humidorstatus cycles 12 humidifying false"
+    );
+    assert_eq!(
+        format!("{:#}", codecs.display(&pe)),
+        "custom\n  subtype: 2\n  name: humidorstatus\n  cycles: 12\n  humidifying: false"
+    );
+
+    // a body that doesn't decode shows raw.
+    let bad = PayloadEnum::custom(2, &[1]).unwrap();
+    assert_eq!(codecs.display(&bad).to_string(), bad.to_string());
+}
+
+// a codec from outside the crate.
+#[derive(Serialize, Deserialize)]
+struct Mist(u8);
+
+impl CustomBody for Mist {
+    const SUBTYPE: u8 = 1;
+    const NAME: &'static str = "mist";
+
+    fn to_body(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn from_body(body: &[u8]) -> Result<Mist, CodecError> {
+        match body {
+            [b] => Ok(Mist(*b)),
+            _ => Err(CodecError::BadBody("mist is one byte".to_string())),
+        }
+    }
+}
+
+#[test]
+fn register_checks_duplicates() {
+    let mut codecs = sketch_codecs();
+    assert!(matches!(
+        codecs.register::<Mist>(),
+        Err(CodecError::Duplicate(_))
+    ));
+    assert!(matches!(
+        codecs.register::<HumidorStatus>(),
+        Err(CodecError::Duplicate(_))
+    ));
+
+    let mut codecs = CodecRegistry::new();
+    codecs.register::<Mist>().unwrap();
+    assert_eq!(
+        codecs.to_json(&Mist(4).to_payload().unwrap()).unwrap()["PeCustom"]["body"],
+        json!(4)
+    );
+}
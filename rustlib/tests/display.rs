@@ -0,0 +1,78 @@
+use automato::automatomsg as am;
+use automato::automatomsg::{FieldFormat, Payload, PayloadEnum, ResultCode};
+use automato::capture::{CaptureRecord, Direction};
+use std::convert::TryFrom;
+
+#[test]
+fn compact() {
+    let cases = [
+        (PayloadEnum::ack(), "ack"),
+        (
+            PayloadEnum::fail(ResultCode::RcRhRouterErrorNoRoute),
+            "fail code RcRhRouterErrorNoRoute",
+        ),
+        (PayloadEnum::PeFail(200), "fail code 200"),
+        (PayloadEnum::writepin(15, 1), "writepin pin 15 state 1"),
+        (
+            PayloadEnum::writemem(5678, &[5, 4, 3, 2, 1]).unwrap(),
+            "writemem address 5678 length 5 data 0504030201",
+        ),
+        (
+            PayloadEnum::readinforeply(1.5, 0x7c9ebd3a5f10, 56, 3),
+            "readinforeply protoversion 1.5 mac 7c9ebd3a5f10 datalen 56 fieldcount 3",
+        ),
+        (
+            PayloadEnum::readfieldreply(7, 77, 4, FieldFormat::FfUint32, "wat").unwrap(),
+            "readfieldreply index 7 offset 77 length 4 format FfUint32 name \"wat\"",
+        ),
+        (
+            PayloadEnum::servo_angle(13, 45, None),
+            "servo pin 13 pulse 1008 angle 45 speed max",
+        ),
+    ];
+    for (pe, s) in cases {
+        assert_eq!(pe.to_string(), s);
+        assert_eq!(Payload::try_from(pe).unwrap().to_string(), s);
+    }
+}
+
+#[test]
+fn verbose() {
+    let data: Vec<u8> = (0..18).collect();
+    let pe = PayloadEnum::readmemreply(&data).unwrap();
+    assert_eq!(
+        format!("{:#}", pe),
+        "readmemreply\n  length: 18\n  \
+         0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n  \
+         0010: 10 11"
+    );
+    assert_eq!(
+        format!("{:#}", PayloadEnum::readpin(22)),
+        "readpin\n  pin: 22"
+    );
+}
+
+#[test]
+fn capture_records() {
+    let p = Payload::try_from(PayloadEnum::readhumidityreply(47.5)).unwrap();
+    let mut r = CaptureRecord::now(Direction::Receive, 3, am::frame_bytes(&p, 3));
+    r.timestamp = 1000;
+    assert_eq!(r.to_string(), "1000 <- 3 readhumidityreply humidity 47.5");
+    assert_eq!(
+        format!("{:#}", r),
+        "1000 <- 3 readhumidityreply\n  humidity: 47.5\n  frame:\n  0000: 6d 03 05 0c 00 00 3e 42"
+    );
+
+    r.direction = Direction::Send;
+    r.frame = vec![b'm', 3, 2, 0x63, 1];
+    assert_eq!(
+        r.to_string(),
+        "1000 -> 3 invalid frame (invalid payload: RcInvalidMessageType) 6d03026301"
+    );
+
+    r.direction = Direction::Timeout;
+    r.frame = vec![];
+    assert_eq!(r.to_string(), "1000 .. 3 timeout");
+    r.frame = vec![b'm', 3, 5, 0x0c];
+    assert_eq!(r.to_string(), "1000 .. 3 timeout mid frame 6d03050c");
+}
@@ -0,0 +1 @@
+"PeAck"
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeCustom":{"subtype":1,"data":[30,0]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeFactoryReset":1162889559}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeFail":14}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeFragment":{"id":7,"index":1,"count":3,"data":[1,2,3]}}
\ No newline at end of file
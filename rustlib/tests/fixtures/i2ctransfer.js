@@ -0,0 +1 @@
+{"PeI2cTransfer":{"address":68,"readlength":6,"data":[36,0]}}
\ No newline at end of file
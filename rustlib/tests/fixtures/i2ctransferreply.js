@@ -0,0 +1 @@
+{"PeI2cTransferreply":{"address":68,"status":0,"data":[102,78,90,138,60,177]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeOnewirescanreply":{"pin":4,"roms":[[40,255,100,30,130,22,3,60],[40,97,100,18,60,124,47,39]]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PePing":305419896}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PePinmode":{"pin":26,"mode":2}}
\ No newline at end of file
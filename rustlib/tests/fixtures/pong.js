@@ -0,0 +1 @@
+{"PePong":{"nonce":305419896,"millis":1000}}
\ No newline at end of file
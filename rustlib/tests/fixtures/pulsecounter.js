@@ -0,0 +1 @@
+{"PePulsecounter":{"pin":18,"edge":1}}
\ No newline at end of file
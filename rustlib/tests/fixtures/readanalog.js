@@ -0,0 +1 @@
+{"PeReadanalog":27}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadanalogreply":{"pin":6,"state":500}}
\ No newline at end of file
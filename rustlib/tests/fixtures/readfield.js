@@ -0,0 +1 @@
+{"PeReadfield":{"index":1}}
\ No newline at end of file
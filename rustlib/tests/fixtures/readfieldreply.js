@@ -0,0 +1 @@
+{"PeReadfieldreply":{"index":7,"offset":77,"length":20,"format":4,"name":[119,97,116,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadfieldreply":{"index":0,"offset":0,"length":25,"format":0,"name":[108,111,119,101,114,116,97,114,103,101,116,104,117,109,105,100,105,116,121,95,50,53,99,104,114]}}
\ No newline at end of file
@@ -0,0 +1 @@
+"PeReadhumidity"
\ No newline at end of file
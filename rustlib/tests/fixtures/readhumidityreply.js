@@ -0,0 +1 @@
+{"PeReadhumidityreply":45.7}
\ No newline at end of file
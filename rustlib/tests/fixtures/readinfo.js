@@ -0,0 +1 @@
+"PeReadinfo"
\ No newline at end of file
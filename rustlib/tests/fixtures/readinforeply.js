@@ -0,0 +1 @@
+{"PeReadinforeply":{"protoversion":1.1,"mac_address":5678,"datalen":5000,"fieldcount":5}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadmem":{"address":1500,"length":75}}
\ No newline at end of file
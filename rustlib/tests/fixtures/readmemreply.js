@@ -0,0 +1 @@
+{"PeReadmemreply":{"data":[1,2,3,4,5]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadmemreply":{"data":[]}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadonewiretemperaturereply":{"pin":4,"rom":[40,255,100,30,130,22,3,60],"temperature":21.5}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReadpin":22}
\ No newline at end of file
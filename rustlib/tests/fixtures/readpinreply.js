@@ -0,0 +1 @@
+{"PeReadpinreply":{"pin":26,"state":1}}
\ No newline at end of file
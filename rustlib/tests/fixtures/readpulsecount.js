@@ -0,0 +1 @@
+{"PeReadpulsecount":{"pin":18,"reset":1}}
\ No newline at end of file
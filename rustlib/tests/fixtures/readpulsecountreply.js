@@ -0,0 +1 @@
+{"PeReadpulsecountreply":{"pin":18,"count":1234,"frequency":12.5}}
\ No newline at end of file
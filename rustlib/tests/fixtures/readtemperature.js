@@ -0,0 +1 @@
+"PeReadtemperature"
\ No newline at end of file
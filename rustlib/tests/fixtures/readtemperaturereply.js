@@ -0,0 +1 @@
+{"PeReadtemperaturereply":98.6}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeReboot":1414483778}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeSecure":{"counter":72623859790382856,"data":[222,173,190,239]}}
\ No newline at end of file
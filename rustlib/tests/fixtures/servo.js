@@ -0,0 +1 @@
+{"PeServo":{"pin":13,"pulse":1500,"speed":60}}
\ No newline at end of file
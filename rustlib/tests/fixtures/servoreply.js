@@ -0,0 +1 @@
+{"PeServoreply":{"pin":13,"pulse":1500}}
\ No newline at end of file
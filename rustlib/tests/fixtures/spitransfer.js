@@ -0,0 +1 @@
+{"PeSpiTransfer":{"cspin":5,"mode":3,"clock":20000000,"data":[138,0,0]}}
\ No newline at end of file
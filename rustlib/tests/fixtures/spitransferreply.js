@@ -0,0 +1 @@
+{"PeSpiTransferreply":{"cspin":5,"data":[255,94,75]}}
\ No newline at end of file
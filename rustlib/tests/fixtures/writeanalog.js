@@ -0,0 +1 @@
+{"PeWriteanalog":{"pin":25,"duty":32768,"frequency":5000}}
\ No newline at end of file
@@ -0,0 +1 @@
+{"PeWritemem":{"address":5678,"data":[5,4,3,2,1]}}
\ No newline at end of file
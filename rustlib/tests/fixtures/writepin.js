@@ -0,0 +1 @@
+{"PeWritepin":{"pin":15,"state":1}}
\ No newline at end of file
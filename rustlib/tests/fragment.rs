@@ -0,0 +1,121 @@
+mod common;
+
+use automato::automatomsg as am;
+use automato::automatomsg::{Payload, PayloadEnum, PayloadType, ResultCode};
+use automato::fragment::{fragment, FragmentingTransport, NodeMtu, Reassembly, MIN_MTU};
+use automato::mesh::SimulatedMesh;
+use automato::simulator::NodeConfig;
+use common::{payload, request};
+
+fn big() -> Payload {
+    payload(PayloadEnum::readmemreply(&[0x5a; am::MAX_READMEM]).unwrap())
+}
+
+#[test]
+fn small_payloads_go_whole() {
+    let p = payload(PayloadEnum::readpin(22));
+    let fragments = fragment(&p, 0, am::RH_RF69_MAX_MESSAGE_LEN);
+    assert_eq!(fragments.len(), 1);
+    assert_eq!(fragments[0].payload_type, PayloadType::PtReadpin);
+}
+
+#[test]
+fn split_and_reassemble() {
+    for mtu in [MIN_MTU, am::RH_RF69_MAX_MESSAGE_LEN, 100, 250] {
+        let fragments = fragment(&big(), 9, mtu);
+        assert!(fragments.len() > 1);
+        assert!(fragments.iter().all(|f| am::payload_size(f) <= mtu));
+
+        // out of order and with a duplicate is fine.
+        let mut r = Reassembly::default();
+        let (last, rest) = fragments.split_last().unwrap();
+        for f in rest.iter().rev().chain(rest.first()) {
+            assert_eq!(r.add(f).unwrap().map(PayloadEnum::from), None);
+        }
+        assert_eq!(
+            r.add(last).unwrap().map(PayloadEnum::from),
+            Some(PayloadEnum::from(big()))
+        );
+    }
+}
+
+#[test]
+fn new_message_drops_partial() {
+    let first = fragment(&big(), 1, 60);
+    let second = fragment(&big(), 2, 60);
+    let mut r = Reassembly::default();
+    // the last fragment of the first message is lost.
+    for f in first[..first.len() - 1].iter() {
+        r.add(f).unwrap();
+    }
+    for f in second[..second.len() - 1].iter() {
+        assert!(r.add(f).unwrap().is_none());
+    }
+    assert!(r.add(second.last().unwrap()).unwrap().is_some());
+    // a straggler from the first message doesn't complete anything.
+    assert!(r.add(first.last().unwrap()).unwrap().is_none());
+}
+
+#[test]
+fn rejects_bad_fragments() {
+    let mut r = Reassembly::default();
+    let bad = payload(PayloadEnum::fragment(0, 3, 3, &[1]).unwrap());
+    assert_eq!(r.add(&bad).unwrap_err(), ResultCode::RcInvalidMessageType);
+
+    // a fragment of fragments.
+    let inner = payload(PayloadEnum::fragment(0, 0, 1, &[1, 2]).unwrap());
+    let outer = payload(PayloadEnum::fragment(1, 0, 1, &am::encode_payload(&inner)).unwrap());
+    assert_eq!(r.add(&outer).unwrap_err(), ResultCode::RcInvalidMessageType);
+
+    // unknown type once put together.
+    let junk = payload(PayloadEnum::fragment(2, 0, 1, &[99, 1, 2]).unwrap());
+    assert_eq!(r.add(&junk).unwrap_err(), ResultCode::RcInvalidMessageType);
+}
+
+// node 3 has an RFM69.
+fn mesh() -> SimulatedMesh {
+    common::mesh(vec![
+        NodeConfig::default(),
+        NodeConfig {
+            address: 3,
+            mtu: Some(am::RH_RF69_MAX_MESSAGE_LEN),
+            datalen: 200,
+            ..NodeConfig::default()
+        },
+    ])
+}
+
+#[test]
+fn transport_fragments_for_small_mtu() {
+    let mtus = [NodeMtu {
+        id: 3,
+        mtu: am::RH_RF69_MAX_MESSAGE_LEN,
+    }];
+    let mut t = FragmentingTransport::new(Box::new(mesh()), &mtus).unwrap();
+
+    let data: Vec<u8> = (0..200).collect();
+    assert_eq!(
+        request(&mut t, PayloadEnum::writemem(0, &data).unwrap(), 3),
+        Some((3, PayloadEnum::PeAck))
+    );
+    assert_eq!(
+        request(&mut t, PayloadEnum::readmem(0, 200).unwrap(), 3),
+        Some((3, PayloadEnum::readmemreply(&data).unwrap()))
+    );
+}
+
+#[test]
+fn radio_refuses_oversize_payloads() {
+    // without fragmenting, the gateway can't send it.
+    let mut m = mesh();
+    assert_eq!(
+        request(&mut m, PayloadEnum::writemem(0, &[1; 100]).unwrap(), 3),
+        Some((
+            3,
+            PayloadEnum::fail(ResultCode::RcRhRouterErrorInvalidLength)
+        ))
+    );
+
+    // and the mtu has to leave room for fragment data.
+    assert!(FragmentingTransport::new(Box::new(m), &[NodeMtu { id: 3, mtu: 5 }]).is_err());
+}
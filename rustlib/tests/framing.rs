@@ -0,0 +1,160 @@
+use automato::automatomsg as am;
+use automato::automatomsg::{Payload, PayloadEnum, ReadLimits};
+use std::convert::TryFrom;
+use std::io::{Cursor, ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+fn frame(pe: PayloadEnum, id: u8) -> Vec<u8> {
+    let mut out = Vec::new();
+    am::write_message(&mut out, &Payload::try_from(pe).unwrap(), id).unwrap();
+    out
+}
+
+fn kind(e: serialport::Error) -> serialport::ErrorKind {
+    e.kind
+}
+
+#[test]
+fn write_is_exact() {
+    // 'm', id, length, then just the payload.
+    assert_eq!(frame(PayloadEnum::readpin(22), 3), vec![b'm', 3, 2, 3, 22]);
+}
+
+#[test]
+fn skips_and_counts_noise() {
+    let mut bytes = vec![0, 0xff, b'x', b'y'];
+    bytes.extend(frame(PayloadEnum::readhumidityreply(47.5), 3));
+    bytes.extend(frame(PayloadEnum::ack(), 4));
+
+    let mut port = Cursor::new(bytes);
+    let limits = ReadLimits::default();
+    let f = am::read_message(&mut port, &limits).unwrap();
+    assert_eq!(
+        (f.id, PayloadEnum::from(f.payload), f.skipped),
+        (3, PayloadEnum::readhumidityreply(47.5), 4)
+    );
+    let f = am::read_message(&mut port, &limits).unwrap();
+    assert_eq!(
+        (f.id, PayloadEnum::from(f.payload), f.skipped),
+        (4, PayloadEnum::ack(), 0)
+    );
+    assert_eq!(
+        kind(am::read_message(&mut port, &limits).unwrap_err()),
+        serialport::ErrorKind::Io(ErrorKind::UnexpectedEof)
+    );
+}
+
+#[test]
+fn gives_up_on_noise() {
+    let mut bytes = vec![b'x'; 11];
+    bytes.extend(frame(PayloadEnum::ack(), 4));
+    let limits = ReadLimits {
+        max_skip: 10,
+        ..ReadLimits::default()
+    };
+    assert_eq!(
+        kind(am::read_message(&mut Cursor::new(&bytes), &limits).unwrap_err()),
+        serialport::ErrorKind::Io(ErrorKind::InvalidData)
+    );
+
+    // one less byte of noise is fine.
+    let f = am::read_message(&mut Cursor::new(&bytes[1..]), &limits).unwrap();
+    assert_eq!(f.skipped, 10);
+}
+
+#[test]
+fn rejects_bad_lengths() {
+    let limits = ReadLimits::default();
+    for bytes in [
+        vec![b'm', 3, 252],
+        // a readpin is 2 bytes.
+        vec![b'm', 3, 1, 3],
+        // unknown type.
+        vec![b'm', 3, 1, 99],
+    ] {
+        assert_eq!(
+            kind(am::read_message(&mut Cursor::new(bytes), &limits).unwrap_err()),
+            serialport::ErrorKind::Io(ErrorKind::InvalidData)
+        );
+    }
+}
+
+// a port that trickles out one byte per read, then times out forever.
+struct Trickle {
+    bytes: Vec<u8>,
+    delay: Duration,
+}
+
+fn trickle(bytes: Vec<u8>, delay_ms: u64) -> Trickle {
+    Trickle {
+        bytes,
+        delay: Duration::from_millis(delay_ms),
+    }
+}
+
+impl Read for Trickle {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        std::thread::sleep(self.delay);
+        if self.bytes.is_empty() {
+            Err(ErrorKind::TimedOut.into())
+        } else {
+            buf[0] = self.bytes.remove(0);
+            Ok(1)
+        }
+    }
+}
+
+#[test]
+fn deadline_covers_whole_message() {
+    let limits = ReadLimits {
+        deadline: Duration::from_millis(50),
+        frame: Duration::ZERO,
+        ..ReadLimits::default()
+    };
+
+    // a frame cut short times out at the deadline, not per byte.
+    let mut bytes = frame(PayloadEnum::readinforeply(1.0, 5678, 56, 3), 3);
+    bytes.truncate(8);
+    let start = Instant::now();
+    assert_eq!(
+        kind(am::read_message(&mut trickle(bytes, 1), &limits).unwrap_err()),
+        serialport::ErrorKind::Io(ErrorKind::TimedOut)
+    );
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(50) && elapsed < Duration::from_millis(500));
+
+    // endless noise also stops at the deadline, if max_skip doesn't stop it first.
+    let bytes = vec![b'x'; 10000];
+    assert_eq!(
+        kind(am::read_message(&mut trickle(bytes, 1), &limits).unwrap_err()),
+        serialport::ErrorKind::Io(ErrorKind::TimedOut)
+    );
+}
+
+#[test]
+fn slow_frame_arrives_whole() {
+    // a short deadline, as when polling; the frame takes several times that.
+    let limits = ReadLimits {
+        deadline: Duration::from_millis(20),
+        frame: Duration::from_millis(500),
+        ..ReadLimits::default()
+    };
+    let mut bytes = vec![b'x', b'y'];
+    bytes.extend(frame(PayloadEnum::readinforeply(1.0, 5678, 56, 3), 3));
+
+    let f = am::read_message(&mut trickle(bytes.clone(), 5), &limits).unwrap();
+    assert_eq!(
+        (f.id, PayloadEnum::from(f.payload), f.skipped),
+        (3, PayloadEnum::readinforeply(1.0, 5678, 56, 3), 2)
+    );
+
+    // without the frame allowance, it's cut off at the deadline.
+    let limits = ReadLimits {
+        frame: Duration::ZERO,
+        ..limits
+    };
+    assert_eq!(
+        kind(am::read_message(&mut trickle(bytes, 5), &limits).unwrap_err()),
+        serialport::ErrorKind::Io(ErrorKind::TimedOut)
+    );
+}
@@ -0,0 +1,492 @@
+// golden fixture tests: regression snapshots of the wire format.  each payload
+// type has a binary fixture with the bytes setup_* produced when the fixture
+// was made, and a json fixture with the PayloadEnum serialization that
+// matoserver and the elm client exchange.  the fixtures came from this crate,
+// not the C library, so they catch changes to the layout rather than prove it
+// matches the C side.
+
+use automato::automatomsg as am;
+use automato::automatomsg::{FieldFormat, I2cStatus, Msgbuf, Payload, ResultCode};
+
+fn encode(mb: &Msgbuf) -> Vec<u8> {
+    unsafe { mb.buf[0..am::payload_size(&mb.payload)].to_vec() }
+}
+
+fn golden(bin: &[u8], js: &str, setup: impl FnOnce(&mut Payload)) {
+    // setup_* writes exactly the fixture bytes.
+    let mut mb = Msgbuf {
+        buf: [0; am::RH_RF95_MAX_MESSAGE_LEN],
+    };
+    setup(unsafe { &mut mb.payload });
+    assert_eq!(encode(&mb), bin);
+
+    // fixture bytes decode to the fixture json.
+    let decoded = am::decode_payload(bin).unwrap();
+    assert_eq!(am::payload_size(&decoded), bin.len());
+    assert_eq!(serde_json::to_string(&decoded).unwrap(), js);
+    assert_eq!(
+        serde_json::to_string(&am::PayloadEnum::from(decoded)).unwrap(),
+        js
+    );
+
+    // fixture json encodes to the fixture bytes.
+    let p: Payload = serde_json::from_str(js).unwrap();
+    assert_eq!(am::encode_payload(&p), bin);
+}
+
+#[test]
+fn ack() {
+    golden(
+        include_bytes!("fixtures/ack.bin"),
+        include_str!("fixtures/ack.js"),
+        am::setup_ack,
+    );
+}
+
+#[test]
+fn fail() {
+    golden(
+        include_bytes!("fixtures/fail.bin"),
+        include_str!("fixtures/fail.js"),
+        |p| am::setup_fail(p, ResultCode::RcInvalidRhRouterError),
+    );
+}
+
+#[test]
+fn pinmode() {
+    golden(
+        include_bytes!("fixtures/pinmode.bin"),
+        include_str!("fixtures/pinmode.js"),
+        |p| am::setup_pinmode(p, 26, 2),
+    );
+}
+
+#[test]
+fn readpin() {
+    golden(
+        include_bytes!("fixtures/readpin.bin"),
+        include_str!("fixtures/readpin.js"),
+        |p| am::setup_readpin(p, 22),
+    );
+}
+
+#[test]
+fn readpinreply() {
+    golden(
+        include_bytes!("fixtures/readpinreply.bin"),
+        include_str!("fixtures/readpinreply.js"),
+        |p| am::setup_readpinreply(p, 26, 1),
+    );
+}
+
+#[test]
+fn writepin() {
+    golden(
+        include_bytes!("fixtures/writepin.bin"),
+        include_str!("fixtures/writepin.js"),
+        |p| am::setup_writepin(p, 15, 1),
+    );
+}
+
+#[test]
+fn readmem() {
+    golden(
+        include_bytes!("fixtures/readmem.bin"),
+        include_str!("fixtures/readmem.js"),
+        |p| am::setup_readmem(p, 1500, 75),
+    );
+}
+
+#[test]
+fn readmemreply() {
+    golden(
+        include_bytes!("fixtures/readmemreply.bin"),
+        include_str!("fixtures/readmemreply.js"),
+        |p| {
+            assert_eq!(
+                am::setup_readmemreply(p, &[1, 2, 3, 4, 5]),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn readmemreply_empty() {
+    golden(
+        include_bytes!("fixtures/readmemreply_empty.bin"),
+        include_str!("fixtures/readmemreply_empty.js"),
+        |p| assert_eq!(am::setup_readmemreply(p, &[]), ResultCode::RcOk),
+    );
+}
+
+#[test]
+fn readmemreply_max() {
+    let data: Vec<u8> = (0..249).map(|i: u32| (i * 7) as u8).collect();
+    golden(
+        include_bytes!("fixtures/readmemreply_max.bin"),
+        include_str!("fixtures/readmemreply_max.js"),
+        |p| assert_eq!(am::setup_readmemreply(p, &data), ResultCode::RcOk),
+    );
+}
+
+#[test]
+fn writemem() {
+    golden(
+        include_bytes!("fixtures/writemem.bin"),
+        include_str!("fixtures/writemem.js"),
+        |p| {
+            assert_eq!(
+                am::setup_writemem(p, 5678, &[5, 4, 3, 2, 1]),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn writemem_max() {
+    let data: Vec<u8> = (0..247).map(|i: u32| (255 - i) as u8).collect();
+    golden(
+        include_bytes!("fixtures/writemem_max.bin"),
+        include_str!("fixtures/writemem_max.js"),
+        |p| assert_eq!(am::setup_writemem(p, 65535, &data), ResultCode::RcOk),
+    );
+}
+
+#[test]
+fn readinfo() {
+    golden(
+        include_bytes!("fixtures/readinfo.bin"),
+        include_str!("fixtures/readinfo.js"),
+        am::setup_readinfo,
+    );
+}
+
+#[test]
+fn readinforeply() {
+    golden(
+        include_bytes!("fixtures/readinforeply.bin"),
+        include_str!("fixtures/readinforeply.js"),
+        |p| am::setup_readinforeply(p, 1.1, 5678, 5000, 5),
+    );
+}
+
+#[test]
+fn readhumidity() {
+    golden(
+        include_bytes!("fixtures/readhumidity.bin"),
+        include_str!("fixtures/readhumidity.js"),
+        am::setup_readhumidity,
+    );
+}
+
+#[test]
+fn readhumidityreply() {
+    golden(
+        include_bytes!("fixtures/readhumidityreply.bin"),
+        include_str!("fixtures/readhumidityreply.js"),
+        |p| am::setup_readhumidityreply(p, 45.7),
+    );
+}
+
+#[test]
+fn readtemperature() {
+    golden(
+        include_bytes!("fixtures/readtemperature.bin"),
+        include_str!("fixtures/readtemperature.js"),
+        am::setup_readtemperature,
+    );
+}
+
+#[test]
+fn readtemperaturereply() {
+    golden(
+        include_bytes!("fixtures/readtemperaturereply.bin"),
+        include_str!("fixtures/readtemperaturereply.js"),
+        |p| am::setup_readtemperaturereply(p, 98.6),
+    );
+}
+
+#[test]
+fn readanalog() {
+    golden(
+        include_bytes!("fixtures/readanalog.bin"),
+        include_str!("fixtures/readanalog.js"),
+        |p| am::setup_readanalog(p, 27),
+    );
+}
+
+#[test]
+fn readanalogreply() {
+    golden(
+        include_bytes!("fixtures/readanalogreply.bin"),
+        include_str!("fixtures/readanalogreply.js"),
+        |p| am::setup_readanalogreply(p, 6, 500),
+    );
+}
+
+#[test]
+fn readfield() {
+    golden(
+        include_bytes!("fixtures/readfield.bin"),
+        include_str!("fixtures/readfield.js"),
+        |p| am::setup_readfield(p, 1),
+    );
+}
+
+#[test]
+fn readfieldreply() {
+    golden(
+        include_bytes!("fixtures/readfieldreply.bin"),
+        include_str!("fixtures/readfieldreply.js"),
+        |p| {
+            assert_eq!(
+                am::setup_readfieldreply(p, 7, 77, 20, FieldFormat::FfUint32, "wat".as_bytes()),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn readfieldreply_maxname() {
+    golden(
+        include_bytes!("fixtures/readfieldreply_maxname.bin"),
+        include_str!("fixtures/readfieldreply_maxname.js"),
+        |p| {
+            assert_eq!(
+                am::setup_readfieldreply(
+                    p,
+                    0,
+                    0,
+                    25,
+                    FieldFormat::FfString,
+                    "lowertargethumidity_25chr".as_bytes(),
+                ),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn secure() {
+    golden(
+        include_bytes!("fixtures/secure.bin"),
+        include_str!("fixtures/secure.js"),
+        |p| {
+            assert_eq!(
+                am::setup_secure(p, 0x0102030405060708, &[0xde, 0xad, 0xbe, 0xef]),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn fragment() {
+    golden(
+        include_bytes!("fixtures/fragment.bin"),
+        include_str!("fixtures/fragment.js"),
+        |p| assert_eq!(am::setup_fragment(p, 7, 1, 3, &[1, 2, 3]), ResultCode::RcOk),
+    );
+}
+
+#[test]
+fn custom() {
+    golden(
+        include_bytes!("fixtures/custom.bin"),
+        include_str!("fixtures/custom.js"),
+        |p| assert_eq!(am::setup_custom(p, 1, &[30, 0]), ResultCode::RcOk),
+    );
+}
+
+#[test]
+fn writeanalog() {
+    golden(
+        include_bytes!("fixtures/writeanalog.bin"),
+        include_str!("fixtures/writeanalog.js"),
+        |p| am::setup_writeanalog(p, 25, 32768, 5000),
+    );
+}
+
+#[test]
+fn i2ctransfer() {
+    golden(
+        include_bytes!("fixtures/i2ctransfer.bin"),
+        include_str!("fixtures/i2ctransfer.js"),
+        |p| {
+            assert_eq!(
+                am::setup_i2ctransfer(p, 0x44, &[0x24, 0x00], 6),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn i2ctransferreply() {
+    golden(
+        include_bytes!("fixtures/i2ctransferreply.bin"),
+        include_str!("fixtures/i2ctransferreply.js"),
+        |p| {
+            assert_eq!(
+                am::setup_i2ctransferreply(
+                    p,
+                    0x44,
+                    I2cStatus::IsOk,
+                    &[0x66, 0x4e, 0x5a, 0x8a, 0x3c, 0xb1]
+                ),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn spitransfer() {
+    golden(
+        include_bytes!("fixtures/spitransfer.bin"),
+        include_str!("fixtures/spitransfer.js"),
+        |p| {
+            assert_eq!(
+                am::setup_spitransfer(p, 5, 3, 20_000_000, &[0x8a, 0x00, 0x00]),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn spitransferreply() {
+    golden(
+        include_bytes!("fixtures/spitransferreply.bin"),
+        include_str!("fixtures/spitransferreply.js"),
+        |p| {
+            assert_eq!(
+                am::setup_spitransferreply(p, 5, &[0xff, 0x5e, 0x4b]),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+const ROM: [u8; 8] = [0x28, 0xff, 0x64, 0x1e, 0x82, 0x16, 0x03, 0x3c];
+
+#[test]
+fn onewirescanreply() {
+    golden(
+        include_bytes!("fixtures/onewirescanreply.bin"),
+        include_str!("fixtures/onewirescanreply.js"),
+        |p| {
+            assert_eq!(
+                am::setup_onewirescanreply(
+                    p,
+                    4,
+                    &[ROM, [0x28, 0x61, 0x64, 0x12, 0x3c, 0x7c, 0x2f, 0x27]]
+                ),
+                ResultCode::RcOk
+            )
+        },
+    );
+}
+
+#[test]
+fn readonewiretemperature() {
+    golden(
+        include_bytes!("fixtures/readonewiretemperature.bin"),
+        include_str!("fixtures/readonewiretemperature.js"),
+        |p| am::setup_readonewiretemperature(p, 4, ROM),
+    );
+}
+
+#[test]
+fn readonewiretemperaturereply() {
+    golden(
+        include_bytes!("fixtures/readonewiretemperaturereply.bin"),
+        include_str!("fixtures/readonewiretemperaturereply.js"),
+        |p| am::setup_readonewiretemperaturereply(p, 4, ROM, 21.5),
+    );
+}
+
+#[test]
+fn servo() {
+    golden(
+        include_bytes!("fixtures/servo.bin"),
+        include_str!("fixtures/servo.js"),
+        |p| am::setup_servo(p, 13, 1500, 60),
+    );
+}
+
+#[test]
+fn servoreply() {
+    golden(
+        include_bytes!("fixtures/servoreply.bin"),
+        include_str!("fixtures/servoreply.js"),
+        |p| am::setup_servoreply(p, 13, 1500),
+    );
+}
+
+#[test]
+fn pulsecounter() {
+    golden(
+        include_bytes!("fixtures/pulsecounter.bin"),
+        include_str!("fixtures/pulsecounter.js"),
+        |p| am::setup_pulsecounter(p, 18, am::PulseEdge::PulseRising),
+    );
+}
+
+#[test]
+fn readpulsecount() {
+    golden(
+        include_bytes!("fixtures/readpulsecount.bin"),
+        include_str!("fixtures/readpulsecount.js"),
+        |p| am::setup_readpulsecount(p, 18, true),
+    );
+}
+
+#[test]
+fn readpulsecountreply() {
+    golden(
+        include_bytes!("fixtures/readpulsecountreply.bin"),
+        include_str!("fixtures/readpulsecountreply.js"),
+        |p| am::setup_readpulsecountreply(p, 18, 1234, 12.5),
+    );
+}
+
+#[test]
+fn reboot() {
+    golden(
+        include_bytes!("fixtures/reboot.bin"),
+        include_str!("fixtures/reboot.js"),
+        am::setup_reboot,
+    );
+}
+
+#[test]
+fn factoryreset() {
+    golden(
+        include_bytes!("fixtures/factoryreset.bin"),
+        include_str!("fixtures/factoryreset.js"),
+        am::setup_factoryreset,
+    );
+}
+
+#[test]
+fn ping() {
+    golden(
+        include_bytes!("fixtures/ping.bin"),
+        include_str!("fixtures/ping.js"),
+        |p| am::setup_ping(p, 0x1234_5678),
+    );
+}
+
+#[test]
+fn pong() {
+    golden(
+        include_bytes!("fixtures/pong.bin"),
+        include_str!("fixtures/pong.js"),
+        |p| am::setup_pong(p, 0x1234_5678, 1000),
+    );
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use automato::i2c::{scan, transfer, I2cError, Scan};
+use automato::mesh::Faults;
+use automato::simulator::{I2cDevice, NodeConfig};
+use common::{mesh, seeded_mesh};
+
+// a humidity sensor at 0x44, and a pressure sensor at 0x76.
+fn sensors() -> NodeConfig {
+    let device = |address, registers: &str| I2cDevice {
+        address,
+        registers: registers.to_string(),
+    };
+    NodeConfig {
+        i2c: vec![device(0x44, "0102030405"), device(0x76, "")],
+        ..NodeConfig::default()
+    }
+}
+
+fn lossy(faults: Faults) -> NodeConfig {
+    NodeConfig {
+        faults,
+        ..sensors()
+    }
+}
+
+#[test]
+fn scan_bus() {
+    let mut mesh = mesh(vec![sensors()]);
+    assert_eq!(
+        scan(&mut mesh, 1).unwrap(),
+        Scan {
+            found: vec![0x44, 0x76],
+            missed: vec![],
+        }
+    );
+}
+
+#[test]
+fn scan_lossy_mesh() {
+    // replies slower than the 20ms timeout turn up during the next address's
+    // transfer, and get passed over.
+    let slow = Faults {
+        latency_ms: 30,
+        ..Faults::default()
+    };
+    let mut mesh = mesh(vec![lossy(slow.clone())]);
+    assert_eq!(scan(&mut mesh, 1).unwrap().found, vec![0x44, 0x76]);
+
+    // lost messages and router fails leave gaps, but the scan goes on.
+    let mut mesh = seeded_mesh(
+        7,
+        vec![lossy(Faults {
+            loss: 0.3,
+            timeout: 0.1,
+            ..slow
+        })],
+    );
+    let s = scan(&mut mesh, 1).unwrap();
+    assert!(!s.missed.is_empty());
+    assert!(s.found.iter().all(|a| [0x44, 0x76].contains(a)));
+}
+
+#[test]
+fn transfers() {
+    let mut mesh = mesh(vec![sensors()]);
+    assert_eq!(transfer(&mut mesh, 1, 0x44, &[3], 2).unwrap(), vec![4, 5]);
+    // a device without registers reads like an empty bus.
+    assert_eq!(
+        transfer(&mut mesh, 1, 0x76, &[], 2).unwrap(),
+        vec![0xff, 0xff]
+    );
+
+    let e = transfer(&mut mesh, 1, 0x45, &[0], 1).unwrap_err();
+    assert!(matches!(e, I2cError::Bus(0x45, 2)));
+    assert_eq!(e.to_string(), "i2c 0x45: IsAddressNack");
+    assert!(matches!(
+        transfer(&mut mesh, 1, 0x44, &[0; 248], 0),
+        Err(I2cError::Build(_))
+    ));
+}
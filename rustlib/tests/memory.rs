@@ -0,0 +1,180 @@
+mod common;
+
+use automato::automatomsg::FieldFormat;
+use automato::memory::{AutomatoMemory, FixedStr, MemoryError, MemoryField};
+use automato::simulator::{MapField, NodeConfig};
+use common::mesh;
+
+// serialtolora.ino's ServerData, which NodeConfig::default() simulates.
+#[derive(AutomatoMemory, Debug, PartialEq)]
+struct SerialToLora {
+    name: FixedStr<25>,
+    targettemp: f32,
+    #[automato(unmapped)]
+    mac_address: u64,
+    loops: i32,
+    #[automato(unmapped)]
+    temperature: f32,
+    #[automato(unmapped)]
+    humidity: f32,
+}
+
+// humidor-remote.ino's.
+#[derive(AutomatoMemory)]
+struct HumidorRemote {
+    _name: FixedStr<25>,
+    _lowertargethumidity: f32,
+    _uppertargethumidity: f32,
+    _loops: u32,
+    #[automato(name = "checkInterval")]
+    _check_interval: u32,
+}
+
+fn field(name: &'static str, offset: u16, length: u8, format: FieldFormat) -> MemoryField {
+    MemoryField {
+        name,
+        offset,
+        length,
+        format,
+        mapped: true,
+    }
+}
+
+#[test]
+fn c_layout() {
+    assert_eq!(SerialToLora::SIZE, NodeConfig::default().datalen as usize);
+    let mapped: Vec<(u16, u8, FieldFormat)> = SerialToLora::FIELDS
+        .iter()
+        .filter(|f| f.mapped)
+        .map(|f| (f.offset, f.length, f.format))
+        .collect();
+    let node: Vec<(u16, u8, FieldFormat)> = NodeConfig::default()
+        .fields
+        .iter()
+        .map(|f| (f.offset, f.length, f.format))
+        .collect();
+    assert_eq!(mapped, node);
+    // the uint64_t is 8 aligned.
+    assert_eq!(SerialToLora::FIELDS[2].offset, 32);
+
+    assert_eq!(
+        HumidorRemote::FIELDS,
+        &[
+            field("_name", 0, 25, FieldFormat::FfString),
+            field("_lowertargethumidity", 28, 4, FieldFormat::FfFloat),
+            field("_uppertargethumidity", 32, 4, FieldFormat::FfFloat),
+            field("_loops", 36, 4, FieldFormat::FfUint32),
+            field("checkInterval", 40, 4, FieldFormat::FfUint32),
+        ]
+    );
+    assert_eq!(HumidorRemote::SIZE, 44);
+}
+
+#[test]
+fn read_and_write() {
+    let mut mesh = mesh(vec![NodeConfig::default()]);
+    SerialToLora::verify(&mut mesh, 1).unwrap();
+
+    let mut sd = SerialToLora::read(&mut mesh, 1).unwrap();
+    assert_eq!(sd.name.to_string(), "test 1 2 3");
+    assert_eq!(sd.targettemp, 42.0);
+    assert_eq!(sd.loops, 0);
+
+    sd.name = FixedStr::new("greenhouse").unwrap();
+    sd.loops = -5;
+    sd.mac_address = 0x7c9ebd3a5f10;
+    sd.write(&mut mesh, 1).unwrap();
+    assert_eq!(SerialToLora::read(&mut mesh, 1).unwrap(), sd);
+
+    assert!(FixedStr::<4>::new("too long").is_err());
+}
+
+#[test]
+fn mismatches() {
+    let mut mesh = mesh(vec![NodeConfig::default()]);
+
+    // wrong format.
+    #[derive(AutomatoMemory)]
+    struct Unsigned {
+        _name: FixedStr<25>,
+        _targettemp: f32,
+        #[automato(unmapped)]
+        _mac_address: u64,
+        _loops: u32,
+        #[automato(unmapped)]
+        _rest: [u8; 8],
+    }
+    let e = Unsigned::verify(&mut mesh, 1).unwrap_err();
+    assert!(matches!(e, MemoryError::Mismatch(_)), "{}", e);
+
+    // too small.
+    #[derive(AutomatoMemory)]
+    struct Short {
+        _name: FixedStr<25>,
+    }
+    assert!(matches!(
+        Short::verify(&mut mesh, 1),
+        Err(MemoryError::Mismatch(_))
+    ));
+
+    // right size, but targettemp isn't marked unmapped.
+    #[derive(AutomatoMemory)]
+    struct Missing {
+        #[automato(name = "name")]
+        _name: FixedStr<25>,
+        #[automato(name = "targettemp")]
+        _targettemp: f32,
+        #[automato(unmapped)]
+        _mac_address: u64,
+        #[automato(name = "loops")]
+        _loops: i32,
+        _temperature: f32,
+        #[automato(unmapped)]
+        _humidity: f32,
+    }
+    let e = Missing::verify(&mut mesh, 1).unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "memory map mismatch: '_temperature' isn't in the node's memoryMap[]"
+    );
+
+    // no such node.
+    assert!(SerialToLora::verify(&mut mesh, 9).is_err());
+}
+
+// bigger than one readmem or writemem.
+#[derive(AutomatoMemory, Debug, PartialEq)]
+struct Blobs {
+    first: [u8; 200],
+    second: [u8; 200],
+    count: u16,
+}
+
+#[test]
+fn chunked() {
+    let blob = |name: &str, offset, length, format| MapField {
+        name: name.to_string(),
+        offset,
+        length,
+        format,
+        value: None,
+    };
+    let mut mesh = mesh(vec![NodeConfig {
+        datalen: 402,
+        fields: vec![
+            blob("first", 0, 200, FieldFormat::FfOther),
+            blob("second", 200, 200, FieldFormat::FfOther),
+            blob("count", 400, 2, FieldFormat::FfUint16),
+        ],
+        ..NodeConfig::default()
+    }]);
+    Blobs::verify(&mut mesh, 1).unwrap();
+
+    let b = Blobs {
+        first: [1; 200],
+        second: [2; 200],
+        count: 513,
+    };
+    b.write(&mut mesh, 1).unwrap();
+    assert_eq!(Blobs::read(&mut mesh, 1).unwrap(), b);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use automato::automatomsg::{PayloadEnum, ResultCode};
+use automato::mesh::Faults;
+use automato::simulator::NodeConfig;
+use automato::transport::Transport;
+use common::{mesh, node, request, seeded_mesh};
+use std::time::Duration;
+
+// a node at 1, and one at 3 with 'faults'.
+fn nodes(faults: Faults) -> Vec<NodeConfig> {
+    vec![NodeConfig::default(), node(3, faults)]
+}
+
+fn fail(id: u8, rc: ResultCode) -> Option<(u8, PayloadEnum)> {
+    Some((id, PayloadEnum::PeFail(rc as u8)))
+}
+
+#[test]
+fn routes_by_address() {
+    let mut m = mesh(nodes(Faults::default()));
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 1),
+        Some((1, PayloadEnum::PeReadhumidityreply(45.0)))
+    );
+    m.node(3).unwrap().humidity = 60.0;
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 3),
+        Some((3, PayloadEnum::PeReadhumidityreply(60.0)))
+    );
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 7),
+        fail(7, ResultCode::RcRhRouterErrorNoRoute)
+    );
+}
+
+#[test]
+fn router_errors() {
+    let faults = |no_route, timeout, unable_to_deliver| Faults {
+        no_route,
+        timeout,
+        unable_to_deliver,
+        ..Faults::default()
+    };
+    let mut m = mesh(nodes(faults(1.0, 0.0, 0.0)));
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 3),
+        fail(3, ResultCode::RcRhRouterErrorNoRoute)
+    );
+    let mut m = mesh(nodes(faults(0.0, 1.0, 0.0)));
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 3),
+        fail(3, ResultCode::RcRhRouterErrorTimeout)
+    );
+    let mut m = mesh(nodes(faults(0.0, 0.0, 1.0)));
+    assert_eq!(
+        request(&mut m, PayloadEnum::PeReadhumidity, 3),
+        fail(3, ResultCode::RcRhRouterErrorUnableToDeliver)
+    );
+}
+
+#[test]
+fn loss_is_deterministic() {
+    let faults = Faults {
+        loss: 0.5,
+        ..Faults::default()
+    };
+    let run = |seed| {
+        let mut m = seeded_mesh(seed, nodes(faults.clone()));
+        m.set_timeout(Duration::from_millis(1)).unwrap();
+        (0..40)
+            .map(|_| request(&mut m, PayloadEnum::PeReadhumidity, 3).is_some())
+            .collect::<Vec<bool>>()
+    };
+    let a = run(42);
+    assert_eq!(a, run(42));
+    assert!(a.contains(&true) && a.contains(&false));
+}
+
+#[test]
+fn late_replies_arrive_later() {
+    let faults = Faults {
+        latency_ms: 50,
+        ..Faults::default()
+    };
+    let mut m = mesh(nodes(faults));
+    assert_eq!(request(&mut m, PayloadEnum::PeReadhumidity, 3), None);
+    m.advance(Duration::from_millis(50));
+    // the reply is still queued, so the next receive gets it.
+    assert_eq!(
+        m.receive().ok().map(|(id, p)| (id, PayloadEnum::from(p))),
+        Some((3, PayloadEnum::PeReadhumidityreply(45.0)))
+    );
+}
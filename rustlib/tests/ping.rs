@@ -0,0 +1,61 @@
+mod common;
+
+use automato::automatomsg::ResultCode;
+use automato::mesh::{Faults, SimulatedMesh};
+use automato::ping::{ping, PingError};
+use automato::transport::Transport;
+use common::node;
+use std::thread::sleep;
+use std::time::Duration;
+
+// node 1 with 'faults', and a timeout that's longer than the mesh's.
+fn mesh(faults: Faults) -> SimulatedMesh {
+    let mut mesh = common::mesh(vec![node(1, faults)]);
+    mesh.set_timeout(Duration::from_millis(100)).unwrap();
+    mesh
+}
+
+fn latency(latency_ms: u64) -> Faults {
+    Faults {
+        latency_ms,
+        ..Faults::default()
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut mesh = mesh(latency(30));
+    let (rtt, first) = ping(&mut mesh, 1).unwrap();
+    // timed on the mesh clock, so it's the latency exactly.
+    assert_eq!(rtt, Duration::from_millis(30));
+
+    // the node's clock keeps going between pings.
+    sleep(Duration::from_millis(20));
+    let (_, second) = ping(&mut mesh, 1).unwrap();
+    assert!({ second.millis } >= { first.millis } + 20);
+}
+
+#[test]
+fn late_pong() {
+    let mut mesh = mesh(latency(150));
+    assert!(matches!(ping(&mut mesh, 1), Err(PingError::Transport(_))));
+    assert_eq!(mesh.timeout(), Duration::from_millis(100));
+
+    // the first pong turns up during the second ping, and is passed over.
+    mesh.set_timeout(Duration::from_millis(200)).unwrap();
+    let (rtt, _) = ping(&mut mesh, 1).unwrap();
+    assert_eq!(rtt, Duration::from_millis(150));
+}
+
+#[test]
+fn failed() {
+    let mut mesh = mesh(Faults {
+        no_route: 1.0,
+        ..Faults::default()
+    });
+    // no pong comes, so the fail is what's reported.
+    assert!(matches!(
+        ping(&mut mesh, 1),
+        Err(PingError::Failed(code)) if code == ResultCode::RcRhRouterErrorNoRoute as u8
+    ));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use automato::automatomsg::{frame_bytes, PayloadEnum, Pinval, Pong, ReadField, ResultCode};
+use automato::capture::{CaptureRecord, Direction};
+use automato::ping::{ping, PingError};
+use automato::transport::ReplayTransport;
+use common::{payload, request};
+use std::path::Path;
+
+fn humidor() -> ReplayTransport {
+    ReplayTransport::open(Path::new("tests/fixtures/humidor.capture")).unwrap()
+}
+
+#[test]
+fn replays_recorded_session() {
+    let mut t = humidor();
+
+    match request(&mut t, PayloadEnum::PeReadinfo, 3) {
+        Some((3, PayloadEnum::PeReadinforeply(ri))) => {
+            assert_eq!({ ri.fieldcount }, 5);
+            assert_eq!({ ri.datalen }, 41);
+        }
+        r => panic!("bad readinfo reply: {:?}", r),
+    }
+
+    match request(&mut t, PayloadEnum::PeReadfield(ReadField { index: 0 }), 3) {
+        Some((3, PayloadEnum::PeReadfieldreply(rfr))) => assert_eq!(&rfr.name[0..8], b"humidor\0"),
+        r => panic!("bad readfield reply: {:?}", r),
+    }
+
+    // the same request twice gets the replies in recorded order, then the last one again.
+    let hum = |t: &mut ReplayTransport| request(t, PayloadEnum::PeReadhumidity, 3);
+    assert_eq!(
+        hum(&mut t),
+        Some((3, PayloadEnum::PeReadhumidityreply(47.5)))
+    );
+    assert_eq!(
+        hum(&mut t),
+        Some((3, PayloadEnum::PeReadhumidityreply(46.25)))
+    );
+    assert_eq!(
+        hum(&mut t),
+        Some((3, PayloadEnum::PeReadhumidityreply(46.25)))
+    );
+
+    assert_eq!(
+        request(
+            &mut t,
+            PayloadEnum::PeWritepin(Pinval { pin: 33, state: 1 }),
+            5
+        ),
+        Some((
+            5,
+            PayloadEnum::PeFail(ResultCode::RcRhRouterErrorNoRoute as u8)
+        ))
+    );
+}
+
+#[test]
+fn unrecorded_requests_time_out() {
+    let mut t = humidor();
+
+    // right request, wrong address.
+    assert_eq!(request(&mut t, PayloadEnum::PeReadinfo, 4), None);
+    // right address, different request.
+    assert_eq!(
+        request(
+            &mut t,
+            PayloadEnum::PeWritepin(Pinval { pin: 33, state: 0 }),
+            5
+        ),
+        None
+    );
+}
+
+#[test]
+fn replays_a_ping() {
+    // recorded with a nonce of its own; ping() sends a different one.
+    let record = |direction, pe| CaptureRecord::now(direction, 1, frame_bytes(&payload(pe), 1));
+    let mut t = ReplayTransport::new(vec![
+        record(Direction::Send, PayloadEnum::ping(7)),
+        record(
+            Direction::Receive,
+            PayloadEnum::PePong(Pong {
+                nonce: 7,
+                millis: 5000,
+            }),
+        ),
+    ]);
+    let (_, pong) = ping(&mut t, 1).unwrap();
+    assert_eq!({ pong.millis }, 5000);
+    // pings to the same address get the last recorded pong again.
+    assert!(ping(&mut t, 1).is_ok());
+    assert!(matches!(ping(&mut t, 2), Err(PingError::Transport(_))));
+}
@@ -0,0 +1,217 @@
+// property tests: every PayloadEnum survives the trip through the C layout,
+// the wire bytes and json.
+
+use automato::automatomsg as am;
+use automato::automatomsg::{
+    AnalogPinval, BuildError, Custom, Fragment, I2cTransfer, I2cTransferReply, OnewireAddress,
+    OnewireScanReply, OnewireTemperature, Payload, PayloadEnum, Pinmode, Pinval, Pong, PulseCount,
+    PulseCounter, ReadField, ReadFieldReply, ReadPulsecount, Readmem, ReadmemReply, RemoteInfo,
+    Secure, Servo, ServoReply, SpiTransfer, SpiTransferReply, Writeanalog, Writemem,
+};
+use proptest::prelude::*;
+use std::convert::TryFrom;
+
+// finite floats only; json has no NaN or infinity.
+fn arb_f32() -> impl Strategy<Value = f32> {
+    prop::num::f32::NORMAL | prop::num::f32::SUBNORMAL | prop::num::f32::ZERO
+}
+
+fn arb_payload_enum() -> impl Strategy<Value = PayloadEnum> {
+    prop_oneof![
+        Just(PayloadEnum::PeAck),
+        any::<u8>().prop_map(PayloadEnum::PeFail),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(pin, mode)| PayloadEnum::PePinmode(Pinmode { pin, mode })),
+        any::<u8>().prop_map(PayloadEnum::PeReadpin),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(pin, state)| PayloadEnum::PeReadpinreply(Pinval { pin, state })),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(pin, state)| PayloadEnum::PeWritepin(Pinval { pin, state })),
+        (any::<u16>(), any::<u8>())
+            .prop_map(|(address, length)| PayloadEnum::PeReadmem(Readmem { address, length })),
+        prop::collection::vec(any::<u8>(), 0..=249)
+            .prop_map(|data| PayloadEnum::PeReadmemreply(ReadmemReply { data })),
+        (any::<u16>(), prop::collection::vec(any::<u8>(), 0..=247))
+            .prop_map(|(address, data)| PayloadEnum::PeWritemem(Writemem { address, data })),
+        Just(PayloadEnum::PeReadinfo),
+        (arb_f32(), any::<u64>(), any::<u16>(), any::<u16>()).prop_map(
+            |(protoversion, mac_address, datalen, fieldcount)| {
+                PayloadEnum::PeReadinforeply(RemoteInfo {
+                    protoversion,
+                    mac_address,
+                    datalen,
+                    fieldcount,
+                })
+            }
+        ),
+        Just(PayloadEnum::PeReadhumidity),
+        arb_f32().prop_map(PayloadEnum::PeReadhumidityreply),
+        Just(PayloadEnum::PeReadtemperature),
+        arb_f32().prop_map(PayloadEnum::PeReadtemperaturereply),
+        any::<u8>().prop_map(PayloadEnum::PeReadanalog),
+        (any::<u8>(), any::<u16>())
+            .prop_map(|(pin, state)| PayloadEnum::PeReadanalogreply(AnalogPinval { pin, state })),
+        any::<u16>().prop_map(|index| PayloadEnum::PeReadfield(ReadField { index })),
+        (
+            any::<u16>(),
+            any::<u16>(),
+            any::<u8>(),
+            any::<u8>(),
+            any::<[u8; 25]>()
+        )
+            .prop_map(|(index, offset, length, format, name)| {
+                PayloadEnum::PeReadfieldreply(ReadFieldReply {
+                    index,
+                    offset,
+                    length,
+                    format,
+                    name,
+                })
+            }),
+        (any::<u64>(), prop::collection::vec(any::<u8>(), 0..=241))
+            .prop_map(|(counter, data)| PayloadEnum::PeSecure(Secure { counter, data })),
+        (
+            any::<u8>(),
+            any::<u8>(),
+            any::<u8>(),
+            prop::collection::vec(any::<u8>(), 0..=246)
+        )
+            .prop_map(
+                |(id, index, count, data)| PayloadEnum::PeFragment(Fragment {
+                    id,
+                    index,
+                    count,
+                    data
+                })
+            ),
+        (any::<u8>(), prop::collection::vec(any::<u8>(), 0..=248))
+            .prop_map(|(subtype, data)| PayloadEnum::PeCustom(Custom { subtype, data })),
+        (any::<u8>(), any::<u16>(), any::<u32>()).prop_map(|(pin, duty, frequency)| {
+            PayloadEnum::PeWriteanalog(Writeanalog {
+                pin,
+                duty,
+                frequency,
+            })
+        }),
+        (
+            any::<u8>(),
+            any::<u8>(),
+            prop::collection::vec(any::<u8>(), 0..=247)
+        )
+            .prop_map(|(address, readlength, data)| PayloadEnum::PeI2cTransfer(
+                I2cTransfer {
+                    address,
+                    readlength,
+                    data
+                }
+            )),
+        (
+            any::<u8>(),
+            any::<u8>(),
+            prop::collection::vec(any::<u8>(), 0..=247)
+        )
+            .prop_map(|(address, status, data)| PayloadEnum::PeI2cTransferreply(
+                I2cTransferReply {
+                    address,
+                    status,
+                    data
+                }
+            )),
+        (
+            any::<u8>(),
+            0..=3u8,
+            any::<u32>(),
+            prop::collection::vec(any::<u8>(), 0..=243)
+        )
+            .prop_map(|(cspin, mode, clock, data)| PayloadEnum::PeSpiTransfer(
+                SpiTransfer {
+                    cspin,
+                    mode,
+                    clock,
+                    data
+                }
+            )),
+        (any::<u8>(), prop::collection::vec(any::<u8>(), 0..=243)).prop_map(|(cspin, data)| {
+            PayloadEnum::PeSpiTransferreply(SpiTransferReply { cspin, data })
+        }),
+        any::<u8>().prop_map(PayloadEnum::PeOnewirescan),
+        (any::<u8>(), prop::collection::vec(any::<[u8; 8]>(), 0..=31)).prop_map(|(pin, roms)| {
+            PayloadEnum::PeOnewirescanreply(OnewireScanReply { pin, roms })
+        }),
+        (any::<u8>(), any::<[u8; 8]>()).prop_map(|(pin, rom)| {
+            PayloadEnum::PeReadonewiretemperature(OnewireAddress { pin, rom })
+        }),
+        (any::<u8>(), any::<[u8; 8]>(), arb_f32()).prop_map(|(pin, rom, temperature)| {
+            PayloadEnum::PeReadonewiretemperaturereply(OnewireTemperature {
+                pin,
+                rom,
+                temperature,
+            })
+        }),
+        (any::<u8>(), any::<u16>(), any::<u16>())
+            .prop_map(|(pin, pulse, speed)| PayloadEnum::PeServo(Servo { pin, pulse, speed })),
+        (any::<u8>(), any::<u16>())
+            .prop_map(|(pin, pulse)| PayloadEnum::PeServoreply(ServoReply { pin, pulse })),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(pin, edge)| PayloadEnum::PePulsecounter(PulseCounter { pin, edge })),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(pin, reset)| PayloadEnum::PeReadpulsecount(ReadPulsecount { pin, reset })),
+        (any::<u8>(), any::<u32>(), arb_f32()).prop_map(|(pin, count, frequency)| {
+            PayloadEnum::PeReadpulsecountreply(PulseCount {
+                pin,
+                count,
+                frequency,
+            })
+        }),
+        any::<u32>().prop_map(PayloadEnum::PeReboot),
+        any::<u32>().prop_map(PayloadEnum::PeFactoryReset),
+        any::<u32>().prop_map(PayloadEnum::PePing),
+        (any::<u32>(), any::<u32>())
+            .prop_map(|(nonce, millis)| PayloadEnum::PePong(Pong { nonce, millis })),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn payload_roundtrip(pe in arb_payload_enum()) {
+        let bytes = am::encode_payload(&Payload::try_from(pe.clone()).unwrap());
+        prop_assert!(bytes.len() <= am::RH_RF95_MAX_MESSAGE_LEN);
+
+        let decoded = PayloadEnum::from(am::decode_payload(&bytes).unwrap());
+        prop_assert_eq!(&decoded, &pe);
+
+        let js = serde_json::to_string(&decoded).unwrap();
+        let fromjs: PayloadEnum = serde_json::from_str(&js).unwrap();
+        prop_assert_eq!(&fromjs, &pe);
+
+        // Payload's serde goes through PayloadEnum, so the json is the same.
+        let p: Payload = serde_json::from_str(&js).unwrap();
+        prop_assert_eq!(am::encode_payload(&p), bytes);
+    }
+
+    #[test]
+    fn oversize_vecs_rejected(address in any::<u16>(), data in prop::collection::vec(any::<u8>(), 250..1000)) {
+        let wm = PayloadEnum::PeWritemem(Writemem { address, data: data.clone() });
+        prop_assert_eq!(
+            Payload::try_from(wm).err(),
+            Some(BuildError::TooLong { what: "writemem data", length: data.len(), max: 247 })
+        );
+        let rmr = PayloadEnum::PeReadmemreply(ReadmemReply { data: data.clone() });
+        prop_assert_eq!(
+            Payload::try_from(rmr).err(),
+            Some(BuildError::TooLong { what: "readmemreply data", length: data.len(), max: 249 })
+        );
+    }
+
+    #[test]
+    fn decode_arbitrary_bytes(bytes in prop::collection::vec(any::<u8>(), 0..300)) {
+        // garbage is rejected or decodes to something that encodes back the same.
+        if let Ok(p) = am::decode_payload(&bytes) {
+            let encoded = am::encode_payload(&p);
+            prop_assert_eq!(&encoded[..], &bytes[0..encoded.len()]);
+            let pe = PayloadEnum::from(p);
+            let p2 = Payload::try_from(pe).unwrap();
+            prop_assert_eq!(am::encode_payload(&p2), encoded);
+        }
+    }
+}
@@ -0,0 +1,122 @@
+use automato::automatomsg::{FieldFormat, PayloadEnum};
+use automato::schema::{CType, Schema, SchemaField};
+use automato::simulator::{NodeConfig, Simulator};
+use std::fs;
+use std::path::PathBuf;
+
+fn sketch(name: &str) -> PathBuf {
+    [env!("CARGO_MANIFEST_DIR"), "..", "sketches", name]
+        .iter()
+        .collect()
+}
+
+fn schema(fields: &[(&str, &str, bool)]) -> Schema {
+    Schema {
+        fields: fields
+            .iter()
+            .map(|(name, ctype, unmapped)| SchemaField {
+                name: name.to_string(),
+                ctype: ctype.to_string(),
+                unmapped: *unmapped,
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn sketch_headers_are_current() {
+    for name in ["serialtolora", "remotelora", "humidor-remote"] {
+        let dir = sketch(name);
+        let map = Schema::load(&dir.join("serverdata.toml"))
+            .unwrap()
+            .layout()
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("ServerData.h")).unwrap(),
+            map.to_cpp("serverdata.toml"),
+            "regenerate {}/ServerData.h with matoschema",
+            name
+        );
+    }
+}
+
+#[test]
+fn matches_simulator() {
+    let map = Schema::load(&sketch("serialtolora").join("serverdata.toml"))
+        .unwrap()
+        .layout()
+        .unwrap();
+    let node = NodeConfig::default();
+    assert_eq!(map.datalen, node.datalen);
+
+    let placed = |fields: Vec<automato::simulator::MapField>| {
+        fields
+            .into_iter()
+            .map(|f| (f.name, f.offset, f.length, f.format))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(placed(map.map_fields()), placed(node.fields.clone()));
+
+    // the labels are what the node would say.
+    let mut sim = Simulator::new(&node).unwrap();
+    for rfr in map.readfield_replies() {
+        assert_eq!(
+            sim.handle(&PayloadEnum::readfield(rfr.index)),
+            PayloadEnum::PeReadfieldreply(rfr)
+        );
+    }
+}
+
+#[test]
+fn json_and_types() {
+    let s = schema(&[
+        ("flag", "uint8_t", false),
+        ("big", "double", true),
+        ("raw", "uint8_t [3]", false),
+        ("count", "int16_t", false),
+    ]);
+    let path = std::env::temp_dir().join(format!("schema-{}.json", std::process::id()));
+    fs::write(&path, serde_json::to_string(&s).unwrap()).unwrap();
+    assert_eq!(Schema::load(&path).unwrap(), s);
+    fs::remove_file(&path).unwrap();
+
+    let map = s.layout().unwrap();
+    let placed: Vec<(u16, CType)> = map.fields.iter().map(|f| (f.offset, f.ctype)).collect();
+    assert_eq!(
+        placed,
+        vec![
+            (0, CType::Uint8),
+            (8, CType::Double),
+            (16, CType::Bytes(3)),
+            (20, CType::Int16),
+        ]
+    );
+    assert_eq!(map.datalen, 24);
+    assert_eq!(map.fields[2].ctype.format(), FieldFormat::FfOther);
+    assert_eq!({ map.readfield_replies()[2].index }, 2);
+}
+
+#[test]
+fn bad_schemas() {
+    let bad = [
+        schema(&[("x", "float", false), ("x", "float", false)]),
+        schema(&[("x", "long", false)]),
+        schema(&[("x", "float[2]", false)]),
+        schema(&[("x", "char[0]", false)]),
+        schema(&[("x", "char[256]", false)]),
+        schema(&[("2x", "float", false)]),
+        schema(&[("a_very_long_field_name_indeed", "float", false)]),
+        schema(&[("x", "float", true)]),
+        schema(&[]),
+    ];
+    for s in bad.iter() {
+        assert!(s.layout().is_err(), "{:?}", s);
+    }
+    // long names are fine when the node doesn't send them.
+    assert!(schema(&[
+        ("a_very_long_field_name_indeed", "float", true),
+        ("x", "float", false)
+    ])
+    .layout()
+    .is_ok());
+}
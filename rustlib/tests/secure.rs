@@ -0,0 +1,259 @@
+mod common;
+
+use automato::automatomsg::{Payload, PayloadEnum, PayloadType, ResultCode};
+use automato::mesh::SimulatedMesh;
+use automato::secure::{Key, Link, NodeKey, SecureError, SecureTransport, Sender};
+use automato::simulator::NodeConfig;
+use automato::transport::Transport;
+use common::{payload, request};
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+fn key() -> Key {
+    Key::from_hex(KEY).unwrap()
+}
+
+#[test]
+fn seal_and_open() {
+    let mut host = Link::new(&key(), Sender::Host(0), 3);
+    let mut node = Link::new(&key(), Sender::Node, 3);
+
+    let sealed = host.seal(&payload(PayloadEnum::writepin(22, 1))).unwrap();
+    assert_eq!(sealed.payload_type, PayloadType::PtSecure);
+    assert_eq!(
+        PayloadEnum::from(node.open(&sealed).unwrap()),
+        PayloadEnum::writepin(22, 1)
+    );
+
+    let sealed = node.seal(&payload(PayloadEnum::ack())).unwrap();
+    assert_eq!(
+        PayloadEnum::from(host.open(&sealed).unwrap()),
+        PayloadEnum::ack()
+    );
+}
+
+#[test]
+fn rejects_forgeries() {
+    let mut host = Link::new(&key(), Sender::Host(0), 3);
+    let sealed = host.seal(&payload(PayloadEnum::writepin(22, 1))).unwrap();
+
+    // a flipped bit.
+    let mut s = match PayloadEnum::from(sealed) {
+        PayloadEnum::PeSecure(s) => s,
+        pe => panic!("not sealed: {:?}", pe),
+    };
+    s.data[0] ^= 1;
+    let tampered = payload(PayloadEnum::PeSecure(s));
+    let mut node = Link::new(&key(), Sender::Node, 3);
+    assert_eq!(node.open(&tampered).unwrap_err(), SecureError::Forged);
+
+    // sent to another address.
+    let mut other = Link::new(&key(), Sender::Node, 4);
+    assert_eq!(other.open(&sealed).unwrap_err(), SecureError::Forged);
+
+    // the host's own message, reflected back at it.
+    assert_eq!(host.open(&sealed).unwrap_err(), SecureError::Forged);
+
+    // a different key.
+    let mut wrong = Link::new(&Key::new([7; 32]), Sender::Node, 3);
+    assert_eq!(wrong.open(&sealed).unwrap_err(), SecureError::Forged);
+
+    // not sealed at all.
+    assert_eq!(
+        node.open(&payload(PayloadEnum::ack())).unwrap_err(),
+        SecureError::NotSecure(PayloadType::PtAck)
+    );
+}
+
+#[test]
+fn rejects_replays() {
+    let mut host = Link::new(&key(), Sender::Host(0), 3);
+    let mut node = Link::new(&key(), Sender::Node, 3);
+    let first = host.seal(&payload(PayloadEnum::writepin(22, 1))).unwrap();
+    let second = host.seal(&payload(PayloadEnum::writepin(22, 0))).unwrap();
+
+    node.open(&second).unwrap();
+    assert!(matches!(
+        node.open(&second).unwrap_err(),
+        SecureError::Replayed { .. }
+    ));
+    // older messages are out too.
+    assert!(matches!(
+        node.open(&first).unwrap_err(),
+        SecureError::Replayed { .. }
+    ));
+}
+
+#[test]
+fn hosts_sharing_a_key() {
+    // a long running server, and a command line tool started after it.
+    let mut server = Link::new(&key(), Sender::Host(0), 3);
+    let mut tool = Link::new(&key(), Sender::Host(1), 3);
+    let mut node = Link::new(&key(), Sender::Node, 3);
+
+    // the tool's counter doesn't hold up the server's.
+    for _ in 0..5 {
+        node.open(&tool.seal(&payload(PayloadEnum::ack())).unwrap())
+            .unwrap();
+    }
+    let first = server.seal(&payload(PayloadEnum::writepin(22, 1))).unwrap();
+    node.open(&first).unwrap();
+
+    // they never seal with the same counter, and so the same nonce.
+    let counter = |p: &Payload| match PayloadEnum::from(*p) {
+        PayloadEnum::PeSecure(s) => s.counter,
+        pe => panic!("not sealed: {:?}", pe),
+    };
+    let a = server.seal(&payload(PayloadEnum::ack())).unwrap();
+    let b = tool.seal(&payload(PayloadEnum::ack())).unwrap();
+    assert_ne!(counter(&a), counter(&b));
+
+    // replays are still caught for each.
+    assert!(matches!(
+        node.open(&first).unwrap_err(),
+        SecureError::Replayed { .. }
+    ));
+    node.open(&b).unwrap();
+    assert!(matches!(
+        node.open(&b).unwrap_err(),
+        SecureError::Replayed { .. }
+    ));
+
+    // and both can read the node's replies.
+    let reply = node.seal(&payload(PayloadEnum::ack())).unwrap();
+    assert_eq!(
+        PayloadEnum::from(server.open(&reply).unwrap()),
+        PayloadEnum::ack()
+    );
+    assert_eq!(
+        PayloadEnum::from(tool.open(&reply).unwrap()),
+        PayloadEnum::ack()
+    );
+}
+
+#[test]
+fn rejects_bad_keys() {
+    assert!(matches!(Key::from_hex("0011"), Err(SecureError::BadKey(_))));
+    assert!(matches!(
+        Key::from_hex("not hex"),
+        Err(SecureError::BadKey(_))
+    ));
+    assert_eq!(format!("{:?}", key()), "Key(..)");
+}
+
+// node 3 has the key.
+fn mesh() -> SimulatedMesh {
+    common::mesh(vec![
+        NodeConfig::default(),
+        NodeConfig {
+            address: 3,
+            key: Some(KEY.to_string()),
+            ..NodeConfig::default()
+        },
+    ])
+}
+
+#[test]
+fn transport_to_keyed_node() {
+    let keys = [NodeKey {
+        id: 3,
+        key: KEY.to_string(),
+    }];
+    let mut t = SecureTransport::new(Box::new(mesh()), &keys, 0).unwrap();
+
+    // node 3 is sealed, node 1 is not.
+    assert_eq!(
+        request(&mut t, PayloadEnum::PeReadhumidity, 3),
+        Some((3, PayloadEnum::PeReadhumidityreply(45.0)))
+    );
+    assert_eq!(
+        request(&mut t, PayloadEnum::PeReadhumidity, 1),
+        Some((1, PayloadEnum::PeReadhumidityreply(45.0)))
+    );
+}
+
+#[test]
+fn keyed_node_refuses_plain_requests() {
+    let mut m = mesh();
+    // the refusal is sealed, so only a host with the key can read it.
+    m.send(&payload(PayloadEnum::PeReadhumidity), 3).unwrap();
+    let (fromid, sealed) = m.receive().unwrap();
+    assert_eq!((fromid, sealed.payload_type), (3, PayloadType::PtSecure));
+    let mut host = Link::new(&key(), Sender::Host(0), 3);
+    assert_eq!(
+        PayloadEnum::from(host.open(&sealed).unwrap()),
+        PayloadEnum::PeFail(ResultCode::RcOperationForbidden as u8)
+    );
+
+    // a wrong key gets a refusal it can't open.
+    let keys = [NodeKey {
+        id: 3,
+        key: "ff".repeat(32),
+    }];
+    let mut t = SecureTransport::new(Box::new(m), &keys, 0).unwrap();
+    t.send(&payload(PayloadEnum::PeReadhumidity), 3).unwrap();
+    assert_eq!(
+        t.receive().unwrap_err().kind,
+        serialport::ErrorKind::Io(ErrorKind::InvalidData)
+    );
+}
+
+// replies as if off the radio, whatever was sent.
+struct Canned(VecDeque<(u8, Payload)>);
+
+impl Transport for Canned {
+    fn send(&mut self, _payload: &Payload, _toid: u8) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Result<(u8, Payload), serialport::Error> {
+        self.0.pop_front().ok_or_else(|| {
+            serialport::Error::new(serialport::ErrorKind::Io(ErrorKind::TimedOut), "no more")
+        })
+    }
+
+    fn set_timeout(&mut self, _timeout: Duration) -> Result<(), serialport::Error> {
+        Ok(())
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+#[test]
+fn only_router_fails_go_unsealed() {
+    let fail = |rc: ResultCode| PayloadEnum::PeFail(rc as u8);
+    let canned = Canned(
+        vec![
+            (3, payload(fail(ResultCode::RcRhRouterErrorNoRoute))),
+            (3, payload(fail(ResultCode::RcOperationForbidden))),
+            (1, payload(fail(ResultCode::RcOperationForbidden))),
+        ]
+        .into(),
+    );
+    let keys = [NodeKey {
+        id: 3,
+        key: KEY.to_string(),
+    }];
+    let mut t = SecureTransport::new(Box::new(canned), &keys, 0).unwrap();
+
+    // from the gateway.
+    assert_eq!(
+        PayloadEnum::from(t.receive().unwrap().1),
+        fail(ResultCode::RcRhRouterErrorNoRoute)
+    );
+    // from anyone with a radio; node 3 would have sealed it.
+    assert_eq!(
+        t.receive().unwrap_err().kind,
+        serialport::ErrorKind::Io(ErrorKind::InvalidData)
+    );
+    // node 1 has no key, so nothing it sends is sealed.
+    assert_eq!(
+        PayloadEnum::from(t.receive().unwrap().1),
+        fail(ResultCode::RcOperationForbidden)
+    );
+}
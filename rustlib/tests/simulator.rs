@@ -0,0 +1,363 @@
+use automato::automatomsg::{
+    I2cStatus, PayloadEnum, Pinmode, Pinval, PulseEdge, ReadField, Readmem, ReadmemReply,
+    ResultCode, Writemem, REBOOT_TOKEN,
+};
+use automato::simulator::{
+    I2cDevice, NodeConfig, OnewireDevice, PinState, PulseSource, Simulator, SpiDevice,
+};
+use std::thread::sleep;
+use std::time::Duration;
+
+fn fail(rc: ResultCode) -> PayloadEnum {
+    PayloadEnum::PeFail(rc as u8)
+}
+
+#[test]
+fn memory_map() {
+    let mut sim = Simulator::new(&NodeConfig::default()).unwrap();
+
+    match sim.handle(&PayloadEnum::PeReadinfo) {
+        PayloadEnum::PeReadinforeply(ri) => {
+            assert_eq!({ ri.datalen }, 56);
+            assert_eq!({ ri.fieldcount }, 3);
+        }
+        r => panic!("bad reply: {:?}", r),
+    }
+
+    match sim.handle(&PayloadEnum::PeReadfield(ReadField { index: 1 })) {
+        PayloadEnum::PeReadfieldreply(rfr) => {
+            assert_eq!(&rfr.name[0..11], b"targettemp\0");
+            assert_eq!({ rfr.offset }, 28);
+            assert_eq!({ rfr.length }, 4);
+            assert_eq!({ rfr.format }, 1);
+        }
+        r => panic!("bad reply: {:?}", r),
+    }
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadfield(ReadField { index: 3 })),
+        fail(ResultCode::RcInvalidMemAddress)
+    );
+
+    // initial values from the config.
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadmem(Readmem {
+            address: 28,
+            length: 4
+        })),
+        PayloadEnum::PeReadmemreply(ReadmemReply {
+            data: 42.0f32.to_le_bytes().to_vec()
+        })
+    );
+
+    let wm = Writemem {
+        address: 40,
+        data: vec![1, 2, 3, 4],
+    };
+    assert_eq!(sim.handle(&PayloadEnum::PeWritemem(wm)), PayloadEnum::PeAck);
+    assert_eq!(sim.memory[40..44], [1, 2, 3, 4]);
+
+    // off the end of ServerData.
+    let wm = Writemem {
+        address: 54,
+        data: vec![1, 2, 3],
+    };
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeWritemem(wm)),
+        fail(ResultCode::RcInvalidMemAddress)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadmem(Readmem {
+            address: 0,
+            length: 250
+        })),
+        fail(ResultCode::RcInvalidMemLength)
+    );
+}
+
+#[test]
+fn pins() {
+    let mut sim = Simulator::new(&NodeConfig::default()).unwrap();
+
+    let pm = Pinmode { pin: 33, mode: 1 };
+    assert_eq!(sim.handle(&PayloadEnum::PePinmode(pm)), PayloadEnum::PeAck);
+    let pv = Pinval { pin: 33, state: 1 };
+    assert_eq!(sim.handle(&PayloadEnum::PeWritepin(pv)), PayloadEnum::PeAck);
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadpin(33)),
+        PayloadEnum::PeReadpinreply(pv)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadpin(40)),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+
+    // pwm starts at the default frequency, and keeps it unless told otherwise.
+    assert_eq!(
+        sim.handle(&PayloadEnum::writeanalog(25, 1000, None)),
+        PayloadEnum::PeAck
+    );
+    assert_eq!((sim.pins[25].duty, sim.pins[25].frequency), (1000, 1000));
+    sim.handle(&PayloadEnum::writeanalog(25, 2000, Some(25000)));
+    sim.handle(&PayloadEnum::writeanalog(25, 3000, None));
+    assert_eq!((sim.pins[25].duty, sim.pins[25].frequency), (3000, 25000));
+    assert_eq!(
+        sim.handle(&PayloadEnum::writeanalog(40, 0, None)),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+
+    // servos are clamped to the Servo library's range.
+    assert_eq!(
+        sim.handle(&PayloadEnum::servo_angle(13, 90, Some(30))),
+        PayloadEnum::servoreply(13, 1472)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::servo(13, 3000, None)),
+        PayloadEnum::servoreply(13, 2400)
+    );
+    assert_eq!(sim.pins[13].servo, 2400);
+    assert_eq!(
+        sim.handle(&PayloadEnum::servo(40, 1500, None)),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+}
+
+#[test]
+fn i2c() {
+    let mut sim = Simulator::new(&NodeConfig {
+        i2c: vec![I2cDevice {
+            address: 0x50,
+            registers: "00112233".to_string(),
+        }],
+        ..NodeConfig::default()
+    })
+    .unwrap();
+    let transfer = |sim: &mut Simulator, address, data: &[u8], readlength| {
+        sim.handle(&PayloadEnum::i2ctransfer(address, data, readlength).unwrap())
+    };
+
+    // read from register 1, wrapping around.
+    assert_eq!(
+        transfer(&mut sim, 0x50, &[1], 4),
+        PayloadEnum::i2ctransferreply(0x50, I2cStatus::IsOk, &[0x11, 0x22, 0x33, 0x00]).unwrap()
+    );
+    // write from register 2, then carry on reading.
+    assert_eq!(
+        transfer(&mut sim, 0x50, &[2, 0xaa], 2),
+        PayloadEnum::i2ctransferreply(0x50, I2cStatus::IsOk, &[0x33, 0x00]).unwrap()
+    );
+    assert_eq!(sim.i2c[0].registers, [0x00, 0x11, 0xaa, 0x33]);
+
+    assert_eq!(
+        transfer(&mut sim, 0x51, &[], 0),
+        PayloadEnum::i2ctransferreply(0x51, I2cStatus::IsAddressNack, &[]).unwrap()
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::i2ctransferreply(0x50, I2cStatus::IsOk, &[]).unwrap()),
+        fail(ResultCode::RcInvalidMessageType)
+    );
+}
+
+#[test]
+fn spi() {
+    let mut sim = Simulator::new(&NodeConfig {
+        spi: vec![SpiDevice {
+            cspin: 5,
+            registers: "d0600000".to_string(),
+        }],
+        ..NodeConfig::default()
+    })
+    .unwrap();
+    let transfer = |sim: &mut Simulator, cspin, data: &[u8]| {
+        sim.handle(&PayloadEnum::spitransfer(cspin, 0, 1_000_000, data).unwrap())
+    };
+
+    // read registers 0 and 1, write 2 and 3.
+    assert_eq!(
+        transfer(&mut sim, 5, &[0x80, 0, 0]),
+        PayloadEnum::spitransferreply(5, &[0xff, 0xd0, 0x60]).unwrap()
+    );
+    assert_eq!(
+        transfer(&mut sim, 5, &[0x02, 0x27, 0xa0]),
+        PayloadEnum::spitransferreply(5, &[0xff; 3]).unwrap()
+    );
+    assert_eq!(sim.spi[0].registers, [0xd0, 0x60, 0x27, 0xa0]);
+
+    // nothing on pin 4.
+    assert_eq!(
+        transfer(&mut sim, 4, &[0x80, 0]),
+        PayloadEnum::spitransferreply(4, &[0xff; 2]).unwrap()
+    );
+    assert_eq!(
+        transfer(&mut sim, 40, &[0x80, 0]),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+}
+
+#[test]
+fn onewire() {
+    let probe = |rom: &str, temperature| OnewireDevice {
+        pin: 4,
+        rom: rom.to_string(),
+        temperature,
+    };
+    let mut sim = Simulator::new(&NodeConfig {
+        onewire: vec![
+            probe("28ff641e8216033c", 21.5),
+            probe("286164123c7c2f27", 18.0625),
+        ],
+        ..NodeConfig::default()
+    })
+    .unwrap();
+    let roms = [
+        [0x28, 0xff, 0x64, 0x1e, 0x82, 0x16, 0x03, 0x3c],
+        [0x28, 0x61, 0x64, 0x12, 0x3c, 0x7c, 0x2f, 0x27],
+    ];
+
+    assert_eq!(
+        sim.handle(&PayloadEnum::onewirescan(4)),
+        PayloadEnum::onewirescanreply(4, &roms).unwrap()
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::onewirescan(5)),
+        PayloadEnum::onewirescanreply(5, &[]).unwrap()
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::readonewiretemperature(4, roms[1])),
+        PayloadEnum::readonewiretemperaturereply(4, roms[1], 18.0625)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::readonewiretemperature(5, roms[1])),
+        fail(ResultCode::RcNoDevice)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::onewirescan(40)),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+
+    let config = NodeConfig {
+        onewire: vec![probe("28ff641e82", 0.0)],
+        ..NodeConfig::default()
+    };
+    assert!(Simulator::new(&config).is_err());
+}
+
+#[test]
+fn pulses() {
+    let mut sim = Simulator::new(&NodeConfig {
+        pulses: vec![PulseSource {
+            pin: 18,
+            frequency: 1000.0,
+        }],
+        ..NodeConfig::default()
+    })
+    .unwrap();
+
+    assert_eq!(
+        sim.handle(&PayloadEnum::readpulsecount(18, false)),
+        fail(ResultCode::RcOperationForbidden)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::pulsecounter(18, PulseEdge::PulseChange)),
+        PayloadEnum::PeAck
+    );
+    sleep(Duration::from_millis(50));
+
+    // both edges of a 1kHz signal, for at least 50ms.
+    match sim.handle(&PayloadEnum::readpulsecount(18, true)) {
+        PayloadEnum::PeReadpulsecountreply(pc) => {
+            assert!({ pc.count } >= 100, "count {}", { pc.count });
+            assert!((900.0..=1000.0).contains(&{ pc.frequency }));
+        }
+        r => panic!("bad reply: {:?}", r),
+    }
+    // reset starts again from 0.
+    match sim.handle(&PayloadEnum::readpulsecount(18, false)) {
+        PayloadEnum::PeReadpulsecountreply(pc) => assert!({ pc.count } < 100),
+        r => panic!("bad reply: {:?}", r),
+    }
+
+    // a pin without a signal counts nothing.
+    sim.handle(&PayloadEnum::pulsecounter(19, PulseEdge::PulseRising));
+    match sim.handle(&PayloadEnum::readpulsecount(19, false)) {
+        PayloadEnum::PeReadpulsecountreply(pc) => assert_eq!({ pc.count }, 0),
+        r => panic!("bad reply: {:?}", r),
+    }
+
+    sim.handle(&PayloadEnum::pulsecounter(18, PulseEdge::PulseOff));
+    assert_eq!(sim.pins[18].counter, None);
+    assert_eq!(
+        sim.handle(&PayloadEnum::pulsecounter(40, PulseEdge::PulseRising)),
+        fail(ResultCode::RcInvalidPinNumber)
+    );
+}
+
+#[test]
+fn reset() {
+    let mut sim = Simulator::new(&NodeConfig::default()).unwrap();
+    let factory = sim.memory.clone();
+    sim.handle(&PayloadEnum::writemem(28, &[1, 2, 3, 4]).unwrap());
+    sim.handle(&PayloadEnum::writeanalog(25, 2000, Some(25000)));
+
+    // the wrong token doesn't do anything.
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReboot(0)),
+        fail(ResultCode::RcOperationForbidden)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeFactoryReset(REBOOT_TOKEN)),
+        fail(ResultCode::RcOperationForbidden)
+    );
+    assert_eq!(sim.pins[25].duty, 2000);
+
+    // reboot resets the pins, millis() and the memory map, which is in RAM.
+    sleep(Duration::from_millis(50));
+    assert_eq!(sim.handle(&PayloadEnum::reboot()), PayloadEnum::PeAck);
+    assert_eq!(sim.pins[25], PinState::default());
+    assert_eq!(sim.memory, factory);
+    match sim.handle(&PayloadEnum::ping(7)) {
+        PayloadEnum::PePong(p) => assert!({ p.nonce } == 7 && { p.millis } < 50),
+        r => panic!("bad reply: {:?}", r),
+    }
+
+    sim.handle(&PayloadEnum::writemem(28, &[1, 2, 3, 4]).unwrap());
+    assert_eq!(sim.handle(&PayloadEnum::factoryreset()), PayloadEnum::PeAck);
+    assert_eq!(sim.memory, factory);
+}
+
+#[test]
+fn replies_are_not_requests() {
+    let mut sim = Simulator::new(&NodeConfig::default()).unwrap();
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeAck),
+        fail(ResultCode::RcInvalidMessageType)
+    );
+    assert_eq!(
+        sim.handle(&PayloadEnum::PeReadhumidityreply(1.0)),
+        fail(ResultCode::RcInvalidMessageType)
+    );
+}
+
+#[test]
+fn bad_config() {
+    let mut config = NodeConfig::default();
+    config.fields[0].value = Some("much too long for a 25 byte field".to_string());
+    assert!(Simulator::new(&config).is_err());
+
+    let config = NodeConfig {
+        datalen: 40,
+        ..NodeConfig::default()
+    };
+    assert!(Simulator::new(&config).is_err());
+
+    let device = |address| I2cDevice {
+        address,
+        registers: String::new(),
+    };
+    for i2c in [vec![device(0x80)], vec![device(0x50), device(0x50)]] {
+        let config = NodeConfig {
+            i2c,
+            ..NodeConfig::default()
+        };
+        assert!(Simulator::new(&config).is_err());
+    }
+}
@@ -0,0 +1,34 @@
+// generated by matoschema from serverdata.toml; don't edit.
+// -*- mode: C++ -*-
+
+#ifndef SERVERDATA_H
+#define SERVERDATA_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <Automato.h>
+
+struct ServerData {
+  char name[25];
+  float lowertargethumidity;
+  float uppertargethumidity;
+  uint32_t loops;
+  uint32_t checkInterval;
+};
+
+MapField memoryMap[] =
+  { map_field(ServerData, name, ff_char)
+  , map_field(ServerData, lowertargethumidity, ff_float)
+  , map_field(ServerData, uppertargethumidity, ff_float)
+  , map_field(ServerData, loops, ff_uint32)
+  , map_field(ServerData, checkInterval, ff_uint32)
+  };
+
+static_assert(sizeof(ServerData) == 44, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, name) == 0, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, lowertargethumidity) == 28, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, uppertargethumidity) == 32, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, loops) == 36, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, checkInterval) == 40, "ServerData isn't laid out like the schema");
+
+#endif
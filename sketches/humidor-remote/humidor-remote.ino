@@ -11,25 +11,11 @@ Preferences prefs;
 #define RO_MODE true
 #define RW_MODE false
 
-// ideally this would go in a shared header file,
-struct ServerData {
-  char name[25];
-  float lowertargethumidity;
-  float uppertargethumidity;
-  uint32_t loops;
-  uint32_t checkInterval;
-};
+// ServerData and memoryMap[], generated from serverdata.toml.
+#include "ServerData.h"
 
 ServerData serverdata;
 
-MapField memoryMap[] =
-  { map_field(ServerData, name, ff_char)
-  , map_field(ServerData, lowertargethumidity, ff_float)
-  , map_field(ServerData, uppertargethumidity, ff_float)
-  , map_field(ServerData, loops, ff_uint32)
-  , map_field(ServerData, checkInterval, ff_uint32)
-  };
-
 Automato automato(3, (void*)&serverdata, sizeof(ServerData), (void*)&memoryMap, sizeof(memoryMap) / sizeof(MapField), true);
 
 int8_t output_pin = 33;
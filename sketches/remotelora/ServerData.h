@@ -0,0 +1,34 @@
+// generated by matoschema from serverdata.toml; don't edit.
+// -*- mode: C++ -*-
+
+#ifndef SERVERDATA_H
+#define SERVERDATA_H
+
+#include <stddef.h>
+#include <stdint.h>
+#include <Automato.h>
+
+struct ServerData {
+  char name[25];
+  float targettemp;
+  int32_t loops;
+  uint64_t macAddress;
+  float temperature;
+  float humidity;
+};
+
+MapField memoryMap[] =
+  { map_field(ServerData, name, ff_char)
+  , map_field(ServerData, targettemp, ff_float)
+  , map_field(ServerData, loops, ff_int32)
+  };
+
+static_assert(sizeof(ServerData) == 56, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, name) == 0, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, targettemp) == 28, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, loops) == 32, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, macAddress) == 40, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, temperature) == 48, "ServerData isn't laid out like the schema");
+static_assert(offsetof(ServerData, humidity) == 52, "ServerData isn't laid out like the schema");
+
+#endif
@@ -6,25 +6,12 @@
 #include <AutomatoMsg.h>
 #include <Automato.h>
 
-// ideally this would go in a shared header file,
-struct ServerData {
-  char name[25];
-  float targettemp;
-  int32_t loops;
-  uint64_t macAddress;
-  float temperature;
-  float humidity;
-};
+// ServerData and memoryMap[], generated from serverdata.toml.
+#include "ServerData.h"
 
 ServerData serverdata;
 
-MapField memoryMap[] = 
-  { map_field(ServerData, name, ff_char)
-  , map_field(ServerData, targettemp, ff_float)
-  , map_field(ServerData, loops, ff_int32)
-  }; 
-
-Automato automato(2, (void*)&serverdata, sizeof(ServerData), (void*)&memoryMap, 3, true);
+Automato automato(2, (void*)&serverdata, sizeof(ServerData), (void*)&memoryMap, sizeof(memoryMap) / sizeof(MapField), true);
 
 void setup()
 {
@@ -6,26 +6,13 @@
 #include <pins_arduino.h>
 #include <Automato.h>
 
-// ideally this would go in a shared header file,
-struct ServerData {
-  char name[25];
-  float targettemp;
-  uint64_t macAddress;
-  int32_t loops;
-  float temperature;
-  float humidity;
-};
+// ServerData and memoryMap[], generated from serverdata.toml.
+#include "ServerData.h"
 
 ServerData serverdata;
 
-MapField memoryMap[] = 
-  { map_field(ServerData, name, ff_char)
-  , map_field(ServerData, targettemp, ff_float)
-  , map_field(ServerData, loops, ff_int32)
-  }; 
 
-
-Automato automato(1, (void*)&serverdata, sizeof(ServerData), (void*)&memoryMap, 3, true);
+Automato automato(1, (void*)&serverdata, sizeof(ServerData), (void*)&memoryMap, sizeof(memoryMap) / sizeof(MapField), true);
 
 bool on;
 
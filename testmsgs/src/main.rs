@@ -1,8 +1,11 @@
 use automato::automatomsg as am;
-use automato::automatomsg::{Msgbuf, Payload, PayloadData, PayloadType, ResultCode};
+use automato::automatomsg::{
+    FieldFormat, Msgbuf, Payload, PayloadData, PayloadEnum, PayloadType, ResultCode,
+};
 use clap::{Arg, Command};
 use serde_json;
 use simple_error::bail;
+use std::convert::TryFrom;
 use std::error::Error;
 use std::fs::File;
 use std::io::{Read, Write};
@@ -108,69 +111,43 @@ unsafe fn write_message_files(
     dir: &str,
     write_message: unsafe fn(&str, &str, Payload) -> Result<(), Box<dyn std::error::Error>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut payload = Payload {
-        payload_type: PayloadType::PtAck,
-        data: PayloadData { unit: () },
-    };
-
-    am::setup_ack(&mut payload);
-    write_message(dir, "ack.js", payload)?;
-
-    am::setup_fail(&mut payload, ResultCode::RcInvalidRhRouterError);
-    write_message(dir, "fail.js", payload)?;
-
-    am::setup_pinmode(&mut payload, 26, 2);
-    write_message(dir, "pinmode.js", payload)?;
-
-    am::setup_readpin(&mut payload, 22);
-    write_message(dir, "readpin.js", payload)?;
-
-    am::setup_readpinreply(&mut payload, 26, 1);
-    write_message(dir, "readpinreply.js", payload)?;
-
-    am::setup_writepin(&mut payload, 15, 1);
-    write_message(dir, "writepin.js", payload)?;
-
-    am::setup_readanalog(&mut payload, 27);
-    write_message(dir, "readanalog.js", payload)?;
-
-    am::setup_readanalogreply(&mut payload, 6, 500);
-    write_message(dir, "readanalogreply.js", payload)?;
-
-    am::setup_readmem(&mut payload, 1500, 75);
-    write_message(dir, "readmem.js", payload)?;
-
-    let test = [1, 2, 3, 4, 5];
-    am::setup_readmemreply(&mut payload, &test);
-    write_message(dir, "readmemreply.js", payload)?;
-
-    let test = [5, 4, 3, 2, 1];
-    am::setup_writemem(&mut payload, 5678, &test);
-    write_message(dir, "writemem.js", payload)?;
-
-    am::setup_readinfo(&mut payload);
-    write_message(dir, "readinfo.js", payload)?;
-
-    am::setup_readinforeply(&mut payload, 1.1, 5678, 5000, 5);
-    write_message(dir, "readinforeply.js", payload)?;
-
-    am::setup_readhumidity(&mut payload);
-    write_message(dir, "readhumidity.js", payload)?;
-
-    am::setup_readhumidityreply(&mut payload, 45.7);
-    write_message(dir, "readhumidityreply.js", payload)?;
-
-    am::setup_readtemperature(&mut payload);
-    write_message(dir, "readtemperature.js", payload)?;
-
-    am::setup_readtemperaturereply(&mut payload, 98.6);
-    write_message(dir, "readtemperaturereply.js", payload)?;
-
-    am::setup_readfield(&mut payload, 1);
-    write_message(dir, "readfield.js", payload)?;
-
-    am::setup_readfieldreply(&mut payload, 7, 77, 20, 4, "wat".as_bytes());
-    write_message(dir, "readfieldreply.js", payload)?;
+    let messages = [
+        ("ack", PayloadEnum::ack()),
+        (
+            "fail",
+            PayloadEnum::fail(ResultCode::RcInvalidRhRouterError),
+        ),
+        ("pinmode", PayloadEnum::pinmode(26, 2)),
+        ("readpin", PayloadEnum::readpin(22)),
+        ("readpinreply", PayloadEnum::readpinreply(26, 1)),
+        ("writepin", PayloadEnum::writepin(15, 1)),
+        ("readanalog", PayloadEnum::readanalog(27)),
+        ("readanalogreply", PayloadEnum::readanalogreply(6, 500)),
+        ("readmem", PayloadEnum::readmem(1500, 75)?),
+        ("readmemreply", PayloadEnum::readmemreply(&[1, 2, 3, 4, 5])?),
+        ("writemem", PayloadEnum::writemem(5678, &[5, 4, 3, 2, 1])?),
+        ("readinfo", PayloadEnum::readinfo()),
+        (
+            "readinforeply",
+            PayloadEnum::readinforeply(1.1, 5678, 5000, 5),
+        ),
+        ("readhumidity", PayloadEnum::readhumidity()),
+        ("readhumidityreply", PayloadEnum::readhumidityreply(45.7)),
+        ("readtemperature", PayloadEnum::readtemperature()),
+        (
+            "readtemperaturereply",
+            PayloadEnum::readtemperaturereply(98.6),
+        ),
+        ("readfield", PayloadEnum::readfield(1)),
+        (
+            "readfieldreply",
+            PayloadEnum::readfieldreply(7, 77, 20, FieldFormat::FfUint32, "wat")?,
+        ),
+    ];
+
+    for (name, pe) in messages {
+        write_message(dir, name, Payload::try_from(pe)?)?;
+    }
 
     Ok(())
 }
@@ -192,7 +169,7 @@ unsafe fn read_msg_file_bin(
     //     let c = msgbuf.buf[i];
     //     println!("{} - {}", c, c as char);
     // }
-    am::print_payload(&msgbuf.payload);
+    println!("{:#}", msgbuf.payload);
 
     Ok(())
 }
@@ -219,7 +196,7 @@ unsafe fn read_msg_file_js(
     //     let c = msgbuf.buf[i];
     //     println!("{} - {}", c, c as char);
     // }
-    // am::print_payload(&msgbuf.payload);
+    // println!("{:#}", msgbuf.payload);
 
     Ok(())
 }
@@ -388,8 +365,7 @@ unsafe fn read_message_files(
         || mb.payload.data.readfieldreply.index != 7
         || mb.payload.data.readfieldreply.offset != 77
         || mb.payload.data.readfieldreply.length != 20
-    // TODO: define format codes
-        || mb.payload.data.readfieldreply.format != 4
+        || mb.payload.data.readfieldreply.format != FieldFormat::FfUint32 as u8
     {
         println!("readfieldreply msg failed");
         return Ok(false);